@@ -1,6 +1,7 @@
-use crate::models::attempt::CreateAttemptRequest;
+use crate::error::AppError;
+use crate::models::attempt::{CreateAttemptRequest, SubmitAttemptRequest, SubmittedAttempt};
 use crate::models::Attempt;
-use crate::services::{AttemptService, FsrsService, ProblemService};
+use crate::services::AttemptService;
 use sqlx::SqlitePool;
 use tauri::State;
 
@@ -8,34 +9,20 @@ use tauri::State;
 pub async fn create_attempt(
     db: State<'_, SqlitePool>,
     req: CreateAttemptRequest,
-) -> Result<Attempt, String> {
-    let attempt = AttemptService::create(
-        db.inner(),
-        req.problem_id.clone(),
-        req.is_solved,
-        req.commentary,
-    )
-    .await?;
-
-    let attempt_count =
-        AttemptService::get_count_by_problem(db.inner(), req.problem_id.clone()).await?;
-
-    if attempt_count == 1 {
-        // FIX: Add .clone() here. This ensures the original
-        // req.problem_id is still available for the next block.
-        let _ = FsrsService::get_or_create_card(db.inner(), req.problem_id.clone()).await;
-    }
-
-    if req.is_solved {
-        // Now this works because the previous call didn't "eat" the original string.
-        let _ = ProblemService::mark_solved(db.inner(), req.problem_id).await;
-    }
+) -> Result<Attempt, AppError> {
+    AttemptService::create(db.inner(), req.problem_id, req.is_solved, req.commentary).await
+}
 
-    Ok(attempt)
+#[tauri::command]
+pub async fn submit_attempt(
+    db: State<'_, SqlitePool>,
+    req: SubmitAttemptRequest,
+) -> Result<SubmittedAttempt, AppError> {
+    AttemptService::submit(db.inner(), req).await
 }
 
 #[tauri::command]
-pub async fn get_attempt(db: State<'_, SqlitePool>, id: String) -> Result<Attempt, String> {
+pub async fn get_attempt(db: State<'_, SqlitePool>, id: String) -> Result<Attempt, AppError> {
     AttemptService::get_by_id(db.inner(), id).await
 }
 
@@ -43,7 +30,7 @@ pub async fn get_attempt(db: State<'_, SqlitePool>, id: String) -> Result<Attemp
 pub async fn list_attempts_by_problem(
     db: State<'_, SqlitePool>,
     problem_id: String,
-) -> Result<Vec<Attempt>, String> {
+) -> Result<Vec<Attempt>, AppError> {
     AttemptService::list_by_problem(db.inner(), problem_id).await
 }
 
@@ -52,7 +39,7 @@ pub async fn update_attempt_commentary(
     db: State<'_, SqlitePool>,
     id: String,
     commentary: String,
-) -> Result<Attempt, String> {
+) -> Result<Attempt, AppError> {
     AttemptService::update_commentary(db.inner(), id, commentary).await
 }
 
@@ -60,7 +47,7 @@ pub async fn update_attempt_commentary(
 pub async fn get_problem_attempt_stats(
     db: State<'_, SqlitePool>,
     problem_id: String,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, AppError> {
     let total = AttemptService::get_count_by_problem(db.inner(), problem_id.clone()).await?;
     let successful = AttemptService::get_success_count_by_problem(db.inner(), problem_id).await?;
 
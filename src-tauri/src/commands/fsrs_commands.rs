@@ -1,4 +1,7 @@
-use crate::models::fsrs::{FsrsStats, ReviewRequest, ReviewResult};
+use crate::error::AppError;
+use crate::models::fsrs::{
+    CardRetrievability, ForgettingCurvePoint, FsrsStats, RatingPreview, ReviewRequest, ReviewResult,
+};
 use crate::models::FsrsCard;
 use crate::services::FsrsService;
 use sqlx::SqlitePool;
@@ -8,22 +11,33 @@ use tauri::State;
 pub async fn process_review(
     db: State<'_, SqlitePool>,
     req: ReviewRequest,
-) -> Result<ReviewResult, String> {
+) -> Result<ReviewResult, AppError> {
     FsrsService::process_review(db.inner(), req).await
 }
 
 #[tauri::command]
-pub async fn get_due_cards(db: State<'_, SqlitePool>) -> Result<Vec<FsrsCard>, String> {
+pub async fn preview_review(
+    db: State<'_, SqlitePool>,
+    card_id: String,
+) -> Result<Vec<RatingPreview>, AppError> {
+    FsrsService::preview_review(db.inner(), card_id).await
+}
+
+#[tauri::command]
+pub async fn get_due_cards(db: State<'_, SqlitePool>) -> Result<Vec<FsrsCard>, AppError> {
     FsrsService::get_due_cards(db.inner()).await
 }
 
 #[tauri::command]
-pub async fn get_fsrs_stats(db: State<'_, SqlitePool>) -> Result<FsrsStats, String> {
+pub async fn get_fsrs_stats(db: State<'_, SqlitePool>) -> Result<FsrsStats, AppError> {
     FsrsService::get_stats(db.inner()).await
 }
 
 #[tauri::command]
-pub async fn get_fsrs_card(db: State<'_, SqlitePool>, card_id: String) -> Result<FsrsCard, String> {
+pub async fn get_fsrs_card(
+    db: State<'_, SqlitePool>,
+    card_id: String,
+) -> Result<FsrsCard, AppError> {
     FsrsService::get_card_by_id(db.inner(), card_id).await
 }
 
@@ -31,14 +45,90 @@ pub async fn get_fsrs_card(db: State<'_, SqlitePool>, card_id: String) -> Result
 pub async fn get_fsrs_card_by_problem(
     db: State<'_, SqlitePool>,
     problem_id: String,
-) -> Result<FsrsCard, String> {
-    FsrsService::get_or_create_card(db.inner(), problem_id).await
+) -> Result<FsrsCard, AppError> {
+    let mut conn = db.inner().acquire().await?;
+    FsrsService::get_or_create_card(&mut conn, problem_id).await
 }
 
 #[tauri::command]
 pub async fn get_cards_by_state(
     db: State<'_, SqlitePool>,
     state: String,
-) -> Result<Vec<FsrsCard>, String> {
+) -> Result<Vec<FsrsCard>, AppError> {
     FsrsService::get_cards_by_state(db.inner(), state).await
 }
+
+#[tauri::command]
+pub async fn undo_review(
+    db: State<'_, SqlitePool>,
+    card_id: Option<String>,
+) -> Result<FsrsCard, AppError> {
+    FsrsService::undo_last_review(db.inner(), card_id).await
+}
+
+#[tauri::command]
+pub async fn get_card_retrievability(
+    db: State<'_, SqlitePool>,
+    card_id: String,
+    at: Option<String>,
+) -> Result<CardRetrievability, AppError> {
+    FsrsService::get_retrievability(db.inner(), card_id, at).await
+}
+
+#[tauri::command]
+pub async fn list_card_retrievability(
+    db: State<'_, SqlitePool>,
+    subject_id: Option<String>,
+    at: Option<String>,
+) -> Result<Vec<CardRetrievability>, AppError> {
+    FsrsService::list_retrievability(db.inner(), subject_id, at).await
+}
+
+#[tauri::command]
+pub async fn get_forgetting_curve(
+    db: State<'_, SqlitePool>,
+    card_id: String,
+    days: u32,
+) -> Result<Vec<ForgettingCurvePoint>, AppError> {
+    FsrsService::get_forgetting_curve(db.inner(), card_id, days).await
+}
+
+#[tauri::command]
+pub async fn suspend_card(
+    db: State<'_, SqlitePool>,
+    card_id: String,
+) -> Result<FsrsCard, AppError> {
+    FsrsService::suspend_card(db.inner(), card_id).await
+}
+
+#[tauri::command]
+pub async fn unsuspend_card(
+    db: State<'_, SqlitePool>,
+    card_id: String,
+) -> Result<FsrsCard, AppError> {
+    FsrsService::unsuspend_card(db.inner(), card_id).await
+}
+
+#[tauri::command]
+pub async fn bury_card(db: State<'_, SqlitePool>, card_id: String) -> Result<FsrsCard, AppError> {
+    FsrsService::bury_card(db.inner(), card_id).await
+}
+
+#[tauri::command]
+pub async fn set_card_due(
+    db: State<'_, SqlitePool>,
+    card_id: String,
+    due: String,
+) -> Result<FsrsCard, AppError> {
+    FsrsService::set_card_due(db.inner(), card_id, due).await
+}
+
+#[tauri::command]
+pub async fn reset_card(db: State<'_, SqlitePool>, card_id: String) -> Result<FsrsCard, AppError> {
+    FsrsService::reset_card(db.inner(), card_id).await
+}
+
+#[tauri::command]
+pub async fn forget_card(db: State<'_, SqlitePool>, card_id: String) -> Result<FsrsCard, AppError> {
+    FsrsService::forget_card(db.inner(), card_id).await
+}
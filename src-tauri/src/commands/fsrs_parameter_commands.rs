@@ -0,0 +1,78 @@
+use crate::error::AppError;
+use crate::models::fsrs::{
+    CreateFsrsParameterSetRequest, FsrsParameterSet, OptimizationResult,
+    UpdateFsrsParameterSetRequest,
+};
+use crate::models::reschedule::RescheduleSummary;
+use crate::services::{FsrsOptimizer, FsrsParameterService, RescheduleService};
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_fsrs_parameters(db: State<'_, SqlitePool>) -> Result<FsrsParameterSet, AppError> {
+    FsrsParameterService::get_global(db.inner()).await
+}
+
+#[tauri::command]
+pub async fn get_fsrs_parameters_for_subject(
+    db: State<'_, SqlitePool>,
+    subject_id: String,
+) -> Result<FsrsParameterSet, AppError> {
+    FsrsParameterService::get_for_subject(db.inner(), subject_id).await
+}
+
+#[tauri::command]
+pub async fn list_fsrs_parameter_sets(
+    db: State<'_, SqlitePool>,
+) -> Result<Vec<FsrsParameterSet>, AppError> {
+    FsrsParameterService::list_all(db.inner()).await
+}
+
+#[tauri::command]
+pub async fn create_fsrs_parameter_set(
+    db: State<'_, SqlitePool>,
+    req: CreateFsrsParameterSetRequest,
+) -> Result<FsrsParameterSet, AppError> {
+    FsrsParameterService::create(db.inner(), req).await
+}
+
+#[tauri::command]
+pub async fn update_fsrs_parameter_set(
+    db: State<'_, SqlitePool>,
+    id: String,
+    req: UpdateFsrsParameterSetRequest,
+) -> Result<FsrsParameterSet, AppError> {
+    FsrsParameterService::update(db.inner(), id, req).await
+}
+
+#[tauri::command]
+pub async fn delete_fsrs_parameter_set(
+    db: State<'_, SqlitePool>,
+    id: String,
+) -> Result<(), AppError> {
+    FsrsParameterService::delete(db.inner(), id).await
+}
+
+#[tauri::command]
+pub async fn optimize_fsrs_parameters(
+    db: State<'_, SqlitePool>,
+    subject_id: Option<String>,
+) -> Result<OptimizationResult, AppError> {
+    FsrsOptimizer::optimize(db.inner(), subject_id).await
+}
+
+#[tauri::command]
+pub async fn preview_reschedule(
+    db: State<'_, SqlitePool>,
+    subject_id: Option<String>,
+) -> Result<RescheduleSummary, AppError> {
+    RescheduleService::preview(db.inner(), subject_id).await
+}
+
+#[tauri::command]
+pub async fn reschedule_cards(
+    db: State<'_, SqlitePool>,
+    subject_id: Option<String>,
+) -> Result<RescheduleSummary, AppError> {
+    RescheduleService::apply(db.inner(), subject_id).await
+}
@@ -0,0 +1,29 @@
+use crate::error::AppError;
+use crate::models::leech::Leech;
+use crate::models::problem::UpdateProblemRequest;
+use crate::models::FsrsCard;
+use crate::services::LeechService;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_leeches(
+    db: State<'_, SqlitePool>,
+    subject_id: Option<String>,
+) -> Result<Vec<Leech>, AppError> {
+    LeechService::list(db.inner(), subject_id).await
+}
+
+#[tauri::command]
+pub async fn reset_leech(db: State<'_, SqlitePool>, card_id: String) -> Result<FsrsCard, AppError> {
+    LeechService::reset(db.inner(), card_id).await
+}
+
+#[tauri::command]
+pub async fn rewrite_leech(
+    db: State<'_, SqlitePool>,
+    card_id: String,
+    req: UpdateProblemRequest,
+) -> Result<Leech, AppError> {
+    LeechService::rewrite(db.inner(), card_id, req).await
+}
@@ -1,15 +1,33 @@
 pub mod attempt_commands;
 pub mod error_log_commands;
 pub mod fsrs_commands;
+pub mod fsrs_parameter_commands;
+pub mod leech_commands;
 pub mod problem_commands;
+pub mod review_log_commands;
+pub mod review_queue_commands;
+pub mod settings_commands;
+pub mod study_phase_commands;
+pub mod study_session_commands;
 pub mod subject_commands;
 pub mod theory_commands;
+pub mod time_budget_commands;
 pub mod topic_commands;
+pub mod workload_commands;
 
 pub use attempt_commands::*;
 pub use error_log_commands::*;
 pub use fsrs_commands::*;
+pub use fsrs_parameter_commands::*;
+pub use leech_commands::*;
 pub use problem_commands::*;
+pub use review_log_commands::*;
+pub use review_queue_commands::*;
+pub use settings_commands::*;
+pub use study_phase_commands::*;
+pub use study_session_commands::*;
 pub use subject_commands::*;
 pub use theory_commands::*;
+pub use time_budget_commands::*;
 pub use topic_commands::*;
+pub use workload_commands::*;
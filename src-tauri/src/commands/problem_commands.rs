@@ -1,5 +1,8 @@
-use crate::models::problem::{CreateProblemRequest, Problem, UpdateProblemRequest};
-use crate::services::{FsrsService, ProblemService};
+use crate::error::AppError;
+use crate::models::problem::{
+    CreateProblemRequest, Problem, ProblemMastery, ProblemTag, UpdateProblemRequest,
+};
+use crate::services::ProblemService;
 use serde::Serialize;
 use sqlx::SqlitePool;
 use tauri::State;
@@ -8,8 +11,8 @@ use tauri::State;
 pub async fn create_problem(
     db: State<'_, SqlitePool>,
     req: CreateProblemRequest,
-) -> Result<Problem, String> {
-    let problem = ProblemService::create(
+) -> Result<Problem, AppError> {
+    ProblemService::create(
         db.inner(),
         req.topic_id,
         req.theory_id,
@@ -18,16 +21,11 @@ pub async fn create_problem(
         req.image_url,
         req.difficulty,
     )
-    .await?;
-
-    // Create FSRS card for this problem
-    FsrsService::create_card(db.inner(), problem.id.clone()).await?;
-
-    Ok(problem)
+    .await
 }
 
 #[tauri::command]
-pub async fn get_problem(db: State<'_, SqlitePool>, id: String) -> Result<Problem, String> {
+pub async fn get_problem(db: State<'_, SqlitePool>, id: String) -> Result<Problem, AppError> {
     ProblemService::get_by_id(db.inner(), id).await
 }
 
@@ -35,7 +33,7 @@ pub async fn get_problem(db: State<'_, SqlitePool>, id: String) -> Result<Proble
 pub async fn list_problems_by_topic(
     db: State<'_, SqlitePool>,
     topic_id: String,
-) -> Result<Vec<Problem>, String> {
+) -> Result<Vec<Problem>, AppError> {
     ProblemService::list_by_topic(db.inner(), topic_id).await
 }
 
@@ -43,7 +41,7 @@ pub async fn list_problems_by_topic(
 pub async fn list_problems_by_theory(
     db: State<'_, SqlitePool>,
     theory_id: String,
-) -> Result<Vec<Problem>, String> {
+) -> Result<Vec<Problem>, AppError> {
     ProblemService::list_by_theory(db.inner(), theory_id).await
 }
 
@@ -52,7 +50,7 @@ pub async fn update_problem(
     db: State<'_, SqlitePool>,
     id: String,
     req: UpdateProblemRequest,
-) -> Result<Problem, String> {
+) -> Result<Problem, AppError> {
     ProblemService::update(
         db.inner(),
         id,
@@ -65,15 +63,69 @@ pub async fn update_problem(
 }
 
 #[tauri::command]
-pub async fn delete_problem(db: State<'_, SqlitePool>, id: String) -> Result<(), String> {
+pub async fn delete_problem(db: State<'_, SqlitePool>, id: String) -> Result<(), AppError> {
     ProblemService::delete(db.inner(), id).await
 }
 
 #[tauri::command]
-pub async fn mark_problem_solved(db: State<'_, SqlitePool>, id: String) -> Result<Problem, String> {
+pub async fn mark_problem_solved(
+    db: State<'_, SqlitePool>,
+    id: String,
+) -> Result<Problem, AppError> {
     ProblemService::mark_solved(db.inner(), id).await
 }
 
+#[tauri::command]
+pub async fn search_problems(
+    db: State<'_, SqlitePool>,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<Problem>, AppError> {
+    ProblemService::search(db.inner(), query, limit).await
+}
+
+#[tauri::command]
+pub async fn add_problem_tag(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+    tag_name: String,
+) -> Result<Vec<ProblemTag>, AppError> {
+    ProblemService::add_tag(db.inner(), problem_id, tag_name).await
+}
+
+#[tauri::command]
+pub async fn remove_problem_tag(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+    tag_name: String,
+) -> Result<Vec<ProblemTag>, AppError> {
+    ProblemService::remove_tag(db.inner(), problem_id, tag_name).await
+}
+
+#[tauri::command]
+pub async fn list_problem_tags(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+) -> Result<Vec<ProblemTag>, AppError> {
+    ProblemService::list_tags(db.inner(), problem_id).await
+}
+
+#[tauri::command]
+pub async fn list_problems_by_tag(
+    db: State<'_, SqlitePool>,
+    tag_name: String,
+) -> Result<Vec<Problem>, AppError> {
+    ProblemService::list_by_tag(db.inner(), tag_name).await
+}
+
+#[tauri::command]
+pub async fn get_problem_mastery(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+) -> Result<Option<ProblemMastery>, AppError> {
+    ProblemService::get_mastery(db.inner(), problem_id).await
+}
+
 #[derive(Debug, Serialize)]
 pub struct ProblemWithDetails {
     pub problem: Problem,
@@ -85,7 +137,7 @@ pub struct ProblemWithDetails {
 pub async fn get_problem_with_details(
     db: State<'_, SqlitePool>,
     id: String,
-) -> Result<ProblemWithDetails, String> {
+) -> Result<ProblemWithDetails, AppError> {
     let problem = ProblemService::get_by_id(db.inner(), id.clone()).await?;
 
     // Get attempts count and unresolved errors
@@ -93,8 +145,7 @@ pub async fn get_problem_with_details(
         sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM attempts WHERE problem_id = ?")
             .bind(&id)
             .fetch_one(db.inner())
-            .await
-            .map_err(|e| e.to_string())?
+            .await?
             .0;
 
     let unresolved_errors = ProblemService::get_unresolved_error_count(db.inner(), id).await?;
@@ -0,0 +1,21 @@
+use crate::error::AppError;
+use crate::models::review_log::{ReviewLog, ReviewLogFilter};
+use crate::services::ReviewLogService;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_card_review_history(
+    db: State<'_, SqlitePool>,
+    card_id: String,
+) -> Result<Vec<ReviewLog>, AppError> {
+    ReviewLogService::list_by_card(db.inner(), card_id).await
+}
+
+#[tauri::command]
+pub async fn list_review_logs(
+    db: State<'_, SqlitePool>,
+    filter: Option<ReviewLogFilter>,
+) -> Result<Vec<ReviewLog>, AppError> {
+    ReviewLogService::list(db.inner(), filter.unwrap_or_default()).await
+}
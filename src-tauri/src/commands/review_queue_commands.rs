@@ -0,0 +1,13 @@
+use crate::error::AppError;
+use crate::models::review_queue::{QueueOptions, ReviewQueue};
+use crate::services::ReviewQueueService;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_review_queue(
+    db: State<'_, SqlitePool>,
+    options: Option<QueueOptions>,
+) -> Result<ReviewQueue, AppError> {
+    ReviewQueueService::build(db.inner(), options.unwrap_or_default()).await
+}
@@ -0,0 +1,32 @@
+use crate::error::AppError;
+use crate::models::Setting;
+use crate::services::SettingsService;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_setting(
+    db: State<'_, SqlitePool>,
+    key: String,
+) -> Result<Option<Setting>, AppError> {
+    SettingsService::get(db.inner(), key).await
+}
+
+#[tauri::command]
+pub async fn list_settings(db: State<'_, SqlitePool>) -> Result<Vec<Setting>, AppError> {
+    SettingsService::list_all(db.inner()).await
+}
+
+#[tauri::command]
+pub async fn set_setting(
+    db: State<'_, SqlitePool>,
+    key: String,
+    value: String,
+) -> Result<Setting, AppError> {
+    SettingsService::set(db.inner(), key, value).await
+}
+
+#[tauri::command]
+pub async fn delete_setting(db: State<'_, SqlitePool>, key: String) -> Result<(), AppError> {
+    SettingsService::delete(db.inner(), key).await
+}
@@ -0,0 +1,92 @@
+use crate::error::AppError;
+use crate::models::study_phase::{
+    AdvancePhaseRequest, PhaseAdvanceResult, PhaseTimeSummary, StudyPhaseEntry, StudySessionData,
+    UpdatePhaseTimeRequest,
+};
+use crate::models::theory::TheoryWithPhase;
+use crate::services::StudyPhaseService;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[tauri::command]
+pub async fn start_study_phase(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+) -> Result<StudySessionData, AppError> {
+    StudyPhaseService::start(db.inner(), problem_id).await
+}
+
+#[tauri::command]
+pub async fn get_study_session(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+) -> Result<Option<StudySessionData>, AppError> {
+    StudyPhaseService::get_session(db.inner(), problem_id).await
+}
+
+#[tauri::command]
+pub async fn advance_study_phase(
+    db: State<'_, SqlitePool>,
+    req: AdvancePhaseRequest,
+) -> Result<PhaseAdvanceResult, AppError> {
+    StudyPhaseService::advance(db.inner(), req).await
+}
+
+#[tauri::command]
+pub async fn update_study_phase_time(
+    db: State<'_, SqlitePool>,
+    req: UpdatePhaseTimeRequest,
+) -> Result<StudySessionData, AppError> {
+    StudyPhaseService::update_time(db.inner(), req).await
+}
+
+#[tauri::command]
+pub async fn complete_study_phase(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+    time_spent_seconds: i32,
+    notes: Option<String>,
+) -> Result<PhaseAdvanceResult, AppError> {
+    StudyPhaseService::complete(db.inner(), problem_id, time_spent_seconds, notes).await
+}
+
+#[tauri::command]
+pub async fn reset_study_phase(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+) -> Result<StudySessionData, AppError> {
+    StudyPhaseService::reset(db.inner(), problem_id).await
+}
+
+#[tauri::command]
+pub async fn list_study_phase_entries(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+    phase: Option<i32>,
+) -> Result<Vec<StudyPhaseEntry>, AppError> {
+    StudyPhaseService::list_entries(db.inner(), problem_id, phase).await
+}
+
+#[tauri::command]
+pub async fn get_phase_times_by_problem(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+) -> Result<Vec<PhaseTimeSummary>, AppError> {
+    StudyPhaseService::phase_times_by_problem(db.inner(), problem_id).await
+}
+
+#[tauri::command]
+pub async fn get_phase_times_by_topic(
+    db: State<'_, SqlitePool>,
+    topic_id: String,
+) -> Result<Vec<PhaseTimeSummary>, AppError> {
+    StudyPhaseService::phase_times_by_topic(db.inner(), topic_id).await
+}
+
+#[tauri::command]
+pub async fn get_study_phase_theory(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+) -> Result<Vec<TheoryWithPhase>, AppError> {
+    StudyPhaseService::get_phase_theory(db.inner(), problem_id).await
+}
@@ -0,0 +1,68 @@
+use crate::error::AppError;
+use crate::models::study_session::{StudySession, StudySessionSummary, Timebox};
+use crate::services::StudySessionService;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[tauri::command]
+pub async fn start_study_session(
+    db: State<'_, SqlitePool>,
+    timebox: Option<Timebox>,
+) -> Result<StudySession, AppError> {
+    StudySessionService::start(db.inner(), timebox).await
+}
+
+#[tauri::command]
+pub async fn study_session_heartbeat(
+    db: State<'_, SqlitePool>,
+    id: String,
+) -> Result<StudySession, AppError> {
+    StudySessionService::heartbeat(db.inner(), id).await
+}
+
+#[tauri::command]
+pub async fn pause_study_session(
+    db: State<'_, SqlitePool>,
+    id: String,
+) -> Result<StudySession, AppError> {
+    StudySessionService::pause(db.inner(), id).await
+}
+
+#[tauri::command]
+pub async fn resume_study_session(
+    db: State<'_, SqlitePool>,
+    id: String,
+) -> Result<StudySession, AppError> {
+    StudySessionService::resume(db.inner(), id).await
+}
+
+#[tauri::command]
+pub async fn stop_study_session(
+    db: State<'_, SqlitePool>,
+    id: String,
+) -> Result<StudySessionSummary, AppError> {
+    StudySessionService::stop(db.inner(), id).await
+}
+
+#[tauri::command]
+pub async fn get_current_study_session(
+    db: State<'_, SqlitePool>,
+) -> Result<Option<StudySessionSummary>, AppError> {
+    StudySessionService::get_current(db.inner()).await
+}
+
+#[tauri::command]
+pub async fn get_study_session_summary(
+    db: State<'_, SqlitePool>,
+    id: String,
+) -> Result<StudySessionSummary, AppError> {
+    StudySessionService::get_summary(db.inner(), id).await
+}
+
+#[tauri::command]
+pub async fn list_study_sessions(
+    db: State<'_, SqlitePool>,
+    limit: Option<i64>,
+) -> Result<Vec<StudySessionSummary>, AppError> {
+    StudySessionService::list(db.inner(), limit.unwrap_or(50)).await
+}
@@ -1,3 +1,4 @@
+use crate::error::AppError;
 use crate::models::subject::{CreateSubjectRequest, Subject};
 use crate::services::SubjectService;
 use sqlx::SqlitePool;
@@ -7,17 +8,17 @@ use tauri::State;
 pub async fn create_subject(
     db: State<'_, SqlitePool>,
     req: CreateSubjectRequest,
-) -> Result<Subject, String> {
+) -> Result<Subject, AppError> {
     SubjectService::create(db.inner(), req.name, req.description).await
 }
 
 #[tauri::command]
-pub async fn get_subject(db: State<'_, SqlitePool>, id: String) -> Result<Subject, String> {
+pub async fn get_subject(db: State<'_, SqlitePool>, id: String) -> Result<Subject, AppError> {
     SubjectService::get_by_id(db.inner(), id).await
 }
 
 #[tauri::command]
-pub async fn list_subjects(db: State<'_, SqlitePool>) -> Result<Vec<Subject>, String> {
+pub async fn list_subjects(db: State<'_, SqlitePool>) -> Result<Vec<Subject>, AppError> {
     SubjectService::list_all(db.inner()).await
 }
 
@@ -27,11 +28,11 @@ pub async fn update_subject(
     id: String,
     name: Option<String>,
     description: Option<String>,
-) -> Result<Subject, String> {
+) -> Result<Subject, AppError> {
     SubjectService::update(db.inner(), id, name, description).await
 }
 
 #[tauri::command]
-pub async fn delete_subject(db: State<'_, SqlitePool>, id: String) -> Result<(), String> {
+pub async fn delete_subject(db: State<'_, SqlitePool>, id: String) -> Result<(), AppError> {
     SubjectService::delete(db.inner(), id).await
 }
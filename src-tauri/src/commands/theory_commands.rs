@@ -1,3 +1,4 @@
+use crate::error::AppError;
 use crate::models::Theory;
 use crate::services::TheoryService;
 use sqlx::SqlitePool;
@@ -10,12 +11,12 @@ pub async fn create_theory(
     phase_number: i32,
     title: String,
     content: Option<String>,
-) -> Result<Theory, String> {
+) -> Result<Theory, AppError> {
     TheoryService::create(db.inner(), topic_id, phase_number, title, content).await
 }
 
 #[tauri::command]
-pub async fn get_theory(db: State<'_, SqlitePool>, id: String) -> Result<Theory, String> {
+pub async fn get_theory(db: State<'_, SqlitePool>, id: String) -> Result<Theory, AppError> {
     TheoryService::get_by_id(db.inner(), id).await
 }
 
@@ -23,7 +24,7 @@ pub async fn get_theory(db: State<'_, SqlitePool>, id: String) -> Result<Theory,
 pub async fn list_theories_by_topic(
     db: State<'_, SqlitePool>,
     topic_id: String,
-) -> Result<Vec<Theory>, String> {
+) -> Result<Vec<Theory>, AppError> {
     TheoryService::list_by_topic(db.inner(), topic_id).await
 }
 
@@ -32,7 +33,7 @@ pub async fn get_theory_by_phase(
     db: State<'_, SqlitePool>,
     topic_id: String,
     phase_number: i32,
-) -> Result<Option<Theory>, String> {
+) -> Result<Option<Theory>, AppError> {
     TheoryService::get_by_phase(db.inner(), topic_id, phase_number).await
 }
 
@@ -42,11 +43,11 @@ pub async fn update_theory(
     id: String,
     title: Option<String>,
     content: Option<String>,
-) -> Result<Theory, String> {
+) -> Result<Theory, AppError> {
     TheoryService::update(db.inner(), id, title, content).await
 }
 
 #[tauri::command]
-pub async fn delete_theory(db: State<'_, SqlitePool>, id: String) -> Result<(), String> {
+pub async fn delete_theory(db: State<'_, SqlitePool>, id: String) -> Result<(), AppError> {
     TheoryService::delete(db.inner(), id).await
 }
@@ -0,0 +1,38 @@
+use crate::error::AppError;
+use crate::models::time_budget::{DifficultyTimeBudget, TimeBudget};
+use crate::services::TimeBudgetService;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_difficulty_time_budgets(
+    db: State<'_, SqlitePool>,
+) -> Result<Vec<DifficultyTimeBudget>, AppError> {
+    TimeBudgetService::list_by_difficulty(db.inner()).await
+}
+
+#[tauri::command]
+pub async fn set_difficulty_time_budget(
+    db: State<'_, SqlitePool>,
+    difficulty: i32,
+    budget_seconds: i64,
+) -> Result<DifficultyTimeBudget, AppError> {
+    TimeBudgetService::set_for_difficulty(db.inner(), difficulty, budget_seconds).await
+}
+
+#[tauri::command]
+pub async fn get_problem_time_budget(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+) -> Result<Option<TimeBudget>, AppError> {
+    TimeBudgetService::get_for_problem(db.inner(), problem_id).await
+}
+
+#[tauri::command]
+pub async fn set_problem_time_budget(
+    db: State<'_, SqlitePool>,
+    problem_id: String,
+    budget_seconds: Option<i64>,
+) -> Result<Option<TimeBudget>, AppError> {
+    TimeBudgetService::set_for_problem(db.inner(), problem_id, budget_seconds).await
+}
@@ -1,3 +1,4 @@
+use crate::error::AppError;
 use crate::models::Topic;
 use crate::services::TopicService;
 use sqlx::SqlitePool;
@@ -9,12 +10,12 @@ pub async fn create_topic(
     subject_id: String,
     name: String,
     description: Option<String>,
-) -> Result<Topic, String> {
+) -> Result<Topic, AppError> {
     TopicService::create(db.inner(), subject_id, name, description).await
 }
 
 #[tauri::command]
-pub async fn get_topic(db: State<'_, SqlitePool>, id: String) -> Result<Topic, String> {
+pub async fn get_topic(db: State<'_, SqlitePool>, id: String) -> Result<Topic, AppError> {
     TopicService::get_by_id(db.inner(), id).await
 }
 
@@ -22,7 +23,7 @@ pub async fn get_topic(db: State<'_, SqlitePool>, id: String) -> Result<Topic, S
 pub async fn list_topics_by_subject(
     db: State<'_, SqlitePool>,
     subject_id: String,
-) -> Result<Vec<Topic>, String> {
+) -> Result<Vec<Topic>, AppError> {
     TopicService::list_by_subject(db.inner(), subject_id).await
 }
 
@@ -32,11 +33,11 @@ pub async fn update_topic(
     id: String,
     name: Option<String>,
     description: Option<String>,
-) -> Result<Topic, String> {
+) -> Result<Topic, AppError> {
     TopicService::update(db.inner(), id, name, description).await
 }
 
 #[tauri::command]
-pub async fn delete_topic(db: State<'_, SqlitePool>, id: String) -> Result<(), String> {
+pub async fn delete_topic(db: State<'_, SqlitePool>, id: String) -> Result<(), AppError> {
     TopicService::delete(db.inner(), id).await
 }
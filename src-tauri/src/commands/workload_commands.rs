@@ -0,0 +1,13 @@
+use crate::error::AppError;
+use crate::models::workload::{SimulationRequest, WorkloadForecast};
+use crate::services::WorkloadSimulator;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[tauri::command]
+pub async fn simulate_workload(
+    db: State<'_, SqlitePool>,
+    req: Option<SimulationRequest>,
+) -> Result<WorkloadForecast, AppError> {
+    WorkloadSimulator::simulate(db.inner(), req.unwrap_or_default()).await
+}
@@ -0,0 +1,1088 @@
+use chrono::Utc;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::fmt;
+
+/// One schema change, applied once and recorded in `schema_migrations`.
+/// Never edit a migration that has shipped: its checksum is verified on every start.
+/// Add a new one at the end instead.
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    steps: &'static [Step],
+}
+
+enum Step {
+    Sql(&'static str),
+    /// Idempotent, so databases from builds that predate versioning (whose columns were
+    /// added on the fly) can be brought up to date by replaying every migration
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+use Step::{AddColumn, Sql};
+
+pub static MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        steps: &[
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS subjects (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                description TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#),
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS topics (
+                id TEXT PRIMARY KEY,
+                subject_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                description TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (subject_id) REFERENCES subjects(id) ON DELETE CASCADE,
+                UNIQUE(subject_id, name)
+            )
+            "#),
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS theories (
+                id TEXT PRIMARY KEY,
+                topic_id TEXT NOT NULL,
+                phase_number INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                content TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (topic_id) REFERENCES topics(id) ON DELETE CASCADE
+            )
+            "#),
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS problems (
+                id TEXT PRIMARY KEY,
+                topic_id TEXT NOT NULL,
+                theory_id TEXT,
+                title TEXT NOT NULL,
+                description TEXT,
+                image_url TEXT,
+                difficulty INTEGER DEFAULT 1,
+                is_solved BOOLEAN DEFAULT 0,
+                total_unresolved_errors INTEGER DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (topic_id) REFERENCES topics(id) ON DELETE CASCADE,
+                FOREIGN KEY (theory_id) REFERENCES theories(id) ON DELETE SET NULL
+            )
+            "#),
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS attempts (
+                id TEXT PRIMARY KEY,
+                problem_id TEXT NOT NULL,
+                is_solved BOOLEAN NOT NULL,
+                commentary TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE
+            )
+            "#),
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS error_types (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                description TEXT,
+                multiplier REAL DEFAULT 1.0,
+                created_at TEXT NOT NULL
+            )
+            "#),
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS attempt_errors (
+                id TEXT PRIMARY KEY,
+                attempt_id TEXT NOT NULL,
+                error_type_id INTEGER NOT NULL,
+                description TEXT,
+                is_resolved BOOLEAN DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (attempt_id) REFERENCES attempts(id) ON DELETE CASCADE,
+                FOREIGN KEY (error_type_id) REFERENCES error_types(id) ON DELETE RESTRICT
+            )
+            "#),
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS fsrs_cards (
+                id TEXT PRIMARY KEY,
+                problem_id TEXT NOT NULL UNIQUE,
+                due TEXT NOT NULL,
+                stability REAL DEFAULT 1.0,
+                difficulty REAL DEFAULT 5.0,
+                state TEXT DEFAULT 'new',
+                reps INTEGER DEFAULT 0,
+                lapses INTEGER DEFAULT 0,
+                elapsed_days INTEGER DEFAULT 0,
+                scheduled_days INTEGER DEFAULT 1,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE
+            )
+            "#),
+            Sql("CREATE INDEX IF NOT EXISTS idx_topics_subject ON topics(subject_id)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_theories_topic ON theories(topic_id)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_problems_topic ON problems(topic_id)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_problems_theory ON problems(theory_id)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_attempts_problem ON attempts(problem_id)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_errors_attempt ON attempt_errors(attempt_id)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_fsrs_due ON fsrs_cards(due)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_fsrs_problem ON fsrs_cards(problem_id)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_fsrs_state ON fsrs_cards(state)"),
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "card_last_review",
+        steps: &[AddColumn {
+            table: "fsrs_cards",
+            column: "last_review",
+            definition: "TEXT",
+        }],
+    },
+    Migration {
+        version: 3,
+        name: "review_log",
+        steps: &[
+            // One row per review, never updated
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS fsrs_reviews (
+                id TEXT PRIMARY KEY,
+                card_id TEXT NOT NULL,
+                problem_id TEXT NOT NULL,
+                rating INTEGER NOT NULL, -- 0 for manual actions
+                state_before TEXT NOT NULL,
+                state_after TEXT NOT NULL,
+                stability_before REAL NOT NULL,
+                stability_after REAL NOT NULL,
+                difficulty_before REAL NOT NULL,
+                difficulty_after REAL NOT NULL,
+                elapsed_days INTEGER NOT NULL DEFAULT 0,
+                scheduled_days INTEGER NOT NULL DEFAULT 0,
+                duration_seconds INTEGER NOT NULL DEFAULT 0,
+                reviewed_at TEXT NOT NULL,
+                FOREIGN KEY (card_id) REFERENCES fsrs_cards(id) ON DELETE CASCADE,
+                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE
+            )
+            "#),
+            Sql("CREATE INDEX IF NOT EXISTS idx_reviews_card ON fsrs_reviews(card_id)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_reviews_reviewed_at ON fsrs_reviews(reviewed_at)"),
+        ],
+    },
+    Migration {
+        version: 4,
+        name: "fsrs_parameters",
+        steps: &[
+            // Weights stored as a JSON array of 19 floats
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS fsrs_parameters (
+                id TEXT PRIMARY KEY,
+                weights TEXT NOT NULL,
+                desired_retention REAL NOT NULL DEFAULT 0.9,
+                maximum_interval INTEGER NOT NULL DEFAULT 36500,
+                total_reviews INTEGER NOT NULL DEFAULT 0,
+                log_loss REAL,
+                rmse REAL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#),
+        ],
+    },
+    Migration {
+        version: 5,
+        name: "parameter_sets",
+        steps: &[
+            AddColumn {
+                table: "fsrs_parameters",
+                column: "name",
+                definition: "TEXT NOT NULL DEFAULT 'Default'",
+            },
+            AddColumn {
+                table: "fsrs_parameters",
+                column: "subject_id",
+                definition: "TEXT REFERENCES subjects(id) ON DELETE CASCADE",
+            },
+            // At most one override per subject (NULL = global set, not constrained)
+            Sql("CREATE UNIQUE INDEX IF NOT EXISTS idx_fsrs_parameters_subject ON fsrs_parameters(subject_id)"),
+        ],
+    },
+    Migration {
+        version: 6,
+        name: "review_undo",
+        steps: &[
+            // JSON snapshot of the card, used to undo the review
+            AddColumn {
+                table: "fsrs_reviews",
+                column: "card_before",
+                definition: "TEXT",
+            },
+        ],
+    },
+    Migration {
+        version: 7,
+        name: "learning_steps",
+        steps: &[
+            // Index into the (re)learning steps
+            AddColumn {
+                table: "fsrs_cards",
+                column: "step",
+                definition: "INTEGER NOT NULL DEFAULT 0",
+            },
+            // Minutes, JSON arrays
+            AddColumn {
+                table: "fsrs_parameters",
+                column: "learning_steps",
+                definition: "TEXT NOT NULL DEFAULT '[1,10]'",
+            },
+            AddColumn {
+                table: "fsrs_parameters",
+                column: "relearning_steps",
+                definition: "TEXT NOT NULL DEFAULT '[10]'",
+            },
+        ],
+    },
+    Migration {
+        version: 8,
+        name: "fuzz_and_load_balance",
+        steps: &[
+            AddColumn {
+                table: "fsrs_parameters",
+                column: "enable_fuzz",
+                definition: "BOOLEAN NOT NULL DEFAULT 1",
+            },
+            AddColumn {
+                table: "fsrs_parameters",
+                column: "enable_load_balance",
+                definition: "BOOLEAN NOT NULL DEFAULT 0",
+            },
+        ],
+    },
+    Migration {
+        version: 9,
+        name: "error_policy",
+        steps: &[AddColumn {
+            table: "fsrs_parameters",
+            column: "error_policy",
+            definition: "TEXT NOT NULL DEFAULT 'scale_stability'",
+        }],
+    },
+    Migration {
+        version: 10,
+        name: "leeches",
+        steps: &[
+            AddColumn {
+                table: "fsrs_cards",
+                column: "is_leech",
+                definition: "BOOLEAN NOT NULL DEFAULT 0",
+            },
+            AddColumn {
+                table: "fsrs_cards",
+                column: "is_suspended",
+                definition: "BOOLEAN NOT NULL DEFAULT 0",
+            },
+            AddColumn {
+                table: "fsrs_parameters",
+                column: "leech_threshold",
+                definition: "INTEGER NOT NULL DEFAULT 8",
+            },
+            AddColumn {
+                table: "fsrs_parameters",
+                column: "leech_action",
+                definition: "TEXT NOT NULL DEFAULT 'tag'",
+            },
+        ],
+    },
+    Migration {
+        version: 11,
+        name: "card_actions",
+        steps: &[
+            AddColumn {
+                table: "fsrs_cards",
+                column: "buried_until",
+                definition: "TEXT",
+            },
+            // review, or the manual action taken
+            AddColumn {
+                table: "fsrs_reviews",
+                column: "kind",
+                definition: "TEXT NOT NULL DEFAULT 'review'",
+            },
+        ],
+    },
+    // Tables first sketched in the old standalone `Database` type; its fsrs_cards,
+    // fsrs_parameters and fsrs_reviews were superseded by the ones above
+    Migration {
+        version: 12,
+        name: "tags_mastery_progress_settings_search",
+        steps: &[
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS problem_tags (
+                id TEXT PRIMARY KEY,
+                problem_id TEXT NOT NULL,
+                tag_name TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE,
+                UNIQUE(problem_id, tag_name)
+            )
+            "#),
+            Sql("CREATE INDEX IF NOT EXISTS idx_problem_tags_name ON problem_tags(tag_name)"),
+            // Derived from attempts by the triggers below, never written directly
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS problem_mastery (
+                id TEXT PRIMARY KEY,
+                problem_id TEXT NOT NULL UNIQUE,
+                solved BOOLEAN NOT NULL DEFAULT 0,
+                mastery_percent REAL NOT NULL DEFAULT 0, -- share of attempts solved
+                attempt_count INTEGER NOT NULL DEFAULT 0,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE
+            )
+            "#),
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS study_phase_progress (
+                id TEXT PRIMARY KEY,
+                problem_id TEXT NOT NULL UNIQUE,
+                current_phase INTEGER NOT NULL DEFAULT 1, -- 1-4
+                current_step INTEGER NOT NULL DEFAULT 1,
+                phase_time_total INTEGER NOT NULL DEFAULT 0, -- seconds
+                completed_at TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE
+            )
+            "#),
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#),
+            Sql(r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS problems_fts USING fts5(
+                id UNINDEXED,
+                title,
+                description,
+                tags
+            )
+            "#),
+            // Keep the search index in step with problems and their tags
+            Sql(r#"
+            CREATE TRIGGER IF NOT EXISTS problems_fts_ai AFTER INSERT ON problems BEGIN
+                INSERT INTO problems_fts (id, title, description, tags)
+                VALUES (new.id, new.title, new.description, '');
+            END
+            "#),
+            Sql(r#"
+            CREATE TRIGGER IF NOT EXISTS problems_fts_au AFTER UPDATE OF title, description ON problems BEGIN
+                UPDATE problems_fts SET title = new.title, description = new.description
+                WHERE id = new.id;
+            END
+            "#),
+            Sql(r#"
+            CREATE TRIGGER IF NOT EXISTS problems_fts_ad AFTER DELETE ON problems BEGIN
+                DELETE FROM problems_fts WHERE id = old.id;
+            END
+            "#),
+            Sql(r#"
+            CREATE TRIGGER IF NOT EXISTS problem_tags_fts_ai AFTER INSERT ON problem_tags BEGIN
+                UPDATE problems_fts
+                SET tags = (SELECT group_concat(tag_name, ' ') FROM problem_tags WHERE problem_id = new.problem_id)
+                WHERE id = new.problem_id;
+            END
+            "#),
+            Sql(r#"
+            CREATE TRIGGER IF NOT EXISTS problem_tags_fts_ad AFTER DELETE ON problem_tags BEGIN
+                UPDATE problems_fts
+                SET tags = COALESCE((SELECT group_concat(tag_name, ' ') FROM problem_tags WHERE problem_id = old.problem_id), '')
+                WHERE id = old.problem_id;
+            END
+            "#),
+            Sql(r#"
+            INSERT INTO problems_fts (id, title, description, tags)
+            SELECT id, title, description, '' FROM problems
+            WHERE id NOT IN (SELECT id FROM problems_fts)
+            "#),
+            // Mastery is recomputed from a problem's attempts whenever they change
+            Sql(r#"
+            CREATE TRIGGER IF NOT EXISTS attempts_mastery_ai AFTER INSERT ON attempts BEGIN
+                INSERT INTO problem_mastery (id, problem_id, solved, mastery_percent, attempt_count, updated_at)
+                SELECT new.problem_id, new.problem_id, MAX(is_solved), 100.0 * SUM(is_solved) / COUNT(*), COUNT(*), new.updated_at
+                FROM attempts WHERE problem_id = new.problem_id
+                ON CONFLICT(problem_id) DO UPDATE SET
+                    solved = excluded.solved,
+                    mastery_percent = excluded.mastery_percent,
+                    attempt_count = excluded.attempt_count,
+                    updated_at = excluded.updated_at;
+            END
+            "#),
+            Sql(r#"
+            CREATE TRIGGER IF NOT EXISTS attempts_mastery_au AFTER UPDATE OF is_solved ON attempts BEGIN
+                UPDATE problem_mastery
+                SET solved = (SELECT MAX(is_solved) FROM attempts WHERE problem_id = new.problem_id),
+                    mastery_percent = (SELECT 100.0 * SUM(is_solved) / COUNT(*) FROM attempts WHERE problem_id = new.problem_id),
+                    updated_at = new.updated_at
+                WHERE problem_id = new.problem_id;
+            END
+            "#),
+            Sql(r#"
+            CREATE TRIGGER IF NOT EXISTS attempts_mastery_ad AFTER DELETE ON attempts BEGIN
+                DELETE FROM problem_mastery
+                WHERE problem_id = old.problem_id
+                  AND NOT EXISTS (SELECT 1 FROM attempts WHERE problem_id = old.problem_id);
+                UPDATE problem_mastery
+                SET solved = (SELECT MAX(is_solved) FROM attempts WHERE problem_id = old.problem_id),
+                    mastery_percent = (SELECT 100.0 * SUM(is_solved) / COUNT(*) FROM attempts WHERE problem_id = old.problem_id),
+                    attempt_count = (SELECT COUNT(*) FROM attempts WHERE problem_id = old.problem_id)
+                WHERE problem_id = old.problem_id;
+            END
+            "#),
+            Sql(r#"
+            INSERT OR IGNORE INTO problem_mastery (id, problem_id, solved, mastery_percent, attempt_count, updated_at)
+            SELECT problem_id, problem_id, MAX(is_solved), 100.0 * SUM(is_solved) / COUNT(*), COUNT(*), MAX(updated_at)
+            FROM attempts GROUP BY problem_id
+            "#),
+        ],
+    },
+    // Builds before foreign keys were enforced left children behind when a parent was
+    // deleted. Parents are cleaned before their children, so whole orphaned subtrees go.
+    Migration {
+        version: 13,
+        name: "repair_orphans",
+        steps: &[
+            Sql("DELETE FROM topics WHERE subject_id NOT IN (SELECT id FROM subjects)"),
+            Sql("DELETE FROM theories WHERE topic_id NOT IN (SELECT id FROM topics)"),
+            Sql("DELETE FROM problems WHERE topic_id NOT IN (SELECT id FROM topics)"),
+            Sql("UPDATE problems SET theory_id = NULL WHERE theory_id NOT IN (SELECT id FROM theories)"),
+            Sql("DELETE FROM attempts WHERE problem_id NOT IN (SELECT id FROM problems)"),
+            Sql("DELETE FROM attempt_errors WHERE attempt_id NOT IN (SELECT id FROM attempts)"),
+            Sql("DELETE FROM fsrs_cards WHERE problem_id NOT IN (SELECT id FROM problems)"),
+            Sql("DELETE FROM fsrs_reviews WHERE card_id NOT IN (SELECT id FROM fsrs_cards) OR problem_id NOT IN (SELECT id FROM problems)"),
+            Sql("DELETE FROM fsrs_parameters WHERE subject_id NOT IN (SELECT id FROM subjects)"),
+            Sql("DELETE FROM problem_tags WHERE problem_id NOT IN (SELECT id FROM problems)"),
+            Sql("DELETE FROM problem_mastery WHERE problem_id NOT IN (SELECT id FROM problems)"),
+            Sql("DELETE FROM study_phase_progress WHERE problem_id NOT IN (SELECT id FROM problems)"),
+            Sql("DELETE FROM problems_fts WHERE id NOT IN (SELECT id FROM problems)"),
+        ],
+    },
+    // Running timer of the current phase, checkpointed so a restart doesn't lose it
+    Migration {
+        version: 14,
+        name: "study_phase_elapsed",
+        steps: &[AddColumn {
+            table: "study_phase_progress",
+            column: "phase_elapsed",
+            definition: "INTEGER NOT NULL DEFAULT 0",
+        }],
+    },
+    // One row per finished phase pass; kept across resets as the study history
+    Migration {
+        version: 15,
+        name: "study_phase_entries",
+        steps: &[
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS study_phase_entries (
+                id TEXT PRIMARY KEY,
+                problem_id TEXT NOT NULL,
+                phase INTEGER NOT NULL, -- 1-4
+                time_spent_seconds INTEGER NOT NULL DEFAULT 0,
+                notes TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE
+            )
+            "#),
+            Sql("CREATE INDEX IF NOT EXISTS idx_study_phase_entries_problem ON study_phase_entries(problem_id, phase)"),
+        ],
+    },
+    // Timed sittings; attempts and reviews made while one is open point at it
+    Migration {
+        version: 16,
+        name: "study_sessions",
+        steps: &[
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS study_sessions (
+                id TEXT PRIMARY KEY,
+                status TEXT NOT NULL DEFAULT 'running', -- running | paused | stopped
+                started_at TEXT NOT NULL,
+                ended_at TEXT,
+                last_heartbeat_at TEXT NOT NULL,
+                active_seconds INTEGER NOT NULL DEFAULT 0,
+                idle_seconds INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#),
+            // At most one session open at a time
+            Sql("CREATE UNIQUE INDEX IF NOT EXISTS idx_study_sessions_open ON study_sessions((ended_at IS NULL)) WHERE ended_at IS NULL"),
+            AddColumn {
+                table: "attempts",
+                column: "session_id",
+                definition: "TEXT REFERENCES study_sessions(id) ON DELETE SET NULL",
+            },
+            AddColumn {
+                table: "fsrs_reviews",
+                column: "session_id",
+                definition: "TEXT REFERENCES study_sessions(id) ON DELETE SET NULL",
+            },
+            Sql("CREATE INDEX IF NOT EXISTS idx_attempts_session ON attempts(session_id)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_fsrs_reviews_session ON fsrs_reviews(session_id)"),
+        ],
+    },
+    // Time budgets (problem override, else per difficulty) and timeboxed sessions
+    Migration {
+        version: 17,
+        name: "time_budgets",
+        steps: &[
+            Sql(r#"
+            CREATE TABLE IF NOT EXISTS difficulty_time_budgets (
+                difficulty INTEGER PRIMARY KEY, -- 1-5
+                budget_seconds INTEGER NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#),
+            Sql(r#"
+            INSERT OR IGNORE INTO difficulty_time_budgets (difficulty, budget_seconds, updated_at)
+            VALUES (1, 600, strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+                   (2, 900, strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+                   (3, 1200, strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+                   (4, 1800, strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+                   (5, 2700, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+            "#),
+            // NULL = use the difficulty's budget
+            AddColumn {
+                table: "problems",
+                column: "time_budget_seconds",
+                definition: "INTEGER",
+            },
+            AddColumn {
+                table: "attempts",
+                column: "time_spent_seconds",
+                definition: "INTEGER NOT NULL DEFAULT 0",
+            },
+            // Both NULL = free session
+            AddColumn {
+                table: "study_sessions",
+                column: "work_interval_seconds",
+                definition: "INTEGER",
+            },
+            AddColumn {
+                table: "study_sessions",
+                column: "break_interval_seconds",
+                definition: "INTEGER",
+            },
+            AddColumn {
+                table: "study_sessions",
+                column: "break_seconds",
+                definition: "INTEGER NOT NULL DEFAULT 0",
+            },
+        ],
+    },
+    // One theory per topic and phase. Duplicates merge into the oldest theory: the
+    // others' titles and content are appended to its content and their problems move
+    // over to it before the extras go.
+    Migration {
+        version: 18,
+        name: "unique_theory_phase",
+        steps: &[
+            Sql(r#"
+            UPDATE theories SET content = (
+                SELECT group_concat(part, char(10) || char(10)) FROM (
+                    SELECT CASE WHEN t.rowid = theories.rowid THEN t.content
+                                ELSE '## ' || t.title || COALESCE(char(10) || char(10) || t.content, '')
+                           END AS part
+                    FROM theories t
+                    WHERE t.topic_id = theories.topic_id AND t.phase_number = theories.phase_number
+                    ORDER BY t.rowid
+                )
+            )
+            WHERE NOT EXISTS (
+                SELECT 1 FROM theories k
+                WHERE k.topic_id = theories.topic_id
+                  AND k.phase_number = theories.phase_number
+                  AND k.rowid < theories.rowid
+            ) AND EXISTS (
+                SELECT 1 FROM theories k
+                WHERE k.topic_id = theories.topic_id
+                  AND k.phase_number = theories.phase_number
+                  AND k.rowid > theories.rowid
+            )
+            "#),
+            Sql(r#"
+            UPDATE problems SET theory_id = (
+                SELECT k.id FROM theories k JOIN theories t
+                    ON k.topic_id = t.topic_id AND k.phase_number = t.phase_number
+                WHERE t.id = problems.theory_id
+                ORDER BY k.rowid LIMIT 1
+            )
+            WHERE theory_id IN (SELECT id FROM theories)
+            "#),
+            Sql(r#"
+            DELETE FROM theories WHERE EXISTS (
+                SELECT 1 FROM theories k
+                WHERE k.topic_id = theories.topic_id
+                  AND k.phase_number = theories.phase_number
+                  AND k.rowid < theories.rowid
+            )
+            "#),
+            Sql("CREATE UNIQUE INDEX IF NOT EXISTS idx_theories_topic_phase ON theories(topic_id, phase_number)"),
+        ],
+    },
+    // The divisor the error policy applied to a review's stability, so replaying the
+    // log reproduces it. Reviews logged before error penalties existed had none.
+    Migration {
+        version: 19,
+        name: "review_stability_penalty",
+        steps: &[AddColumn {
+            table: "fsrs_reviews",
+            column: "stability_penalty",
+            definition: "REAL NOT NULL DEFAULT 1.0",
+        }],
+    },
+];
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Database(sqlx::Error),
+    /// The database was written by a newer build; opening it could lose data
+    NewerSchema {
+        found: i64,
+        supported: i64,
+    },
+    /// An applied migration no longer matches the one shipped with this build
+    ChecksumMismatch {
+        version: i64,
+        name: String,
+    },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Database(e) => write!(f, "Migration failed: {}", e),
+            MigrationError::NewerSchema { found, supported } => write!(
+                f,
+                "Database schema version {} is newer than this app supports ({}); please update the app",
+                found, supported
+            ),
+            MigrationError::ChecksumMismatch { version, name } => write!(
+                f,
+                "Migration {} ({}) differs from the one applied to this database",
+                version, name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<sqlx::Error> for MigrationError {
+    fn from(e: sqlx::Error) -> Self {
+        MigrationError::Database(e)
+    }
+}
+
+impl Migration {
+    /// FNV-1a over the migration's name and statements, as 16 hex digits
+    pub fn checksum(&self) -> String {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |text: &str| {
+            for byte in text.bytes().chain([0]) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+
+        feed(self.name);
+        for step in self.steps {
+            match step {
+                Sql(sql) => feed(sql),
+                AddColumn {
+                    table,
+                    column,
+                    definition,
+                } => {
+                    feed(table);
+                    feed(column);
+                    feed(definition);
+                }
+            }
+        }
+
+        format!("{:016x}", hash)
+    }
+}
+
+/// Version the schema is at once every migration has run
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+pub async fn run_migrations(pool: &SqlitePool) -> Result<(), MigrationError> {
+    migrate_to(pool, latest_version()).await
+}
+
+/// Version recorded in `schema_migrations`; 0 for an empty or unversioned database
+pub async fn schema_version(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    ensure_version_table(pool).await?;
+    let (version,): (Option<i64>,) = sqlx::query_as("SELECT MAX(version) FROM schema_migrations")
+        .fetch_one(pool)
+        .await?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Apply the migrations after the recorded version, up to `target`, each in its own
+/// transaction. Refuses a database that is newer than this build or whose applied
+/// migrations were changed since.
+async fn migrate_to(pool: &SqlitePool, target: i64) -> Result<(), MigrationError> {
+    let current = schema_version(pool).await?;
+    if current > latest_version() {
+        return Err(MigrationError::NewerSchema {
+            found: current,
+            supported: latest_version(),
+        });
+    }
+
+    let applied: Vec<(i64, String)> =
+        sqlx::query_as("SELECT version, checksum FROM schema_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await?;
+    for (version, checksum) in applied {
+        let migration = MIGRATIONS.iter().find(|m| m.version == version);
+        if migration.map(|m| m.checksum()) != Some(checksum) {
+            return Err(MigrationError::ChecksumMismatch {
+                version,
+                name: migration.map_or_else(|| "unknown".to_string(), |m| m.name.to_string()),
+            });
+        }
+    }
+
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|m| m.version > current && m.version <= target)
+    {
+        let mut tx = pool.begin().await?;
+
+        for step in migration.steps {
+            match step {
+                Sql(sql) => {
+                    sqlx::query(sql).execute(&mut *tx).await?;
+                }
+                AddColumn {
+                    table,
+                    column,
+                    definition,
+                } => add_column_if_missing(&mut tx, table, column, definition).await?,
+            }
+        }
+
+        sqlx::query(
+            "INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(migration.version)
+        .bind(migration.name)
+        .bind(migration.checksum())
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+async fn ensure_version_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// `CREATE TABLE IF NOT EXISTS` never alters an existing table, so new columns
+/// have to be added explicitly on databases created by older builds.
+async fn add_column_if_missing(
+    conn: &mut SqliteConnection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), sqlx::Error> {
+    let existing: Option<(String,)> =
+        sqlx::query_as("SELECT name FROM pragma_table_info(?) WHERE name = ?")
+            .bind(table)
+            .bind(column)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+    if existing.is_none() {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+
+    /// Columns of every table as (table, column, type, not null, default), sorted
+    async fn schema(pool: &SqlitePool) -> Vec<(String, String, String, bool, Option<String>)> {
+        sqlx::query_as(
+            "SELECT m.name, c.name, c.type, c.\"notnull\", c.dflt_value
+             FROM sqlite_master m, pragma_table_info(m.name) c
+             WHERE m.type = 'table' AND m.name != 'schema_migrations'
+             ORDER BY m.name, c.name",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    /// Rows every schema version since the first can hold
+    async fn insert_fixture(pool: &SqlitePool) {
+        let now = "2024-01-01T00:00:00+00:00";
+        for sql in [
+            "INSERT INTO subjects (id, name, created_at, updated_at) VALUES ('s1', 'Calculus', ?1, ?1)",
+            "INSERT INTO topics (id, subject_id, name, created_at, updated_at) VALUES ('t1', 's1', 'Limits', ?1, ?1)",
+            "INSERT INTO problems (id, topic_id, title, created_at, updated_at) VALUES ('p1', 't1', 'Squeeze', ?1, ?1)",
+            "INSERT INTO fsrs_cards (id, problem_id, due, stability, state, created_at, updated_at) VALUES ('c1', 'p1', ?1, 12.5, 'review', ?1, ?1)",
+        ] {
+            sqlx::query(sql).bind(now).execute(pool).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_versions_are_sequential_and_checksums_distinct() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as i64 + 1);
+        }
+        let mut checksums: Vec<String> = MIGRATIONS.iter().map(|m| m.checksum()).collect();
+        checksums.sort();
+        checksums.dedup();
+        assert_eq!(checksums.len(), MIGRATIONS.len());
+    }
+
+    #[tokio::test]
+    async fn test_migrates_fixture_from_every_version() {
+        let fresh = memory_pool().await;
+        run_migrations(&fresh).await.unwrap();
+        let expected = schema(&fresh).await;
+
+        for version in 0..=latest_version() {
+            let pool = memory_pool().await;
+            migrate_to(&pool, version).await.unwrap();
+            assert_eq!(schema_version(&pool).await.unwrap(), version);
+            if version > 0 {
+                insert_fixture(&pool).await;
+            }
+
+            run_migrations(&pool).await.unwrap();
+            assert_eq!(schema_version(&pool).await.unwrap(), latest_version());
+            assert_eq!(schema(&pool).await, expected, "from version {}", version);
+
+            if version > 0 {
+                let (stability, step, suspended): (f64, i32, bool) = sqlx::query_as(
+                    "SELECT stability, step, is_suspended FROM fsrs_cards WHERE id = 'c1'",
+                )
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+                assert_eq!((stability, step, suspended), (12.5, 0, false));
+
+                let (matches,): (i64,) = sqlx::query_as(
+                    "SELECT COUNT(*) FROM problems_fts WHERE problems_fts MATCH 'squeeze'",
+                )
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+                assert_eq!(matches, 1);
+            }
+
+            // Running again is a no-op
+            run_migrations(&pool).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repairs_orphans_left_without_foreign_keys() {
+        let pool = memory_pool().await;
+        migrate_to(&pool, 12).await.unwrap();
+        insert_fixture(&pool).await;
+
+        // What deleting a subject did before foreign keys were enforced
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO attempts (id, problem_id, is_solved, created_at, updated_at) VALUES ('a1', 'p1', 1, '', '')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("DELETE FROM subjects")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let (violations,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM pragma_foreign_key_check")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(violations > 0);
+
+        run_migrations(&pool).await.unwrap();
+
+        for table in [
+            "topics",
+            "problems",
+            "attempts",
+            "fsrs_cards",
+            "problem_mastery",
+            "problems_fts",
+        ] {
+            let (count,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {}", table))
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            assert_eq!(count, 0, "{}", table);
+        }
+        let (violations,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM pragma_foreign_key_check")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(violations, 0);
+    }
+
+    #[tokio::test]
+    async fn test_merges_duplicate_theories() {
+        let pool = memory_pool().await;
+        migrate_to(&pool, 17).await.unwrap();
+        insert_fixture(&pool).await;
+        for sql in [
+            "INSERT INTO theories (id, topic_id, phase_number, title, content, created_at, updated_at) VALUES ('th1', 't1', 1, 'First', 'Squeeze it.', '', '')",
+            "INSERT INTO theories (id, topic_id, phase_number, title, content, created_at, updated_at) VALUES ('th2', 't1', 1, 'Again', 'Bound it.', '', '')",
+            "INSERT INTO theories (id, topic_id, phase_number, title, created_at, updated_at) VALUES ('th2b', 't1', 1, 'Notes', '', '')",
+            "INSERT INTO theories (id, topic_id, phase_number, title, content, created_at, updated_at) VALUES ('th3', 't1', 2, 'Other', 'Untouched.', '', '')",
+            "UPDATE problems SET theory_id = 'th2' WHERE id = 'p1'",
+        ] {
+            sqlx::query(sql).execute(&pool).await.unwrap();
+        }
+
+        run_migrations(&pool).await.unwrap();
+
+        // The kept theory carries the others' titles and content, in the order they were added
+        let theories: Vec<(String, String, Option<String>)> =
+            sqlx::query_as("SELECT id, title, content FROM theories ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            theories,
+            vec![
+                (
+                    "th1".to_string(),
+                    "First".to_string(),
+                    Some("Squeeze it.\n\n## Again\n\nBound it.\n\n## Notes".to_string())
+                ),
+                (
+                    "th3".to_string(),
+                    "Other".to_string(),
+                    Some("Untouched.".to_string())
+                ),
+            ]
+        );
+        let (theory_id,): (Option<String>,) =
+            sqlx::query_as("SELECT theory_id FROM problems WHERE id = 'p1'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(theory_id.as_deref(), Some("th1"));
+
+        let duplicate = sqlx::query(
+            "INSERT INTO theories (id, topic_id, phase_number, title, created_at, updated_at) VALUES ('th4', 't1', 2, 'Dup', '', '')",
+        )
+        .execute(&pool)
+        .await;
+        assert!(duplicate.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_adopts_unversioned_database() {
+        // A build from before versioning: tables and some later columns, no version table
+        let pool = memory_pool().await;
+        migrate_to(&pool, 7).await.unwrap();
+        insert_fixture(&pool).await;
+        sqlx::query("DROP TABLE schema_migrations")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        run_migrations(&pool).await.unwrap();
+        assert_eq!(schema_version(&pool).await.unwrap(), latest_version());
+
+        let fresh = memory_pool().await;
+        run_migrations(&fresh).await.unwrap();
+        assert_eq!(schema(&pool).await, schema(&fresh).await);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_newer_or_altered_schema() {
+        let pool = memory_pool().await;
+        run_migrations(&pool).await.unwrap();
+
+        sqlx::query("UPDATE schema_migrations SET checksum = 'edited' WHERE version = 3")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(matches!(
+            run_migrations(&pool).await,
+            Err(MigrationError::ChecksumMismatch { version: 3, .. })
+        ));
+
+        sqlx::query("UPDATE schema_migrations SET checksum = ? WHERE version = 3")
+            .bind(MIGRATIONS[2].checksum())
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (?, 'future', '', '')",
+        )
+        .bind(latest_version() + 1)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let err = run_migrations(&pool).await.unwrap_err();
+        assert!(matches!(err, MigrationError::NewerSchema { .. }));
+        assert!(err.to_string().contains("newer than this app supports"));
+    }
+}
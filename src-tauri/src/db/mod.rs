@@ -1,250 +1,225 @@
-use sqlx::sqlite::SqlitePool;
-use std::path::PathBuf;
+pub mod migrations;
 
-#[derive(Clone)]
-pub struct Database {
-    pool: SqlitePool,
-}
-
-impl Database {
-    pub async fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let db_path = Self::get_db_path();
+pub use migrations::MigrationError;
 
-        // Create directory if needed
-        std::fs::create_dir_all(db_path.parent().unwrap())?;
+use crate::error::AppError;
+use crate::models::integrity::{ForeignKeyViolation, IntegrityReport};
 
-        // Create database URL
-        let database_url = format!("sqlite://{}?mode=rwc", db_path.display());
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous,
+};
+use sqlx::SqliteConnection;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-        // Create pool
-        let pool = SqlitePool::connect(&database_url).await?;
+const DATABASE_FILE: &str = "liquidlearn.db";
+const MAX_CONNECTIONS: u32 = 5;
 
-        // Initialize schema
-        Self::init_schema(&pool).await?;
+/// Where the database lives: the platform's app data directory, or the working
+/// directory when there is none
+pub fn database_path() -> PathBuf {
+    tauri::api::path::app_data_dir(&tauri::Config::default())
+        .unwrap_or_else(|| PathBuf::from("./"))
+        .join(DATABASE_FILE)
+}
 
-        Ok(Database { pool })
-    }
+/// Settings every pooled connection is opened with
+pub fn connect_options(path: &Path) -> SqliteConnectOptions {
+    SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .foreign_keys(true)
+        // WAL lets the UI keep reading while a review or reschedule is being written
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(Duration::from_secs(5))
+}
 
-    fn get_db_path() -> PathBuf {
-        let app_data = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
-        PathBuf::from(app_data)
-            .join("LiquidLearn")
-            .join("liquidlearn.db")
+/// Open the database at `path` (creating it and its directory if needed) and bring
+/// its schema up to date
+pub async fn open(path: &Path) -> Result<SqlitePool, MigrationError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).ok();
     }
 
-    async fn init_schema(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-        // Create problems table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS problems (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
-                description TEXT,
-                difficulty INTEGER CHECK(difficulty >= 1 AND difficulty <= 5),
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(pool)
+    // Migrate over a connection of its own. One that cached the schema halfway through
+    // fails to prepare deletes cascading into the search index ("no such table"), so
+    // none of the pooled connections may predate the migration.
+    let migrator = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(connect_options(path))
         .await?;
+    migrations::run_migrations(&migrator).await?;
+    migrator.close().await;
 
-        // Create indexes
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_problems_difficulty ON problems(difficulty)")
-            .execute(pool)
-            .await?;
-
-        // Create problem_tags table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS problem_tags (
-                id TEXT PRIMARY KEY,
-                problem_id TEXT NOT NULL,
-                tag_name TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE,
-                UNIQUE(problem_id, tag_name)
-            )
-            "#,
-        )
-        .execute(pool)
+    let pool = SqlitePoolOptions::new()
+        .max_connections(MAX_CONNECTIONS)
+        .connect_with(connect_options(path))
         .await?;
 
-        // Create fsrs_cards table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS fsrs_cards (
-                id TEXT PRIMARY KEY,
-                problem_id TEXT NOT NULL UNIQUE,
-                due DATE NOT NULL DEFAULT CURRENT_DATE,
-                stability REAL NOT NULL DEFAULT 0.0,
-                difficulty REAL NOT NULL DEFAULT 5.0,
-                state TEXT NOT NULL DEFAULT 'new',
-                reps INTEGER NOT NULL DEFAULT 0,
-                lapses INTEGER NOT NULL DEFAULT 0,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(pool)
-        .await?;
+    Ok(pool)
+}
 
-        // Create problem_mastery table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS problem_mastery (
-                id TEXT PRIMARY KEY,
-                problem_id TEXT NOT NULL UNIQUE,
-                solved BOOLEAN DEFAULT 0,
-                mastery_percent REAL DEFAULT 0,
-                attempt_count INTEGER DEFAULT 0,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(pool)
-        .await?;
+/// Run SQLite's consistency checks: page-level integrity and every declared foreign key
+pub async fn integrity_check(pool: &SqlitePool) -> Result<IntegrityReport, AppError> {
+    let mut conn = pool.acquire().await?;
 
-        // Create fsrs_parameters table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS fsrs_parameters (
-                id TEXT PRIMARY KEY,
-                w_1 REAL DEFAULT 0.40,
-                w_2 REAL DEFAULT 1.86,
-                w_3 REAL DEFAULT 4.93,
-                w_4 REAL DEFAULT 0.94,
-                w_5 REAL DEFAULT 0.86,
-                w_6 REAL DEFAULT 0.01,
-                w_7 REAL DEFAULT 1.49,
-                w_8 REAL DEFAULT 0.04,
-                w_9 REAL DEFAULT 0.36,
-                w_10 REAL DEFAULT 0.86,
-                w_11 REAL DEFAULT 0.20,
-                w_12 REAL DEFAULT 2.50,
-                w_13 REAL DEFAULT 0.14,
-                w_14 REAL DEFAULT 0.94,
-                w_15 REAL DEFAULT 0.16,
-                w_16 REAL DEFAULT 0.10,
-                w_17 REAL DEFAULT 0.29,
-                w_18 REAL DEFAULT 0.34,
-                w_19 REAL DEFAULT 3.73,
-                desired_retention REAL DEFAULT 0.95,
-                total_reviews INTEGER DEFAULT 0,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(pool)
-        .await?;
+    // FTS5 checks its index by writing a command to it, which a read transaction
+    // can't always upgrade to ("database is locked"); hold the write lock throughout
+    sqlx::query("BEGIN IMMEDIATE").execute(&mut *conn).await?;
+    let report = run_integrity_checks(&mut conn).await;
+    sqlx::query("ROLLBACK").execute(&mut *conn).await?;
+    report
+}
 
-        // Insert default parameters if not exists
-        sqlx::query("INSERT OR IGNORE INTO fsrs_parameters (id) VALUES ('global')")
-            .execute(pool)
-            .await?;
-
-        // Create study_phase_progress table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS study_phase_progress (
-                id TEXT PRIMARY KEY,
-                problem_id TEXT NOT NULL UNIQUE,
-                current_phase INTEGER NOT NULL DEFAULT 1,
-                current_step INTEGER NOT NULL DEFAULT 1,
-                time_spent_seconds INTEGER NOT NULL DEFAULT 0,
-                all_phases_completed BOOLEAN DEFAULT 0,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(pool)
+async fn run_integrity_checks(conn: &mut SqliteConnection) -> Result<IntegrityReport, AppError> {
+    let integrity: Vec<(String,)> = sqlx::query_as("PRAGMA integrity_check")
+        .fetch_all(&mut *conn)
         .await?;
+    let integrity_errors: Vec<String> = integrity
+        .into_iter()
+        .map(|(message,)| message)
+        .filter(|message| message != "ok")
+        .collect();
+
+    let foreign_key_violations = sqlx::query_as::<_, ForeignKeyViolation>(
+        "SELECT \"table\", rowid, parent, fkid FROM pragma_foreign_key_check",
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(IntegrityReport {
+        ok: integrity_errors.is_empty() && foreign_key_violations.is_empty(),
+        integrity_errors,
+        foreign_key_violations,
+    })
+}
 
-        // Create fsrs_reviews table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS fsrs_reviews (
-                id TEXT PRIMARY KEY,
-                card_id TEXT NOT NULL,
-                problem_id TEXT NOT NULL,
-                rating INTEGER NOT NULL CHECK(rating >= 1 AND rating <= 10),
-                state_before TEXT NOT NULL,
-                elapsed_seconds INTEGER NOT NULL DEFAULT 0,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (card_id) REFERENCES fsrs_cards(id) ON DELETE CASCADE,
-                FOREIGN KEY (problem_id) REFERENCES problems(id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(pool)
-        .await?;
+/// Empty in-memory database for tests. One connection, so every query sees
+/// the same database.
+#[cfg(test)]
+pub(crate) async fn memory_pool() -> SqlitePool {
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap()
+}
 
-        // Create settings table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS settings (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(pool)
-        .await?;
+/// In-memory database set up the way the app starts: migrated, with the default error types
+#[cfg(test)]
+pub(crate) async fn test_pool() -> SqlitePool {
+    let pool = memory_pool().await;
+    migrations::run_migrations(&pool).await.unwrap();
+    crate::services::ErrorService::init_default_error_types(&pool)
+        .await
+        .unwrap();
+    pool
+}
 
-        // Insert default settings
-        sqlx::query("INSERT OR IGNORE INTO settings (key, value) VALUES ('app_version', '1.0.0')")
-            .execute(pool)
-            .await?;
-
-        // Create FTS5 virtual table for full-text search
-        sqlx::query(
-            r#"
-            CREATE VIRTUAL TABLE IF NOT EXISTS problems_fts USING fts5(
-                id UNINDEXED,
-                title,
-                description,
-                tags
-            )
-            "#,
-        )
-        .execute(pool)
-        .await?;
+/// A difficulty 2 problem "Squeeze" under the subject "Calculus" and topic "Limits"
+#[cfg(test)]
+pub(crate) async fn seed_problem(pool: &SqlitePool) -> crate::models::Problem {
+    use crate::services::{ProblemService, SubjectService, TopicService};
+
+    let subject = SubjectService::create(pool, "Calculus".into(), None)
+        .await
+        .unwrap();
+    let topic = TopicService::create(pool, subject.id, "Limits".into(), None)
+        .await
+        .unwrap();
+    ProblemService::create(pool, topic.id, None, "Squeeze".into(), None, None, 2)
+        .await
+        .unwrap()
+}
 
-        // Create trigger to auto-update FTS on insert
-        sqlx::query(
-            r#"
-            CREATE TRIGGER IF NOT EXISTS problems_ai AFTER INSERT ON problems BEGIN
-              INSERT INTO problems_fts(id, title, description) 
-              VALUES (new.id, new.title, new.description);
-            END
-            "#,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_foreign_keys_enforced_on_every_connection() {
+        let dir = std::env::temp_dir().join(format!("liquidlearn-{}", uuid::Uuid::new_v4()));
+        let pool = open(&dir.join(DATABASE_FILE)).await.unwrap();
+
+        // Hold several connections at once so the pool has to open new ones
+        let mut connections = Vec::new();
+        for _ in 0..MAX_CONNECTIONS {
+            let mut conn = pool.acquire().await.unwrap();
+            let (enabled,): (bool,) = sqlx::query_as("PRAGMA foreign_keys")
+                .fetch_one(&mut *conn)
+                .await
+                .unwrap();
+            assert!(enabled);
+            connections.push(conn);
+        }
+        drop(connections);
+
+        // Deleting a subject takes everything under it along
+        let now = "2024-01-01T00:00:00+00:00";
+        for sql in [
+            "INSERT INTO subjects (id, name, created_at, updated_at) VALUES ('s1', 'Calculus', ?1, ?1)",
+            "INSERT INTO topics (id, subject_id, name, created_at, updated_at) VALUES ('t1', 's1', 'Limits', ?1, ?1)",
+            "INSERT INTO problems (id, topic_id, title, created_at, updated_at) VALUES ('p1', 't1', 'Squeeze', ?1, ?1)",
+            "INSERT INTO fsrs_cards (id, problem_id, due, created_at, updated_at) VALUES ('c1', 'p1', ?1, ?1, ?1)",
+            "DELETE FROM subjects WHERE id = 's1'",
+        ] {
+            sqlx::query(sql).bind(now).execute(&pool).await.unwrap();
+        }
+        let (cards,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM fsrs_cards")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(cards, 0);
+
+        // A dangling reference is refused outright
+        let orphan = sqlx::query(
+            "INSERT INTO topics (id, subject_id, name, created_at, updated_at) VALUES ('t2', 'missing', 'x', '', '')",
         )
-        .execute(pool)
-        .await?;
+        .execute(&pool)
+        .await;
+        assert!(orphan.is_err());
 
-        // Create trigger to auto-update FTS on delete
-        sqlx::query(
-            r#"
-            CREATE TRIGGER IF NOT EXISTS problems_ad AFTER DELETE ON problems BEGIN
-              INSERT INTO problems_fts(problems_fts, id, title, description) 
-              VALUES('delete', old.id, old.title, old.description);
-            END
-            "#,
-        )
-        .execute(pool)
-        .await?;
+        let report = integrity_check(&pool).await.unwrap();
+        assert!(report.ok);
 
-        Ok(())
+        pool.close().await;
+        std::fs::remove_dir_all(dir).ok();
     }
 
-    pub fn pool(&self) -> &SqlitePool {
-        &self.pool
+    #[tokio::test]
+    async fn test_no_connection_outlives_the_migration() {
+        // A connection that cached the schema mid-migration fails to prepare a delete
+        // cascading into the search index ("no such table"), until it reloads the schema
+        let dir = std::env::temp_dir().join(format!("liquidlearn-{}", uuid::Uuid::new_v4()));
+        let pool = open(&dir.join(DATABASE_FILE)).await.unwrap();
+        let mut connections = Vec::new();
+        for _ in 0..MAX_CONNECTIONS {
+            connections.push(pool.acquire().await.unwrap());
+        }
+
+        let now = "2024-01-01T00:00:00+00:00";
+        for i in 1..connections.len() {
+            for sql in [
+                "INSERT INTO subjects (id, name, created_at, updated_at) VALUES (?1, ?1, ?2, ?2)",
+                "INSERT INTO topics (id, subject_id, name, created_at, updated_at) VALUES (?1, ?1, 'Limits', ?2, ?2)",
+                "INSERT INTO problems (id, topic_id, title, created_at, updated_at) VALUES (?1, ?1, 'Squeeze', ?2, ?2)",
+            ] {
+                sqlx::query(sql)
+                    .bind(i.to_string())
+                    .bind(now)
+                    .execute(&mut *connections[0])
+                    .await
+                    .unwrap();
+            }
+        }
+        // Each delete is the first statement its connection runs
+        for (i, conn) in connections.iter_mut().enumerate().skip(1) {
+            sqlx::query("DELETE FROM subjects WHERE id = ?")
+                .bind(i.to_string())
+                .execute(&mut **conn)
+                .await
+                .unwrap();
+        }
     }
 }
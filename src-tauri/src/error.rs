@@ -0,0 +1,191 @@
+use crate::db::migrations::MigrationError;
+use serde::Serialize;
+use std::fmt;
+
+/// Error returned by every service and command.
+///
+/// Serialized with a stable `code` tag so the frontend can branch on the kind
+/// of failure instead of parsing message text, e.g.
+/// `{"code":"conflict","entity":"subject","message":"..."}`.
+///
+/// `entity` is always the singular snake_case name of the row's kind
+/// (`subject`, `fsrs_card`, `study_session`, ...), whichever variant carries it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum AppError {
+    /// The requested row does not exist
+    NotFound { entity: String, id: String },
+    /// The write clashes with existing data (duplicate name, row still referenced, ...)
+    Conflict { entity: String, message: String },
+    /// The input was rejected before touching the database
+    Validation { field: String, message: String },
+    /// Any other database failure
+    Database { message: String },
+    /// File system or worker thread failure
+    Io { message: String },
+}
+
+impl AppError {
+    pub fn not_found(entity: &str, id: impl fmt::Display) -> Self {
+        AppError::NotFound {
+            entity: entity.to_string(),
+            id: id.to_string(),
+        }
+    }
+
+    pub fn conflict(entity: &str, message: impl Into<String>) -> Self {
+        AppError::Conflict {
+            entity: entity.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn validation(field: &str, message: impl Into<String>) -> Self {
+        AppError::Validation {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound { entity, id } => write!(f, "{} {} not found", entity, id),
+            AppError::Conflict { entity, message } => write!(f, "{}: {}", entity, message),
+            AppError::Validation { field, message } => write!(f, "{}: {}", field, message),
+            AppError::Database { message } => write!(f, "Database error: {}", message),
+            AppError::Io { message } => write!(f, "I/O error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// Table named in a SQLite constraint message such as
+/// `UNIQUE constraint failed: subjects.name`
+fn constraint_table(message: &str) -> Option<&str> {
+    let (_, columns) = message.split_once(": ")?;
+    columns.split('.').next().filter(|t| !t.is_empty())
+}
+
+/// Entity name for a table, matching what the services pass to `not_found`
+fn table_entity(table: &str) -> &str {
+    match table {
+        "subjects" => "subject",
+        "topics" => "topic",
+        "theories" => "theory",
+        "problems" => "problem",
+        "problem_tags" => "problem_tag",
+        "problem_mastery" => "problem_mastery",
+        "attempts" => "attempt",
+        "attempt_errors" => "attempt_error",
+        "error_types" => "error_type",
+        "fsrs_cards" => "fsrs_card",
+        "fsrs_reviews" => "review",
+        "fsrs_parameters" => "fsrs_parameter_set",
+        "settings" => "setting",
+        "study_phase_entries" => "study_phase_entry",
+        "study_sessions" => "study_session",
+        "difficulty_time_budgets" => "difficulty_time_budget",
+        other => other,
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            // Lookups that can miss use fetch_optional + a named not_found, so
+            // a missing row here is a broken query rather than a bad id
+            sqlx::Error::RowNotFound => AppError::Database {
+                message: e.to_string(),
+            },
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                let entity = constraint_table(db.message()).map_or("row", table_entity);
+                AppError::conflict(entity, db.message())
+            }
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                // SQLite doesn't say which reference failed
+                AppError::conflict("reference", db.message())
+            }
+            sqlx::Error::Io(io) => AppError::Io {
+                message: io.to_string(),
+            },
+            _ => AppError::Database {
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+impl From<MigrationError> for AppError {
+    fn from(e: MigrationError) -> Self {
+        match e {
+            MigrationError::Database(e) => e.into(),
+            other => AppError::Database {
+                message: other.to_string(),
+            },
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Io {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<tokio::task::JoinError> for AppError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        AppError::Io {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    /// Only raised when decoding data this app wrote itself
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Database {
+            message: e.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::services::SubjectService;
+
+    #[test]
+    fn test_serializes_with_stable_code() {
+        let json = serde_json::to_value(AppError::not_found("subject", "abc")).unwrap();
+        assert_eq!(json["code"], "not_found");
+        assert_eq!(json["entity"], "subject");
+        assert_eq!(json["id"], "abc");
+        assert_eq!(
+            constraint_table("UNIQUE constraint failed: subjects.name"),
+            Some("subjects")
+        );
+        assert_eq!(table_entity("fsrs_parameters"), "fsrs_parameter_set");
+    }
+
+    #[tokio::test]
+    async fn test_database_errors_keep_their_entity() {
+        let pool = test_pool().await;
+
+        SubjectService::create(&pool, "Calculus".into(), None)
+            .await
+            .unwrap();
+        let duplicate = SubjectService::create(&pool, "Calculus".into(), None).await;
+        assert!(
+            matches!(duplicate, Err(AppError::Conflict { ref entity, .. }) if entity == "subject")
+        );
+
+        let missing = SubjectService::get_by_id(&pool, "nope".into()).await;
+        assert_eq!(missing.unwrap_err(), AppError::not_found("subject", "nope"));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod commands;
-pub mod migrations;
+pub mod db;
+pub mod error;
 pub mod models;
 pub mod services;
 
@@ -4,20 +4,19 @@
 )]
 
 mod commands;
-mod migrations;
+mod db;
+mod error;
 mod models;
 mod services;
 
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
-use std::str::FromStr;
+use error::AppError;
+use sqlx::SqlitePool;
 use tauri::State;
 
 #[tauri::command]
-async fn initialize_db(db: State<'_, SqlitePool>) -> Result<String, String> {
+async fn initialize_db(db: State<'_, SqlitePool>) -> Result<String, AppError> {
     // Run migrations
-    migrations::run_migrations(db.inner())
-        .await
-        .map_err(|e| e.to_string())?;
+    db::migrations::run_migrations(db.inner()).await?;
 
     // Initialize default error types
     services::ErrorService::init_default_error_types(db.inner()).await?;
@@ -25,40 +24,39 @@ async fn initialize_db(db: State<'_, SqlitePool>) -> Result<String, String> {
     Ok("Database initialized successfully".to_string())
 }
 
+/// Report corruption and rows whose foreign keys point nowhere
+#[tauri::command]
+async fn integrity_check(
+    db: State<'_, SqlitePool>,
+) -> Result<models::integrity::IntegrityReport, AppError> {
+    db::integrity_check(db.inner()).await
+}
+
 #[tokio::main]
 async fn main() {
-    // Create database directory if it doesn't exist
-    let app_data_dir = tauri::api::path::app_data_dir(&tauri::Config::default())
-        .unwrap_or_else(|| std::path::PathBuf::from("./"));
-
-    std::fs::create_dir_all(&app_data_dir).ok();
-
-    // Database path
-    let db_path = app_data_dir.join("liquidlearn.db");
-    let database_url = format!("sqlite:{}", db_path.to_string_lossy());
+    let db_path = db::database_path();
 
-    // SQLite connection options
-    let connect_options = SqliteConnectOptions::from_str(&database_url)
-        .expect("Invalid database URL")
-        .create_if_missing(true);
-
-    // Create pool
-    let pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect_with(connect_options)
-        .await
-        .expect("Failed to create pool");
-
-    // Run migrations on startup
-    migrations::run_migrations(&pool)
-        .await
-        .expect("Failed to run migrations");
+    // Open and migrate; never touch a database this build doesn't understand
+    let pool = match db::open(&db_path).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            std::process::exit(1);
+        }
+    };
 
     // Initialize default error types
     services::ErrorService::init_default_error_types(&pool)
         .await
         .expect("Failed to initialize error types");
 
+    // Pick up a session the last run left open
+    match services::StudySessionService::recover(&pool).await {
+        Ok(Some(session)) => println!("✓ Study session {} is {:?}", session.id, session.status),
+        Ok(None) => {}
+        Err(e) => eprintln!("✗ Failed to recover study session: {}", e),
+    }
+
     println!("✓ Database initialized at: {}", db_path.display());
 
     tauri::Builder::default()
@@ -66,6 +64,7 @@ async fn main() {
         .invoke_handler(tauri::generate_handler![
             // System
             initialize_db,
+            integrity_check,
             // Subjects
             commands::create_subject,
             commands::get_subject,
@@ -94,8 +93,15 @@ async fn main() {
             commands::delete_problem,
             commands::mark_problem_solved,
             commands::get_problem_with_details,
+            commands::search_problems,
+            commands::add_problem_tag,
+            commands::remove_problem_tag,
+            commands::list_problem_tags,
+            commands::list_problems_by_tag,
+            commands::get_problem_mastery,
             // Attempts
             commands::create_attempt,
+            commands::submit_attempt,
             commands::get_attempt,
             commands::list_attempts_by_problem,
             commands::update_attempt_commentary,
@@ -109,11 +115,73 @@ async fn main() {
             commands::init_error_types,
             // FSRS
             commands::process_review,
+            commands::preview_review,
             commands::get_due_cards,
             commands::get_fsrs_stats,
             commands::get_fsrs_card,
             commands::get_fsrs_card_by_problem,
             commands::get_cards_by_state,
+            commands::undo_review,
+            commands::get_card_retrievability,
+            commands::list_card_retrievability,
+            commands::get_forgetting_curve,
+            commands::suspend_card,
+            commands::unsuspend_card,
+            commands::bury_card,
+            commands::set_card_due,
+            commands::reset_card,
+            commands::forget_card,
+            // FSRS parameters
+            commands::get_fsrs_parameters,
+            commands::get_fsrs_parameters_for_subject,
+            commands::list_fsrs_parameter_sets,
+            commands::create_fsrs_parameter_set,
+            commands::update_fsrs_parameter_set,
+            commands::delete_fsrs_parameter_set,
+            commands::optimize_fsrs_parameters,
+            commands::preview_reschedule,
+            commands::reschedule_cards,
+            // Leeches
+            commands::list_leeches,
+            commands::reset_leech,
+            commands::rewrite_leech,
+            // Review log
+            commands::get_card_review_history,
+            commands::list_review_logs,
+            // Review queue
+            commands::get_review_queue,
+            // Workload
+            commands::simulate_workload,
+            // Settings
+            commands::get_setting,
+            commands::list_settings,
+            commands::set_setting,
+            commands::delete_setting,
+            // Study phases
+            commands::start_study_phase,
+            commands::get_study_session,
+            commands::advance_study_phase,
+            commands::update_study_phase_time,
+            commands::complete_study_phase,
+            commands::reset_study_phase,
+            commands::list_study_phase_entries,
+            commands::get_phase_times_by_problem,
+            commands::get_phase_times_by_topic,
+            commands::get_study_phase_theory,
+            // Study sessions
+            commands::start_study_session,
+            commands::study_session_heartbeat,
+            commands::pause_study_session,
+            commands::resume_study_session,
+            commands::stop_study_session,
+            commands::get_current_study_session,
+            commands::get_study_session_summary,
+            commands::list_study_sessions,
+            // Time budgets
+            commands::get_difficulty_time_budgets,
+            commands::set_difficulty_time_budget,
+            commands::get_problem_time_budget,
+            commands::set_problem_time_budget,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
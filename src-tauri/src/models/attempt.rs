@@ -6,6 +6,7 @@ pub struct Attempt {
     pub problem_id: String,
     pub is_solved: bool,
     pub commentary: Option<String>,
+    pub time_spent_seconds: i64,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -17,6 +18,34 @@ pub struct CreateAttemptRequest {
     pub commentary: Option<String>,
 }
 
+/// An attempt with the errors made and its FSRS review, recorded atomically.
+/// An attempt that takes longer than its problem's time budget gets an
+/// Over Time Limit error added.
+#[derive(Debug, Deserialize)]
+pub struct SubmitAttemptRequest {
+    pub problem_id: String,
+    pub is_solved: bool,
+    pub commentary: Option<String>,
+    #[serde(default)]
+    pub errors: Vec<NewAttemptError>,
+    pub rating: Option<u8>, // 1-4; no review is recorded without one
+    #[serde(default)]
+    pub time_spent_seconds: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewAttemptError {
+    pub error_type_id: i32,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubmittedAttempt {
+    pub attempt: Attempt,
+    pub errors: Vec<AttemptError>,
+    pub review: Option<ReviewResult>,
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Serialize)]
 pub struct AttemptWithErrors {
@@ -26,3 +55,4 @@ pub struct AttemptWithErrors {
 
 // Re-export from error module
 use super::error_log::AttemptError;
+use super::fsrs::ReviewResult;
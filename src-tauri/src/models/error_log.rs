@@ -39,6 +39,9 @@ pub struct AttemptErrorWithType {
     pub error_type: ErrorType,
 }
 
+/// Logged automatically when an attempt runs over its time budget
+pub const OVER_TIME_LIMIT_ERROR_TYPE: i32 = 10;
+
 // Seed error types
 pub fn get_default_error_types() -> Vec<(i32, &'static str, &'static str, f64)> {
     vec![
@@ -12,28 +12,269 @@ pub struct FsrsCard {
     pub lapses: i32,
     pub elapsed_days: i32,
     pub scheduled_days: i32,
+    #[serde(default)]
+    pub step: i32, // Position in the (re)learning steps while learning/relearning
+    #[serde(default)]
+    pub is_leech: bool, // Lapsed `leech_threshold` times or more
+    #[serde(default)]
+    pub is_suspended: bool, // Kept out of the due list and review queue
+    #[serde(default)]
+    pub buried_until: Option<String>, // Hidden until this instant (start of a later day)
+    pub last_review: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// FSRS-5 default weights (w0..w18), as shipped by the reference implementation
+pub const DEFAULT_W: [f64; 19] = [
+    0.40255, 1.18385, 3.173, 15.69105, 7.1949, 0.5345, 1.4604, 0.0046, 1.54575, 0.1192, 1.01925,
+    1.9395, 0.11, 0.29605, 2.2698, 0.2315, 2.9898, 0.51655, 0.6621,
+];
+
+/// Allowed range of each weight (FSRS-5 parameter clipper)
+pub const WEIGHT_BOUNDS: [(f64, f64); 19] = [
+    (0.001, 100.0),
+    (0.001, 100.0),
+    (0.001, 100.0),
+    (0.001, 100.0),
+    (1.0, 10.0),
+    (0.001, 4.0),
+    (0.001, 4.0),
+    (0.001, 0.75),
+    (0.0, 4.5),
+    (0.0, 0.8),
+    (0.001, 3.5),
+    (0.001, 5.0),
+    (0.001, 0.25),
+    (0.001, 0.9),
+    (0.0, 4.0),
+    (0.0, 1.0),
+    (1.0, 6.0),
+    (0.0, 2.0),
+    (0.0, 2.0),
+];
+
+pub const DEFAULT_DESIRED_RETENTION: f64 = 0.9;
+pub const DEFAULT_MAXIMUM_INTERVAL: i32 = 36500;
+pub const DEFAULT_LEARNING_STEPS: [u32; 2] = [1, 10]; // Minutes
+pub const DEFAULT_RELEARNING_STEPS: [u32; 1] = [10]; // Minutes
+
+/// The 4-grade answer used by FSRS
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Rating {
+    Again = 1,
+    Hard = 2,
+    Good = 3,
+    Easy = 4,
+}
+
+impl Rating {
+    pub fn from_u8(n: u8) -> Option<Self> {
+        match n {
+            1 => Some(Rating::Again),
+            2 => Some(Rating::Hard),
+            3 => Some(Rating::Good),
+            4 => Some(Rating::Easy),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(self) -> f64 {
+        self as u8 as f64
+    }
+}
+
+/// How the errors logged on an attempt affect its review.
+/// The error weight is the sum of the `error_types.multiplier` of each logged error.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ErrorPolicy {
+    None, // Errors don't touch scheduling
+    #[default]
+    ScaleStability, // Divide the post-review stability by 1 + the error weight
+    EffectiveRating, // Cap the rating by the error weight (>= 1.5 Again, >= 1.0 Hard, else Good)
+}
+
+pub const DEFAULT_LEECH_THRESHOLD: i32 = 8;
+
+/// What happens to a card once it becomes a leech
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum LeechAction {
+    #[default]
+    Tag, // Flag it, keep scheduling it
+    Suspend, // Flag it and take it out of reviews until reset or unsuspended
+}
+
+/// Weights and scheduling targets fed to the FSRS model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FsrsParameters {
+    pub w: [f64; 19],
+    pub desired_retention: f64,
+    pub maximum_interval: i32,
+    pub learning_steps: Vec<u32>, // Minutes; empty = graduate on first review
+    pub relearning_steps: Vec<u32>, // Minutes; empty = lapses stay in review
+    pub enable_fuzz: bool,        // Spread review intervals deterministically per card
+    pub enable_load_balance: bool, // Pick the least busy day within the fuzz range
+    pub error_policy: ErrorPolicy,
+    pub leech_threshold: i32, // Lapses that make a leech; 0 disables detection
+    pub leech_action: LeechAction,
+}
+
+impl Default for FsrsParameters {
+    fn default() -> Self {
+        FsrsParameters {
+            w: DEFAULT_W,
+            desired_retention: DEFAULT_DESIRED_RETENTION,
+            maximum_interval: DEFAULT_MAXIMUM_INTERVAL,
+            learning_steps: DEFAULT_LEARNING_STEPS.to_vec(),
+            relearning_steps: DEFAULT_RELEARNING_STEPS.to_vec(),
+            enable_fuzz: true,
+            enable_load_balance: false,
+            error_policy: ErrorPolicy::default(),
+            leech_threshold: DEFAULT_LEECH_THRESHOLD,
+            leech_action: LeechAction::default(),
+        }
+    }
+}
+
+/// Persisted parameter set (`fsrs_parameters` row)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct FsrsParameterSet {
+    pub id: String,
+    pub name: String,
+    pub subject_id: Option<String>, // None for the global set
+    #[sqlx(json)]
+    pub weights: Vec<f64>,
+    pub desired_retention: f64,
+    pub maximum_interval: i32,
+    #[sqlx(json)]
+    pub learning_steps: Vec<u32>,
+    #[sqlx(json)]
+    pub relearning_steps: Vec<u32>,
+    pub enable_fuzz: bool,
+    pub enable_load_balance: bool,
+    pub error_policy: ErrorPolicy,
+    pub leech_threshold: i32,
+    pub leech_action: LeechAction,
+    pub total_reviews: i64, // Reviews the weights were trained on (0 = defaults)
+    pub log_loss: Option<f64>,
+    pub rmse: Option<f64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl FsrsParameterSet {
+    pub fn to_parameters(&self) -> FsrsParameters {
+        FsrsParameters {
+            // A malformed row falls back to the defaults rather than breaking reviews
+            w: self.weights.as_slice().try_into().unwrap_or(DEFAULT_W),
+            desired_retention: self.desired_retention,
+            maximum_interval: self.maximum_interval,
+            learning_steps: self.learning_steps.clone(),
+            relearning_steps: self.relearning_steps.clone(),
+            enable_fuzz: self.enable_fuzz,
+            enable_load_balance: self.enable_load_balance,
+            error_policy: self.error_policy,
+            leech_threshold: self.leech_threshold,
+            leech_action: self.leech_action,
+        }
+    }
+}
+
+/// Per-subject override; weights and limits default to the global set's
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateFsrsParameterSetRequest {
+    pub name: String,
+    pub subject_id: String,
+    pub weights: Option<Vec<f64>>,
+    pub desired_retention: Option<f64>,
+    pub maximum_interval: Option<i32>,
+    pub learning_steps: Option<Vec<u32>>,
+    pub relearning_steps: Option<Vec<u32>>,
+    pub enable_fuzz: Option<bool>,
+    pub enable_load_balance: Option<bool>,
+    pub error_policy: Option<ErrorPolicy>,
+    pub leech_threshold: Option<i32>,
+    pub leech_action: Option<LeechAction>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateFsrsParameterSetRequest {
+    pub name: Option<String>,
+    pub weights: Option<Vec<f64>>,
+    pub desired_retention: Option<f64>,
+    pub maximum_interval: Option<i32>,
+    pub learning_steps: Option<Vec<u32>>,
+    pub relearning_steps: Option<Vec<u32>>,
+    pub enable_fuzz: Option<bool>,
+    pub enable_load_balance: Option<bool>,
+    pub error_policy: Option<ErrorPolicy>,
+    pub leech_threshold: Option<i32>,
+    pub leech_action: Option<LeechAction>,
+}
+
+/// Outcome of fitting the weights to the review log
+#[derive(Debug, Clone, Serialize)]
+pub struct OptimizationResult {
+    pub parameter_set_id: String,
+    pub card_count: i64,
+    pub review_count: i64, // Reviews the loss was evaluated on
+    pub weights_before: Vec<f64>,
+    pub weights_after: Vec<f64>,
+    pub log_loss_before: f64,
+    pub log_loss_after: f64,
+    pub rmse_before: f64,
+    pub rmse_after: f64,
+}
+
+/// Card memory state produced by a single review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardUpdate {
+    pub state: String,
+    pub step: i32,
+    pub stability: f64,
+    pub difficulty: f64,
+    pub elapsed_days: i32,
+    pub scheduled_days: i32,    // 0 while in a sub-day (re)learning step
+    pub scheduled_minutes: i64, // Exact interval until `due`
+    pub due: String,
+}
+
+/// What answering with `rating` would do to a card
+#[derive(Debug, Clone, Serialize)]
+pub struct RatingPreview {
+    pub rating: Rating,
+    #[serde(flatten)]
+    pub update: CardUpdate,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReviewRequest {
     pub problem_id: String,
     pub attempt_is_solved: bool, // Whether the attempt was successful
-    pub quality: u8,             // 1-5 (1=fail, 5=perfect)
+    pub rating: u8,              // 1-4 (1=again, 2=hard, 3=good, 4=easy)
     pub time_spent_seconds: i64,
+    #[serde(default)]
+    pub attempt_id: Option<String>, // Errors logged on this attempt feed the error policy
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReviewResult {
     pub problem_id: String,
     pub card_id: String,
+    pub review_id: String,
     pub new_state: String,
     pub new_stability: f64,
     pub new_difficulty: f64,
     pub new_interval_days: i32,
+    pub new_interval_minutes: i64,
     pub next_due: String,
     pub is_correct: bool,
+    pub became_leech: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -47,6 +288,24 @@ pub struct FsrsStats {
     pub retention_rate: f64,
 }
 
+/// Probability of recalling a card's problem at a given moment
+#[derive(Debug, Clone, Serialize)]
+pub struct CardRetrievability {
+    pub card_id: String,
+    pub problem_id: String,
+    pub state: String,
+    pub stability: f64,
+    pub at: String,
+    pub retrievability: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForgettingCurvePoint {
+    pub day: i32, // Days from now
+    pub date: String,
+    pub retrievability: f64,
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Serialize)]
 pub struct ReviewStats {
@@ -55,12 +314,13 @@ pub struct ReviewStats {
     pub new_problems: i64,
 }
 
-#[allow(dead_code)]
 impl FsrsCard {
+    #[allow(dead_code)]
     pub fn is_due(&self) -> bool {
         self.due <= chrono::Utc::now().to_rfc3339()
     }
 
+    #[allow(dead_code)]
     pub fn get_state_display(&self) -> String {
         match self.state.as_str() {
             "new" => "New",
@@ -0,0 +1,17 @@
+use serde::Serialize;
+
+/// A row whose foreign key points at a missing parent, as reported by SQLite
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    pub rowid: Option<i64>,
+    pub parent: String, // Table the missing row should be in
+    pub fkid: i64,      // Index of the violated constraint within `table`
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityReport {
+    pub ok: bool,
+    pub integrity_errors: Vec<String>, // From PRAGMA integrity_check; empty when fine
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+}
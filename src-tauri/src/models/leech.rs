@@ -0,0 +1,10 @@
+use super::{AttemptError, FsrsCard, Problem};
+use serde::Serialize;
+
+/// A card that keeps lapsing, with its problem and everything that went wrong on it
+#[derive(Debug, Clone, Serialize)]
+pub struct Leech {
+    pub card: FsrsCard,
+    pub problem: Problem,
+    pub errors: Vec<AttemptError>, // Every logged error, newest first
+}
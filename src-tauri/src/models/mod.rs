@@ -1,15 +1,27 @@
 pub mod attempt;
 pub mod error_log;
 pub mod fsrs;
+pub mod integrity;
+pub mod leech;
 pub mod problem;
+pub mod reschedule;
+pub mod review_log;
+pub mod review_queue;
+pub mod settings;
+pub mod study_phase;
+pub mod study_session;
 pub mod subject;
 pub mod theory;
+pub mod time_budget;
 pub mod topic;
+pub mod workload;
 
 pub use attempt::Attempt;
 pub use error_log::{AttemptError, ErrorType};
 pub use fsrs::FsrsCard;
-pub use problem::Problem;
+pub use problem::{Problem, ProblemMastery, ProblemTag};
+pub use review_log::ReviewLog;
+pub use settings::Setting;
 pub use subject::Subject;
 pub use theory::Theory;
 pub use topic::Topic;
@@ -42,3 +42,21 @@ pub struct UpdateProblemRequest {
     pub image_url: Option<String>,
     pub difficulty: Option<i32>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProblemTag {
+    pub id: String,
+    pub problem_id: String,
+    pub tag_name: String,
+    pub created_at: String,
+}
+
+/// Attempt statistics of a problem, maintained by the database from its attempts
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProblemMastery {
+    pub problem_id: String,
+    pub solved: bool,         // Solved at least once
+    pub mastery_percent: f64, // Share of attempts solved
+    pub attempt_count: i64,
+    pub updated_at: String,
+}
@@ -0,0 +1,28 @@
+use serde::Serialize;
+
+/// A card whose memory state or due date changes under the current parameters
+#[derive(Debug, Clone, Serialize)]
+pub struct RescheduledCard {
+    pub card_id: String,
+    pub problem_id: String,
+    pub replayed: bool, // Memory state rebuilt from the review log
+    pub stability_before: f64,
+    pub stability_after: f64,
+    pub difficulty_before: f64,
+    pub difficulty_after: f64,
+    pub interval_before: i32,
+    pub interval_after: i32,
+    pub due_before: String,
+    pub due_after: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RescheduleSummary {
+    pub applied: bool, // false for a preview
+    pub total_cards: i64,
+    pub replayed: i64,
+    pub moved: i64, // Due on a different day than before
+    pub earlier: i64,
+    pub later: i64,
+    pub cards: Vec<RescheduledCard>, // Every changed card, moved or not
+}
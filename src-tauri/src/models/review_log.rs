@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// What produced a log entry. Manual actions carry rating 0 and are not replayed
+/// as reviews by the optimizer.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ReviewKind {
+    #[default]
+    Review,
+    Suspend,
+    Unsuspend,
+    Bury,
+    SetDue,
+    Reset,      // Back to new, counters kept
+    Forget,     // Back to new, counters and flags cleared
+    Reschedule, // Recomputed under new parameters
+}
+
+/// One FSRS review or manual card action, with the card's memory state before and after it
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReviewLog {
+    pub id: String,
+    pub card_id: String,
+    pub problem_id: String,
+    pub rating: i32, // 1-4 (again, hard, good, easy), 0 for manual actions
+    #[serde(default)]
+    pub kind: ReviewKind,
+    pub state_before: String,
+    pub state_after: String,
+    pub stability_before: f64,
+    pub stability_after: f64,
+    pub difficulty_before: f64,
+    pub difficulty_after: f64,
+    pub stability_penalty: f64, // Divisor the error policy applied to stability, 1 for none
+    pub elapsed_days: i32,      // Days since the previous review
+    pub scheduled_days: i32,    // Interval chosen by this review
+    pub duration_seconds: i64,
+    pub reviewed_at: String,
+}
+
+/// Filters for the global review log; every field is optional
+#[derive(Debug, Default, Deserialize)]
+pub struct ReviewLogFilter {
+    pub subject_id: Option<String>,
+    pub topic_id: Option<String>,
+    pub problem_id: Option<String>,
+    pub rating: Option<i32>,
+    pub from: Option<String>, // RFC 3339, inclusive
+    pub to: Option<String>,   // RFC 3339, exclusive
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
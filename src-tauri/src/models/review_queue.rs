@@ -0,0 +1,66 @@
+use super::FsrsCard;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_NEW_PER_DAY: i64 = 20;
+pub const DEFAULT_REVIEWS_PER_DAY: i64 = 200;
+
+/// How review cards are ordered within today's queue
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOrder {
+    #[default]
+    Due, // Oldest due date first
+    Retrievability, // Most likely forgotten first
+    Difficulty,     // Hardest first
+    Random,         // Shuffled, stable for the day
+}
+
+/// Limits and ordering for `ReviewQueueService::build`; every field is optional
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct QueueOptions {
+    pub subject_id: Option<String>,
+    pub new_per_day: i64,
+    pub reviews_per_day: i64,
+    pub order: QueueOrder,
+    pub interleave_topics: bool, // Round-robin across topics instead of blocks per topic
+}
+
+impl Default for QueueOptions {
+    fn default() -> Self {
+        QueueOptions {
+            subject_id: None,
+            new_per_day: DEFAULT_NEW_PER_DAY,
+            reviews_per_day: DEFAULT_REVIEWS_PER_DAY,
+            order: QueueOrder::Due,
+            interleave_topics: true,
+        }
+    }
+}
+
+/// A card in the queue, with what the ordering needs to know about it
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct QueuedCard {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub card: FsrsCard,
+    pub topic_id: String,
+    #[sqlx(skip)]
+    pub retrievability: f64, // At the time the queue was built
+}
+
+/// Cards answered since the start of the (local) day, by state before the answer
+#[derive(Debug, Clone, Copy, Default, Serialize, sqlx::FromRow)]
+pub struct StudiedToday {
+    pub new_cards: i64,
+    pub reviews: i64,
+    pub learning: i64, // Learning and relearning steps, never capped
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewQueue {
+    pub cards: Vec<QueuedCard>, // Relearning, learning, reviews, then new cards
+    pub studied_today: StudiedToday,
+    pub new_remaining: i64, // Daily allowance left after this queue
+    pub reviews_remaining: i64,
+}
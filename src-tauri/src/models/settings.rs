@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// A user preference, stored as text under a free-form key
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Setting {
+    pub key: String,
+    pub value: String,
+    pub updated_at: String,
+}
@@ -1,4 +1,6 @@
+use super::theory::THEORY_PHASES;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// The 4 phases of decoupled learning
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -32,15 +34,16 @@ impl StudyPhase {
         }
     }
 
-    pub fn to_string(&self) -> String {
+    pub fn as_str(&self) -> &'static str {
         match self {
-            StudyPhase::Decode => "decode".to_string(),
-            StudyPhase::Encode => "encode".to_string(),
-            StudyPhase::Recall => "recall".to_string(),
-            StudyPhase::Reflect => "reflect".to_string(),
+            StudyPhase::Decode => "decode",
+            StudyPhase::Encode => "encode",
+            StudyPhase::Recall => "recall",
+            StudyPhase::Reflect => "reflect",
         }
     }
 
+    #[allow(dead_code)]
     pub fn from_string(s: &str) -> Self {
         match s {
             "encode" => StudyPhase::Encode,
@@ -50,6 +53,25 @@ impl StudyPhase {
         }
     }
 
+    /// The phase that follows this one; None after Reflect
+    pub fn next(&self) -> Option<StudyPhase> {
+        match self {
+            StudyPhase::Decode => Some(StudyPhase::Encode),
+            StudyPhase::Encode => Some(StudyPhase::Recall),
+            StudyPhase::Recall => Some(StudyPhase::Reflect),
+            StudyPhase::Reflect => None,
+        }
+    }
+
+    /// Theory `phase_number`s shown during this phase, in order
+    pub fn theory_phase_numbers(&self) -> Vec<i32> {
+        THEORY_PHASES
+            .iter()
+            .filter(|(_, _, phase)| phase == self)
+            .map(|(n, _, _)| *n)
+            .collect()
+    }
+
     /// Get description of this phase
     pub fn description(&self) -> &str {
         match self {
@@ -61,14 +83,21 @@ impl StudyPhase {
     }
 }
 
+impl fmt::Display for StudyPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Progress through study phases for a problem
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct StudyPhaseProgress {
     pub id: String,
     pub problem_id: String,
-    pub current_phase: i32, // 1-4
-    pub current_step: i32,  // 1-N (substeps within phase)
-    pub phase_time_total: i32,
+    pub current_phase: i32,           // 1-4
+    pub current_step: i32,            // 1-N (substeps within phase)
+    pub phase_time_total: i32,        // seconds, over every finished phase
+    pub phase_elapsed: i32,           // seconds into the current phase, as last checkpointed
     pub completed_at: Option<String>, // When all 4 phases finished
     pub created_at: String,
     pub updated_at: String,
@@ -88,8 +117,25 @@ impl StudyPhaseProgress {
     }
 
     pub fn get_completion_percent(&self) -> f64 {
+        if self.is_completed() {
+            return 100.0;
+        }
         ((self.current_phase - 1) as f64 / 4.0 * 100.0).min(100.0)
     }
+
+    pub fn to_session_data(&self) -> StudySessionData {
+        let phase = self.get_current_phase();
+        StudySessionData {
+            problem_id: self.problem_id.clone(),
+            current_phase: phase.to_string(),
+            current_step: self.current_step,
+            phase_description: phase.description().to_string(),
+            time_in_phase: self.phase_elapsed,
+            total_time: self.get_total_time() + self.phase_elapsed,
+            total_completion: self.get_completion_percent(),
+            is_completed: self.is_completed(),
+        }
+    }
 }
 
 /// Request to advance to next phase
@@ -100,6 +146,52 @@ pub struct AdvancePhaseRequest {
     pub notes: Option<String>,
 }
 
+/// One finished pass through a phase: its time and the notes taken
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StudyPhaseEntry {
+    pub id: String,
+    pub problem_id: String,
+    pub phase: i32, // 1-4
+    pub time_spent_seconds: i32,
+    pub notes: Option<String>,
+    pub created_at: String,
+}
+
+/// Time spent in one phase, summed over every pass (of a problem or a whole topic)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseTimeSummary {
+    pub phase: String,
+    pub total_seconds: i64,
+    pub passes: i64,
+    pub share: f64, // Fraction of all the time spent, 0-1
+}
+
+impl PhaseTimeSummary {
+    /// Every phase in order from (phase number, total seconds, passes) rows;
+    /// phases never studied get zeroes
+    pub fn from_totals(rows: &[(i32, i64, i64)]) -> Vec<PhaseTimeSummary> {
+        let all: i64 = rows.iter().map(|&(_, seconds, _)| seconds).sum();
+        (1..=4)
+            .map(|n| {
+                let (total_seconds, passes) = rows
+                    .iter()
+                    .find(|&&(phase, _, _)| phase == n)
+                    .map_or((0, 0), |&(_, seconds, passes)| (seconds, passes));
+                PhaseTimeSummary {
+                    phase: StudyPhase::from_number(n).to_string(),
+                    total_seconds,
+                    passes,
+                    share: if all > 0 {
+                        total_seconds as f64 / all as f64
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
 /// Response after advancing phase
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhaseAdvanceResult {
@@ -124,7 +216,9 @@ pub struct StudySessionData {
     pub current_step: i32,
     pub phase_description: String,
     pub time_in_phase: i32,
+    pub total_time: i32,
     pub total_completion: f64,
+    pub is_completed: bool,
 }
 
 #[cfg(test)]
@@ -147,6 +241,7 @@ mod tests {
             current_phase: 2,
             current_step: 1,
             phase_time_total: 100,
+            phase_elapsed: 0,
             completed_at: None,
             created_at: "2024-01-01".to_string(),
             updated_at: "2024-01-01".to_string(),
@@ -156,6 +251,17 @@ mod tests {
         assert!(!progress.is_completed());
     }
 
+    #[test]
+    fn test_phase_time_summary() {
+        let summary = PhaseTimeSummary::from_totals(&[(1, 100, 1), (2, 150, 2), (4, 200, 1)]);
+
+        assert_eq!(summary.len(), 4);
+        assert_eq!(summary.iter().map(|p| p.total_seconds).sum::<i64>(), 450);
+        assert_eq!(summary[2].phase, "recall");
+        assert_eq!((summary[2].total_seconds, summary[2].passes), (0, 0));
+        assert!((summary[3].share - 200.0 / 450.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_total_time_calculation() {
         let progress = StudyPhaseProgress {
@@ -164,12 +270,13 @@ mod tests {
             current_phase: 4,
             current_step: 1,
             phase_time_total: 200,
+            phase_elapsed: 0,
             completed_at: Some("2024-01-02".to_string()),
             created_at: "2024-01-01".to_string(),
             updated_at: "2024-01-02".to_string(),
         };
 
-        assert_eq!(progress.get_total_time(), 450);
+        assert_eq!(progress.get_total_time(), 200);
         assert!(progress.is_completed());
     }
 }
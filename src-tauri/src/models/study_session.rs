@@ -0,0 +1,210 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A heartbeat gap longer than this means the user walked away (or the app was
+/// closed); the gap counts as idle instead of study time
+pub const IDLE_GAP_SECONDS: i64 = 5 * 60;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum SessionStatus {
+    Running,
+    Paused,
+    Stopped,
+}
+
+/// Work/break lengths of a timeboxed (pomodoro) session
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Timebox {
+    pub work_seconds: i64,
+    pub break_seconds: i64,
+}
+
+impl Timebox {
+    /// Break time within the first `position` seconds of running time
+    fn break_time_before(&self, position: i64) -> i64 {
+        let cycle = self.work_seconds + self.break_seconds;
+        (position / cycle) * self.break_seconds + (position % cycle - self.work_seconds).max(0)
+    }
+
+    /// The interval `position` seconds of running time fall into
+    pub fn interval_at(&self, position: i64) -> TimeboxInterval {
+        let cycle = self.work_seconds + self.break_seconds;
+        let offset = position % cycle;
+        let (kind, remaining_seconds) = if offset < self.work_seconds {
+            (IntervalKind::Work, self.work_seconds - offset)
+        } else {
+            (IntervalKind::Break, cycle - offset)
+        };
+        TimeboxInterval {
+            kind,
+            cycle: position / cycle + 1,
+            remaining_seconds,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IntervalKind {
+    Work,
+    Break,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimeboxInterval {
+    pub kind: IntervalKind,
+    pub cycle: i64, // 1-based work + break round
+    pub remaining_seconds: i64,
+}
+
+/// Where the time since the last heartbeat went
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GapSplit {
+    pub active: i64,
+    pub breaks: i64,
+    pub idle: i64,
+}
+
+/// One sitting, timed on the backend from the frontend's heartbeats
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StudySession {
+    pub id: String,
+    pub status: SessionStatus,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub last_heartbeat_at: String,
+    pub active_seconds: i64, // Heartbeat gaps up to IDLE_GAP_SECONDS, breaks excluded
+    pub idle_seconds: i64,   // Longer gaps, paused time not included
+    pub break_seconds: i64,  // Timeboxed sessions only
+    pub work_interval_seconds: Option<i64>, // None = free session
+    pub break_interval_seconds: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[sqlx(skip)]
+    pub interval: Option<TimeboxInterval>, // As of the last heartbeat
+}
+
+impl StudySession {
+    pub fn timebox(&self) -> Option<Timebox> {
+        Some(Timebox {
+            work_seconds: self.work_interval_seconds?,
+            break_seconds: self.break_interval_seconds?,
+        })
+    }
+
+    /// Fill in the current work/break interval of a timeboxed session
+    pub fn with_interval(mut self) -> Self {
+        let position = self.active_seconds + self.break_seconds;
+        self.interval = self.timebox().map(|t| t.interval_at(position));
+        self
+    }
+
+    /// Split the time since the last heartbeat. Only a running session accrues
+    /// time; a gap is either all idle or study time, and in a timeboxed session
+    /// the part of it that falls into breaks is break time.
+    pub fn split_gap(&self, now: DateTime<Utc>) -> GapSplit {
+        if self.status != SessionStatus::Running {
+            return GapSplit::default();
+        }
+        let gap = DateTime::parse_from_rfc3339(&self.last_heartbeat_at)
+            .map(|t| (now - t.with_timezone(&Utc)).num_seconds().max(0))
+            .unwrap_or(0);
+        if gap > IDLE_GAP_SECONDS {
+            return GapSplit {
+                idle: gap,
+                ..GapSplit::default()
+            };
+        }
+
+        let position = self.active_seconds + self.break_seconds;
+        let breaks = self.timebox().map_or(0, |t| {
+            t.break_time_before(position + gap) - t.break_time_before(position)
+        });
+        GapSplit {
+            active: gap - breaks,
+            breaks,
+            idle: 0,
+        }
+    }
+}
+
+/// A session with the attempts and reviews made while it was open
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct StudySessionSummary {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub session: StudySession,
+    pub attempt_count: i64,
+    pub solved_count: i64,
+    pub over_time_count: i64, // Attempts that ran over their time budget
+    pub review_count: i64,
+    pub review_seconds: i64, // Answer time reported with the reviews
+    pub problem_count: i64,  // Distinct problems attempted or reviewed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_heartbeat_gaps_are_idle() {
+        let start = DateTime::parse_from_rfc3339("2024-03-01T10:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut session = StudySession {
+            id: "s".into(),
+            status: SessionStatus::Running,
+            started_at: start.to_rfc3339(),
+            ended_at: None,
+            last_heartbeat_at: start.to_rfc3339(),
+            active_seconds: 0,
+            idle_seconds: 0,
+            break_seconds: 0,
+            work_interval_seconds: None,
+            break_interval_seconds: None,
+            created_at: start.to_rfc3339(),
+            updated_at: start.to_rfc3339(),
+            interval: None,
+        };
+        let split = |active, breaks, idle| GapSplit {
+            active,
+            breaks,
+            idle,
+        };
+
+        let at = |s: i64| start + chrono::Duration::seconds(s);
+        assert_eq!(session.split_gap(at(30)), split(30, 0, 0));
+        assert_eq!(
+            session.split_gap(at(IDLE_GAP_SECONDS)),
+            split(IDLE_GAP_SECONDS, 0, 0)
+        );
+        assert_eq!(session.split_gap(at(3600)), split(0, 0, 3600));
+        // A clock that went backwards adds nothing
+        assert_eq!(session.split_gap(at(-60)), split(0, 0, 0));
+
+        // 25/5 pomodoro, 60s before the first break: the rest of the gap is break
+        session.work_interval_seconds = Some(25 * 60);
+        session.break_interval_seconds = Some(5 * 60);
+        session.active_seconds = 24 * 60;
+        assert_eq!(session.split_gap(at(100)), split(60, 40, 0));
+        let session = StudySession {
+            active_seconds: 25 * 60,
+            break_seconds: 40,
+            ..session
+        }
+        .with_interval();
+        let interval = session.interval.unwrap();
+        assert_eq!(
+            (interval.kind, interval.cycle, interval.remaining_seconds),
+            (IntervalKind::Break, 1, 260)
+        );
+
+        let paused = StudySession {
+            status: SessionStatus::Paused,
+            ..session
+        };
+        assert_eq!(paused.split_gap(at(30)), split(0, 0, 0));
+    }
+}
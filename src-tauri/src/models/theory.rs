@@ -1,10 +1,11 @@
+use super::study_phase::StudyPhase;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Theory {
     pub id: String,
     pub topic_id: String,
-    pub phase_number: i32, // 1, 2, 3, 4 (mapped as 1.1, 1.2, 2.1, 2.2 in UI)
+    pub phase_number: i32, // 1-6, see THEORY_PHASES
     pub title: String,
     pub content: Option<String>,
     pub created_at: String,
@@ -20,24 +21,76 @@ pub struct CreateTheoryRequest {
     pub content: Option<String>,
 }
 
-#[allow(dead_code)]
+/// Every valid theory `phase_number`, its label in the UI and the study phase that
+/// shows it. The only place this mapping is defined.
+pub const THEORY_PHASES: [(i32, &str, StudyPhase); 6] = [
+    (1, "1.1", StudyPhase::Decode),
+    (2, "1.2", StudyPhase::Decode),
+    (3, "2.1", StudyPhase::Encode),
+    (4, "2.2", StudyPhase::Encode),
+    (5, "3", StudyPhase::Recall),
+    (6, "4", StudyPhase::Reflect),
+];
+
 #[derive(Debug, Serialize)]
 pub struct TheoryWithPhase {
     pub theory: Theory,
     pub phase_label: String, // "1.1", "1.2", etc
+    pub study_phase: StudyPhase,
 }
 
-#[allow(dead_code)]
 impl Theory {
     pub fn get_phase_label(&self) -> String {
-        match self.phase_number {
-            1 => "1.1".to_string(),
-            2 => "1.2".to_string(),
-            3 => "2.1".to_string(),
-            4 => "2.2".to_string(),
-            5 => "3".to_string(),
-            6 => "4".to_string(),
-            _ => "Unknown".to_string(),
+        Self::phase_label(self.phase_number)
+            .unwrap_or("Unknown")
+            .to_string()
+    }
+
+    pub fn phase_label(phase_number: i32) -> Option<&'static str> {
+        THEORY_PHASES
+            .iter()
+            .find(|(n, _, _)| *n == phase_number)
+            .map(|(_, label, _)| *label)
+    }
+
+    /// Study phase showing this page; None for a phase number outside THEORY_PHASES
+    pub fn study_phase(&self) -> Option<StudyPhase> {
+        THEORY_PHASES
+            .iter()
+            .find(|(n, _, _)| *n == self.phase_number)
+            .map(|(_, _, phase)| phase.clone())
+    }
+
+    pub fn with_phase(self) -> Option<TheoryWithPhase> {
+        let study_phase = self.study_phase()?;
+        Some(TheoryWithPhase {
+            phase_label: self.get_phase_label(),
+            study_phase,
+            theory: self,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_study_phase_has_theory_pages() {
+        for phase in [
+            StudyPhase::Decode,
+            StudyPhase::Encode,
+            StudyPhase::Recall,
+            StudyPhase::Reflect,
+        ] {
+            let numbers = phase.theory_phase_numbers();
+            assert!(!numbers.is_empty());
+            // Labels start with the study phase's own number: Decode shows 1.x only
+            for n in numbers {
+                let label = Theory::phase_label(n).unwrap();
+                assert!(label.starts_with(&phase.to_number().to_string()));
+            }
         }
+        assert_eq!(Theory::phase_label(7), None);
     }
 }
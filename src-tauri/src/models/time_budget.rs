@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+/// Default budget of every problem with this difficulty (1-5)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DifficultyTimeBudget {
+    pub difficulty: i32,
+    pub budget_seconds: i64,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetSource {
+    Problem,    // Set on the problem itself
+    Difficulty, // Default of the problem's difficulty
+}
+
+/// The time an attempt at a problem should take at most
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimeBudget {
+    pub problem_id: String,
+    pub budget_seconds: i64,
+    pub source: BudgetSource,
+}
+
+impl TimeBudget {
+    /// The problem's own budget wins over its difficulty's; None if neither is set
+    pub fn resolve(
+        problem_id: String,
+        problem_budget: Option<i64>,
+        difficulty_budget: Option<i64>,
+    ) -> Option<Self> {
+        let (budget_seconds, source) = match (problem_budget, difficulty_budget) {
+            (Some(seconds), _) => (seconds, BudgetSource::Problem),
+            (None, Some(seconds)) => (seconds, BudgetSource::Difficulty),
+            (None, None) => return None,
+        };
+        Some(TimeBudget {
+            problem_id,
+            budget_seconds,
+            source,
+        })
+    }
+
+    pub fn is_exceeded_by(&self, time_spent_seconds: i64) -> bool {
+        time_spent_seconds > self.budget_seconds
+    }
+
+    /// Description of the Over Time Limit error logged for an attempt
+    pub fn overrun_description(&self, time_spent_seconds: i64) -> String {
+        format!(
+            "Took {} against a budget of {}",
+            format_duration(time_spent_seconds),
+            format_duration(self.budget_seconds)
+        )
+    }
+}
+
+fn format_duration(seconds: i64) -> String {
+    format!("{}m {:02}s", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problem_budget_overrides_difficulty() {
+        let budget = TimeBudget::resolve("p".into(), Some(300), Some(900)).unwrap();
+        assert_eq!(budget.source, BudgetSource::Problem);
+        assert!(!budget.is_exceeded_by(300));
+        assert!(budget.is_exceeded_by(301));
+        assert_eq!(
+            budget.overrun_description(425),
+            "Took 7m 05s against a budget of 5m 00s"
+        );
+
+        let default = TimeBudget::resolve("p".into(), None, Some(900)).unwrap();
+        assert_eq!(
+            (default.budget_seconds, default.source),
+            (900, BudgetSource::Difficulty)
+        );
+        assert!(TimeBudget::resolve("p".into(), None, None).is_none());
+    }
+}
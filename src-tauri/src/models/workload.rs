@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_SIMULATION_DAYS: u32 = 30;
+pub const DEFAULT_SIMULATION_RUNS: u32 = 20;
+pub const MAX_SIMULATION_DAYS: u32 = 3650;
+pub const MAX_SIMULATION_RUNS: u32 = 1000;
+
+/// Settings to simulate under; unset fields use the current parameters and queue defaults
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SimulationRequest {
+    pub subject_id: Option<String>,
+    pub days: Option<u32>,
+    pub desired_retention: Option<f64>,
+    pub new_per_day: Option<i64>,
+    pub reviews_per_day: Option<i64>, // None = no cap
+    pub runs: Option<u32>,
+    pub seed: Option<u64>, // Same seed, same settings and cards: same result
+}
+
+/// Seconds one answer takes, averaged from the review log
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ReviewCosts {
+    pub new_seconds: f64,
+    pub recall_seconds: f64,
+    pub lapse_seconds: f64,
+}
+
+/// Expected load of one day, averaged over the simulation runs
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyLoad {
+    pub day: u32, // 0 = today
+    pub date: String,
+    pub scheduled: i64, // Cards already due that day (today includes the backlog)
+    pub expected_reviews: f64,
+    pub expected_new: f64,
+    pub expected_lapses: f64,
+    pub expected_minutes: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkloadForecast {
+    pub desired_retention: f64,
+    pub new_per_day: i64,
+    pub reviews_per_day: Option<i64>,
+    pub runs: u32,
+    pub costs: ReviewCosts,
+    pub days: Vec<DailyLoad>,
+    pub total_reviews: f64,
+    pub total_minutes: f64,
+    pub expected_retention: f64, // Share of simulated reviews recalled
+}
@@ -1,5 +1,12 @@
+use crate::error::AppError;
+use crate::models::attempt::{NewAttemptError, SubmitAttemptRequest, SubmittedAttempt};
+use crate::models::error_log::OVER_TIME_LIMIT_ERROR_TYPE;
+use crate::models::fsrs::ReviewRequest;
 use crate::models::Attempt;
-use sqlx::SqlitePool;
+use crate::services::{
+    ErrorService, FsrsParameterService, FsrsService, ProblemService, TimeBudgetService,
+};
+use sqlx::{SqliteConnection, SqlitePool};
 
 pub struct AttemptService;
 
@@ -9,67 +16,166 @@ impl AttemptService {
         problem_id: String,
         is_solved: bool,
         commentary: Option<String>,
-    ) -> Result<Attempt, String> {
+    ) -> Result<Attempt, AppError> {
+        let mut tx = pool.begin().await?;
+        let attempt = Self::record(&mut tx, problem_id, is_solved, commentary, 0).await?;
+        tx.commit().await?;
+
+        Ok(attempt)
+    }
+
+    /// Record an attempt, the errors made and the FSRS review in one transaction:
+    /// either all of it is saved or none of it is. Going over the problem's time
+    /// budget logs an Over Time Limit error unless the caller already did.
+    pub async fn submit(
+        pool: &SqlitePool,
+        req: SubmitAttemptRequest,
+    ) -> Result<SubmittedAttempt, AppError> {
+        let params = match req.rating {
+            Some(_) => {
+                Some(FsrsParameterService::resolve_for_problem(pool, &req.problem_id).await?)
+            }
+            None => None,
+        };
+
+        let time_spent_seconds = req.time_spent_seconds.max(0);
+        let mut new_errors = req.errors;
+
+        let mut tx = pool.begin().await?;
+        if let Some(budget) = TimeBudgetService::resolve(&mut tx, &req.problem_id).await? {
+            let logged = new_errors
+                .iter()
+                .any(|e| e.error_type_id == OVER_TIME_LIMIT_ERROR_TYPE);
+            if budget.is_exceeded_by(time_spent_seconds) && !logged {
+                new_errors.push(NewAttemptError {
+                    error_type_id: OVER_TIME_LIMIT_ERROR_TYPE,
+                    description: Some(budget.overrun_description(time_spent_seconds)),
+                });
+            }
+        }
+
+        let attempt = Self::record(
+            &mut tx,
+            req.problem_id.clone(),
+            req.is_solved,
+            req.commentary,
+            time_spent_seconds,
+        )
+        .await?;
+
+        let mut errors = Vec::with_capacity(new_errors.len());
+        for error in new_errors {
+            errors.push(
+                ErrorService::insert_error(
+                    &mut tx,
+                    attempt.id.clone(),
+                    error.error_type_id,
+                    error.description,
+                )
+                .await?,
+            );
+        }
+        if !errors.is_empty() {
+            ProblemService::recount_unresolved_errors(&mut tx, &req.problem_id).await?;
+        }
+
+        let review = match (req.rating, params) {
+            (Some(rating), Some(params)) => {
+                let review = ReviewRequest {
+                    problem_id: req.problem_id,
+                    attempt_is_solved: req.is_solved,
+                    rating,
+                    time_spent_seconds,
+                    attempt_id: Some(attempt.id.clone()),
+                };
+                Some(FsrsService::review(&mut tx, review, &params).await?)
+            }
+            _ => None,
+        };
+
+        tx.commit().await?;
+
+        Ok(SubmittedAttempt {
+            attempt,
+            errors,
+            review,
+        })
+    }
+
+    /// Insert the attempt, make sure its problem has a card and mark the
+    /// problem solved on success. Only a running study session claims it.
+    async fn record(
+        conn: &mut SqliteConnection,
+        problem_id: String,
+        is_solved: bool,
+        commentary: Option<String>,
+        time_spent_seconds: i64,
+    ) -> Result<Attempt, AppError> {
         let id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
 
         sqlx::query(
-            "INSERT INTO attempts (id, problem_id, is_solved, commentary, created_at, updated_at) 
-             VALUES (?, ?, ?, ?, ?, ?)",
+            "INSERT INTO attempts (id, problem_id, is_solved, commentary, time_spent_seconds, session_id, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, (SELECT id FROM study_sessions WHERE status = 'running'), ?, ?)",
         )
         .bind(&id)
         .bind(&problem_id)
         .bind(is_solved as i32)
         .bind(&commentary)
+        .bind(time_spent_seconds)
         .bind(&now)
         .bind(&now)
-        .execute(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .execute(&mut *conn)
+        .await?;
+
+        FsrsService::get_or_create_card(conn, problem_id.clone()).await?;
+        if is_solved {
+            ProblemService::set_solved(conn, &problem_id).await?;
+        }
 
         Ok(Attempt {
             id,
             problem_id,
             is_solved,
             commentary,
+            time_spent_seconds,
             created_at: now.clone(),
             updated_at: now,
         })
     }
 
-    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<Attempt, String> {
+    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<Attempt, AppError> {
         sqlx::query_as::<_, Attempt>(
-            "SELECT id, problem_id, is_solved, commentary, created_at, updated_at FROM attempts WHERE id = ?"
+            "SELECT id, problem_id, is_solved, commentary, time_spent_seconds, created_at, updated_at FROM attempts WHERE id = ?"
         )
-        .bind(id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| e.to_string())
+        .bind(&id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("attempt", &id))
     }
 
     pub async fn list_by_problem(
         pool: &SqlitePool,
         problem_id: String,
-    ) -> Result<Vec<Attempt>, String> {
+    ) -> Result<Vec<Attempt>, AppError> {
         sqlx::query_as::<_, Attempt>(
-            "SELECT id, problem_id, is_solved, commentary, created_at, updated_at 
+            "SELECT id, problem_id, is_solved, commentary, time_spent_seconds, created_at, updated_at 
              FROM attempts WHERE problem_id = ? ORDER BY created_at DESC",
         )
         .bind(problem_id)
         .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
     }
 
     pub async fn get_count_by_problem(
         pool: &SqlitePool,
         problem_id: String,
-    ) -> Result<i64, String> {
+    ) -> Result<i64, AppError> {
         let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM attempts WHERE problem_id = ?")
             .bind(problem_id)
             .fetch_one(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         Ok(count)
     }
@@ -77,13 +183,12 @@ impl AttemptService {
     pub async fn get_success_count_by_problem(
         pool: &SqlitePool,
         problem_id: String,
-    ) -> Result<i64, String> {
+    ) -> Result<i64, AppError> {
         let (count,): (i64,) =
             sqlx::query_as("SELECT COUNT(*) FROM attempts WHERE problem_id = ? AND is_solved = 1")
                 .bind(problem_id)
                 .fetch_one(pool)
-                .await
-                .map_err(|e| e.to_string())?;
+                .await?;
 
         Ok(count)
     }
@@ -92,7 +197,7 @@ impl AttemptService {
         pool: &SqlitePool,
         id: String,
         commentary: String,
-    ) -> Result<Attempt, String> {
+    ) -> Result<Attempt, AppError> {
         let now = chrono::Utc::now().to_rfc3339();
 
         sqlx::query("UPDATE attempts SET commentary = ?, updated_at = ? WHERE id = ?")
@@ -100,9 +205,160 @@ impl AttemptService {
             .bind(&now)
             .bind(&id)
             .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         Self::get_by_id(pool, id).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{seed_problem, test_pool};
+    use crate::models::fsrs::UpdateFsrsParameterSetRequest;
+
+    #[tokio::test]
+    async fn test_submit_is_all_or_nothing() {
+        let pool = test_pool().await;
+        let problem = seed_problem(&pool).await;
+
+        let submit = |rating| SubmitAttemptRequest {
+            problem_id: problem.id.clone(),
+            is_solved: true,
+            commentary: None,
+            errors: vec![NewAttemptError {
+                error_type_id: 1,
+                description: None,
+            }],
+            rating: Some(rating),
+            time_spent_seconds: 60,
+        };
+
+        // The review fails after the attempt and error were written: nothing sticks
+        let failed = AttemptService::submit(&pool, submit(0)).await;
+        assert!(matches!(failed, Err(AppError::Validation { .. })));
+        let (attempts, errors): (i64, i64) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM attempts), (SELECT COUNT(*) FROM attempt_errors)",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((attempts, errors), (0, 0));
+        assert!(
+            !ProblemService::get_by_id(&pool, problem.id.clone())
+                .await
+                .unwrap()
+                .is_solved
+        );
+
+        let submitted = AttemptService::submit(&pool, submit(3)).await.unwrap();
+        assert_eq!(submitted.errors.len(), 1);
+        assert!(submitted.review.is_some());
+        let problem = ProblemService::get_by_id(&pool, problem.id).await.unwrap();
+        assert_eq!(problem.total_unresolved_errors, 1);
+
+        ErrorService::resolve_error(&pool, submitted.errors[0].id.clone())
+            .await
+            .unwrap();
+        let problem = ProblemService::get_by_id(&pool, problem.id).await.unwrap();
+        assert_eq!(problem.total_unresolved_errors, 0);
+    }
+
+    #[tokio::test]
+    async fn test_over_budget_attempt_logs_over_time_error() {
+        let pool = test_pool().await;
+        let problem = seed_problem(&pool).await;
+        let submit = |time_spent_seconds, errors| SubmitAttemptRequest {
+            problem_id: problem.id.clone(),
+            is_solved: true,
+            commentary: None,
+            errors,
+            rating: None,
+            time_spent_seconds,
+        };
+        let error_types = |submitted: SubmittedAttempt| -> Vec<i32> {
+            submitted.errors.iter().map(|e| e.error_type_id).collect()
+        };
+
+        // Difficulty 2 defaults to 15 minutes
+        let within = AttemptService::submit(&pool, submit(900, vec![])).await;
+        assert!(error_types(within.unwrap()).is_empty());
+
+        TimeBudgetService::set_for_problem(&pool, problem.id.clone(), Some(300))
+            .await
+            .unwrap();
+        let over = AttemptService::submit(&pool, submit(425, vec![]))
+            .await
+            .unwrap();
+        assert_eq!(over.attempt.time_spent_seconds, 425);
+        assert_eq!(
+            over.errors[0].description.as_deref(),
+            Some("Took 7m 05s against a budget of 5m 00s")
+        );
+        assert_eq!(error_types(over), vec![OVER_TIME_LIMIT_ERROR_TYPE]);
+
+        // Already logged by the caller: not doubled
+        let logged = vec![NewAttemptError {
+            error_type_id: OVER_TIME_LIMIT_ERROR_TYPE,
+            description: None,
+        }];
+        let over = AttemptService::submit(&pool, submit(600, logged)).await;
+        assert_eq!(error_types(over.unwrap()), vec![OVER_TIME_LIMIT_ERROR_TYPE]);
+    }
+
+    #[tokio::test]
+    async fn test_over_budget_review_is_not_scheduled_further_out() {
+        let pool = test_pool().await;
+        // Exact intervals: fuzz and load balancing could spread the two apart either way
+        let global = FsrsParameterService::get_global(&pool).await.unwrap();
+        FsrsParameterService::update(
+            &pool,
+            global.id,
+            UpdateFsrsParameterSetRequest {
+                enable_fuzz: Some(false),
+                enable_load_balance: Some(false),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let on_time = seed_problem(&pool).await;
+        let over = ProblemService::create(
+            &pool,
+            on_time.topic_id.clone(),
+            None,
+            "Sandwich".into(),
+            None,
+            None,
+            on_time.difficulty,
+        )
+        .await
+        .unwrap();
+        let review = |problem_id: &str, time_spent_seconds| {
+            AttemptService::submit(
+                &pool,
+                SubmitAttemptRequest {
+                    problem_id: problem_id.to_string(),
+                    is_solved: true,
+                    commentary: None,
+                    errors: vec![],
+                    rating: Some(3),
+                    time_spent_seconds,
+                },
+            )
+        };
+
+        // Same history for both, then the same rating with one over its 15 minutes
+        let mut results = vec![];
+        for (problem, time_spent) in [(&on_time, 600), (&over, 1800)] {
+            review(&problem.id, 600).await.unwrap();
+            let submitted = review(&problem.id, time_spent).await.unwrap();
+            results.push((submitted.errors.len(), submitted.review.unwrap()));
+        }
+        let (on_time_errors, on_time) = &results[0];
+        let (over_errors, over) = &results[1];
+        assert_eq!((*on_time_errors, *over_errors), (0, 1));
+        assert!(over.new_stability < on_time.new_stability);
+        assert!(over.new_interval_minutes <= on_time.new_interval_minutes);
+    }
+}
@@ -1,24 +1,25 @@
+use crate::error::AppError;
 use crate::models::{AttemptError, ErrorType};
-use sqlx::SqlitePool;
+use crate::services::ProblemService;
+use sqlx::{SqliteConnection, SqlitePool};
 
 pub struct ErrorService;
 
 impl ErrorService {
     /// Initialize default error types on first run
-    pub async fn init_default_error_types(pool: &SqlitePool) -> Result<(), String> {
+    pub async fn init_default_error_types(pool: &SqlitePool) -> Result<(), AppError> {
         let defaults = crate::models::error_log::get_default_error_types();
 
         for (id, name, desc, multiplier) in defaults {
             let now = chrono::Utc::now().to_rfc3339();
 
             // Skip if already exists
-            let exists: Result<(i32,), _> =
-                sqlx::query_as("SELECT id FROM error_types WHERE id = ?")
-                    .bind(id)
-                    .fetch_one(pool)
-                    .await;
+            let exists: Option<(i32,)> = sqlx::query_as("SELECT id FROM error_types WHERE id = ?")
+                .bind(id)
+                .fetch_optional(pool)
+                .await?;
 
-            if exists.is_ok() {
+            if exists.is_some() {
                 continue;
             }
 
@@ -32,19 +33,36 @@ impl ErrorService {
             .bind(multiplier)
             .bind(&now)
             .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
         }
 
         Ok(())
     }
 
+    /// Log an error and refresh its problem's unresolved count in one transaction
     pub async fn log_error(
         pool: &SqlitePool,
         attempt_id: String,
         error_type_id: i32,
         description: Option<String>,
-    ) -> Result<AttemptError, String> {
+    ) -> Result<AttemptError, AppError> {
+        let mut tx = pool.begin().await?;
+
+        let problem_id = Self::problem_of_attempt(&mut tx, &attempt_id).await?;
+        let error = Self::insert_error(&mut tx, attempt_id, error_type_id, description).await?;
+        ProblemService::recount_unresolved_errors(&mut tx, &problem_id).await?;
+
+        tx.commit().await?;
+        Ok(error)
+    }
+
+    /// Insert step of a larger write; the caller recounts unresolved errors
+    pub(crate) async fn insert_error(
+        conn: &mut SqliteConnection,
+        attempt_id: String,
+        error_type_id: i32,
+        description: Option<String>,
+    ) -> Result<AttemptError, AppError> {
         let id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
 
@@ -58,9 +76,8 @@ impl ErrorService {
         .bind(&description)
         .bind(&now)
         .bind(&now)
-        .execute(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .execute(conn)
+        .await?;
 
         Ok(AttemptError {
             id,
@@ -73,36 +90,60 @@ impl ErrorService {
         })
     }
 
+    /// Resolve an error and refresh its problem's unresolved count in one transaction
     pub async fn resolve_error(
         pool: &SqlitePool,
         error_id: String,
-    ) -> Result<AttemptError, String> {
+    ) -> Result<AttemptError, AppError> {
         let now = chrono::Utc::now().to_rfc3339();
+        let mut tx = pool.begin().await?;
 
         sqlx::query("UPDATE attempt_errors SET is_resolved = 1, updated_at = ? WHERE id = ?")
             .bind(&now)
             .bind(&error_id)
-            .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .execute(&mut *tx)
+            .await?;
 
-        Self::get_error_by_id(pool, error_id).await
+        let error = Self::get_error_by_id(&mut tx, error_id).await?;
+
+        let problem_id = Self::problem_of_attempt(&mut tx, &error.attempt_id).await?;
+        ProblemService::recount_unresolved_errors(&mut tx, &problem_id).await?;
+
+        tx.commit().await?;
+        Ok(error)
     }
 
-    pub async fn get_error_by_id(pool: &SqlitePool, id: String) -> Result<AttemptError, String> {
+    async fn problem_of_attempt(
+        conn: &mut SqliteConnection,
+        attempt_id: &str,
+    ) -> Result<String, AppError> {
+        let (problem_id,): (String,) =
+            sqlx::query_as("SELECT problem_id FROM attempts WHERE id = ?")
+                .bind(attempt_id)
+                .fetch_optional(conn)
+                .await?
+                .ok_or_else(|| AppError::not_found("attempt", attempt_id))?;
+
+        Ok(problem_id)
+    }
+
+    pub async fn get_error_by_id(
+        conn: &mut SqliteConnection,
+        id: String,
+    ) -> Result<AttemptError, AppError> {
         sqlx::query_as::<_, AttemptError>(
             "SELECT id, attempt_id, error_type_id, description, is_resolved, created_at, updated_at FROM attempt_errors WHERE id = ?"
         )
-        .bind(id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| e.to_string())
+        .bind(&id)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("attempt_error", &id))
     }
 
     pub async fn get_errors_by_attempt(
         pool: &SqlitePool,
         attempt_id: String,
-    ) -> Result<Vec<AttemptError>, String> {
+    ) -> Result<Vec<AttemptError>, AppError> {
         sqlx::query_as::<_, AttemptError>(
             "SELECT id, attempt_id, error_type_id, description, is_resolved, created_at, updated_at 
              FROM attempt_errors WHERE attempt_id = ? ORDER BY created_at DESC"
@@ -110,13 +151,31 @@ impl ErrorService {
         .bind(attempt_id)
         .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+    }
+
+    /// Every error ever logged on a problem, resolved or not, newest first
+    pub async fn get_errors_by_problem(
+        pool: &SqlitePool,
+        problem_id: String,
+    ) -> Result<Vec<AttemptError>, AppError> {
+        sqlx::query_as::<_, AttemptError>(
+            "SELECT ae.id, ae.attempt_id, ae.error_type_id, ae.description, ae.is_resolved, ae.created_at, ae.updated_at
+             FROM attempt_errors ae
+             JOIN attempts a ON ae.attempt_id = a.id
+             WHERE a.problem_id = ?
+             ORDER BY ae.created_at DESC"
+        )
+        .bind(problem_id)
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::from)
     }
 
     pub async fn get_unresolved_errors_by_problem(
         pool: &SqlitePool,
         problem_id: String,
-    ) -> Result<Vec<AttemptError>, String> {
+    ) -> Result<Vec<AttemptError>, AppError> {
         sqlx::query_as::<_, AttemptError>(
             "SELECT ae.id, ae.attempt_id, ae.error_type_id, ae.description, ae.is_resolved, ae.created_at, ae.updated_at
              FROM attempt_errors ae
@@ -127,43 +186,43 @@ impl ErrorService {
         .bind(problem_id)
         .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
     }
 
-    pub async fn get_error_types(pool: &SqlitePool) -> Result<Vec<ErrorType>, String> {
+    pub async fn get_error_types(pool: &SqlitePool) -> Result<Vec<ErrorType>, AppError> {
         sqlx::query_as::<_, ErrorType>(
             "SELECT id, name, description, multiplier, created_at FROM error_types ORDER BY multiplier DESC"
         )
         .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
     }
 
     #[allow(dead_code)]
-    pub async fn get_error_type_by_id(pool: &SqlitePool, id: i32) -> Result<ErrorType, String> {
+    pub async fn get_error_type_by_id(pool: &SqlitePool, id: i32) -> Result<ErrorType, AppError> {
         sqlx::query_as::<_, ErrorType>(
             "SELECT id, name, description, multiplier, created_at FROM error_types WHERE id = ?",
         )
         .bind(id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| e.to_string())
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("error_type", id))
     }
 
-    pub async fn count_unresolved_by_problem(
-        pool: &SqlitePool,
-        problem_id: String,
-    ) -> Result<i32, String> {
-        let (count,): (i32,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM attempt_errors ae
-             JOIN attempts a ON ae.attempt_id = a.id
-             WHERE a.problem_id = ? AND ae.is_resolved = 0",
+    /// Sum of the type multipliers of every error logged on an attempt (0 if none)
+    pub async fn get_attempt_error_weight(
+        conn: &mut SqliteConnection,
+        attempt_id: &str,
+    ) -> Result<f64, AppError> {
+        let (weight,): (f64,) = sqlx::query_as(
+            "SELECT COALESCE(SUM(et.multiplier), 0.0) FROM attempt_errors ae
+             JOIN error_types et ON ae.error_type_id = et.id
+             WHERE ae.attempt_id = ?",
         )
-        .bind(&problem_id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .bind(attempt_id)
+        .fetch_one(conn)
+        .await?;
 
-        Ok(count)
+        Ok(weight)
     }
 }
@@ -0,0 +1,359 @@
+use crate::error::AppError;
+use crate::models::fsrs::{OptimizationResult, Rating, WEIGHT_BOUNDS};
+use crate::models::review_log::ReviewKind;
+use crate::services::{FsrsParameterService, FsrsService};
+use sqlx::SqlitePool;
+
+pub struct FsrsOptimizer;
+
+/// One card's history as (rating, whole days since the previous review)
+type ReviewSequence = Vec<(Rating, i32)>;
+
+/// Review log row: (card_id, rating, kind, state_before, elapsed_days)
+type LogRow = (String, i32, ReviewKind, String, i32);
+
+/// Reviews with a prediction to score (i.e. not first or same-day) required before fitting
+const MIN_REVIEWS: usize = 64;
+const ITERATIONS: usize = 200;
+
+// Adam hyper-parameters, as used by the upstream FSRS optimizer
+const LEARNING_RATE: f64 = 0.04;
+const BETA_1: f64 = 0.9;
+const BETA_2: f64 = 0.999;
+const EPSILON: f64 = 1e-8;
+
+impl FsrsOptimizer {
+    /// Fit the weights to the review log and store them in the parameter set in effect:
+    /// the subject's override when `subject_id` is given (trained on that subject's
+    /// reviews only), the global set otherwise.
+    /// The stored weights are only replaced when they fit the history better.
+    pub async fn optimize(
+        pool: &SqlitePool,
+        subject_id: Option<String>,
+    ) -> Result<OptimizationResult, AppError> {
+        let set = match &subject_id {
+            Some(subject_id) => {
+                FsrsParameterService::get_subject_override(pool, subject_id.clone())
+                    .await?
+                    .ok_or_else(|| {
+                        AppError::not_found(
+                            "fsrs_parameter_set",
+                            format!("for subject {}", subject_id),
+                        )
+                    })?
+            }
+            None => FsrsParameterService::get_global(pool).await?,
+        };
+        let current = set.to_parameters().w;
+        let sequences = Self::load_sequences(pool, subject_id).await?;
+
+        let review_count = Self::predictions(&current, &sequences).len();
+        if review_count < MIN_REVIEWS {
+            return Err(AppError::validation(
+                "review_count",
+                format!(
+                    "Not enough review history to optimize: {} usable reviews, need at least {}",
+                    review_count, MIN_REVIEWS
+                ),
+            ));
+        }
+
+        let card_count = sequences.len() as i64;
+        let (trained, before, after) = tokio::task::spawn_blocking(move || {
+            let before = Self::evaluate(&current, &sequences);
+            let trained = Self::train(&current, &sequences);
+            let after = Self::evaluate(&trained, &sequences);
+            (trained, before, after)
+        })
+        .await?;
+
+        let (weights, after) = if after.0 < before.0 {
+            (trained, after)
+        } else {
+            (current, before)
+        };
+
+        FsrsParameterService::save_trained_weights(
+            pool,
+            set.id.clone(),
+            &weights,
+            review_count as i64,
+            after.0,
+            after.1,
+        )
+        .await?;
+
+        Ok(OptimizationResult {
+            parameter_set_id: set.id,
+            card_count,
+            review_count: review_count as i64,
+            weights_before: current.to_vec(),
+            weights_after: weights.to_vec(),
+            log_loss_before: before.0,
+            log_loss_after: after.0,
+            rmse_before: before.1,
+            rmse_after: after.1,
+        })
+    }
+
+    /// Group the review log into per-card sequences. Cards whose log does not start
+    /// from the `new` state (reviewed before logging existed) cannot be replayed.
+    /// Manual actions are skipped, except that a reset or forget starts a new sequence.
+    async fn load_sequences(
+        pool: &SqlitePool,
+        subject_id: Option<String>,
+    ) -> Result<Vec<ReviewSequence>, AppError> {
+        let rows: Vec<LogRow> = sqlx::query_as(
+            "SELECT r.card_id, r.rating, r.kind, r.state_before, r.elapsed_days FROM fsrs_reviews r
+             JOIN problems p ON r.problem_id = p.id
+             JOIN topics t ON p.topic_id = t.id
+             WHERE ((r.kind = 'review' AND r.rating BETWEEN 1 AND 4) OR r.kind IN ('reset', 'forget'))
+               AND (? IS NULL OR t.subject_id = ?)
+             ORDER BY r.card_id, r.reviewed_at, r.rowid",
+        )
+        .bind(&subject_id)
+        .bind(&subject_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(Self::group_sequences(rows))
+    }
+
+    /// Split rows ordered by card and time into replayable sequences
+    fn group_sequences(rows: Vec<LogRow>) -> Vec<ReviewSequence> {
+        let mut sequences = Vec::new();
+        let mut current_card: Option<String> = None;
+        let mut current: Option<ReviewSequence> = None;
+
+        for (card_id, rating, kind, state_before, elapsed_days) in rows {
+            if current_card.as_ref() != Some(&card_id) {
+                sequences.extend(current.take());
+                current = (kind == ReviewKind::Review && state_before == "new").then(Vec::new);
+                current_card = Some(card_id);
+            }
+            // The card starts over as new: what follows is a fresh history
+            if kind != ReviewKind::Review {
+                sequences.extend(current.take().filter(|seq| !seq.is_empty()));
+                current = Some(Vec::new());
+                continue;
+            }
+            if let (Some(seq), Some(rating)) = (current.as_mut(), Rating::from_u8(rating as u8)) {
+                seq.push((rating, elapsed_days));
+            }
+        }
+        sequences.extend(current);
+
+        sequences
+    }
+
+    /// Replay every sequence with `w`, yielding (predicted retrievability, recalled) for
+    /// each review that happened at least a day after the previous one
+    fn predictions(w: &[f64; 19], sequences: &[ReviewSequence]) -> Vec<(f64, f64)> {
+        let mut out = Vec::new();
+
+        for seq in sequences {
+            let mut memory = None;
+            for &(rating, elapsed_days) in seq {
+                if let Some((stability, _)) = memory {
+                    if elapsed_days > 0 {
+                        let p = FsrsService::retrievability(elapsed_days as f64, stability);
+                        let recalled = if rating == Rating::Again { 0.0 } else { 1.0 };
+                        out.push((p, recalled));
+                    }
+                }
+                memory = Some(FsrsService::next_memory_state(
+                    w,
+                    memory,
+                    elapsed_days,
+                    rating,
+                ));
+            }
+        }
+
+        out
+    }
+
+    fn log_loss(w: &[f64; 19], sequences: &[ReviewSequence]) -> f64 {
+        let predictions = Self::predictions(w, sequences);
+        if predictions.is_empty() {
+            return 0.0;
+        }
+
+        let total: f64 = predictions
+            .iter()
+            .map(|&(p, y)| {
+                let p = p.clamp(1e-6, 1.0 - 1e-6);
+                -(y * p.ln() + (1.0 - y) * (1.0 - p).ln())
+            })
+            .sum();
+
+        total / predictions.len() as f64
+    }
+
+    /// (log-loss, RMSE) of the predictions. RMSE is computed over 20 retrievability
+    /// bins, weighted by bin size, like the upstream "RMSE (bins)" metric.
+    pub fn evaluate(w: &[f64; 19], sequences: &[ReviewSequence]) -> (f64, f64) {
+        let predictions = Self::predictions(w, sequences);
+        if predictions.is_empty() {
+            return (0.0, 0.0);
+        }
+
+        let mut bins = [(0.0, 0.0, 0.0); 20];
+        for &(p, y) in &predictions {
+            let bin = &mut bins[((p * 20.0) as usize).min(19)];
+            bin.0 += p;
+            bin.1 += y;
+            bin.2 += 1.0;
+        }
+        let squared_error: f64 = bins
+            .iter()
+            .filter(|(_, _, n)| *n > 0.0)
+            .map(|(p, y, n)| (p / n - y / n).powi(2) * n)
+            .sum();
+        let rmse = (squared_error / predictions.len() as f64).sqrt();
+
+        (Self::log_loss(w, sequences), rmse)
+    }
+
+    /// Full-batch Adam on the log-loss, with a central-difference gradient
+    pub fn train(initial: &[f64; 19], sequences: &[ReviewSequence]) -> [f64; 19] {
+        let mut w = *initial;
+        Self::clip(&mut w);
+
+        let mut m = [0.0; 19];
+        let mut v = [0.0; 19];
+        let mut best = (Self::log_loss(&w, sequences), w);
+
+        for t in 1..=ITERATIONS {
+            let grad = Self::gradient(&w, sequences);
+            for i in 0..19 {
+                m[i] = BETA_1 * m[i] + (1.0 - BETA_1) * grad[i];
+                v[i] = BETA_2 * v[i] + (1.0 - BETA_2) * grad[i] * grad[i];
+                let m_hat = m[i] / (1.0 - BETA_1.powi(t as i32));
+                let v_hat = v[i] / (1.0 - BETA_2.powi(t as i32));
+                w[i] -= LEARNING_RATE * m_hat / (v_hat.sqrt() + EPSILON);
+            }
+            Self::clip(&mut w);
+
+            let loss = Self::log_loss(&w, sequences);
+            if loss < best.0 {
+                best = (loss, w);
+            }
+        }
+
+        best.1
+    }
+
+    fn gradient(w: &[f64; 19], sequences: &[ReviewSequence]) -> [f64; 19] {
+        let mut grad = [0.0; 19];
+        for i in 0..19 {
+            let h = 1e-5 * w[i].abs().max(1.0);
+            let mut up = *w;
+            let mut down = *w;
+            up[i] += h;
+            down[i] -= h;
+            grad[i] =
+                (Self::log_loss(&up, sequences) - Self::log_loss(&down, sequences)) / (2.0 * h);
+        }
+        grad
+    }
+
+    fn clip(w: &mut [f64; 19]) {
+        for (value, (low, high)) in w.iter_mut().zip(WEIGHT_BOUNDS) {
+            *value = value.clamp(low, high);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::fsrs::DEFAULT_W;
+
+    /// Cards that are remembered far better than the default weights predict:
+    /// four out of five survive a month-long gap after a single Good review.
+    fn strong_memory_history() -> Vec<ReviewSequence> {
+        (0..100)
+            .map(|k| {
+                let late = if k % 5 == 0 {
+                    Rating::Again
+                } else {
+                    Rating::Good
+                };
+                vec![(Rating::Good, 0), (Rating::Good, 10), (late, 30)]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_group_sequences_around_manual_actions() {
+        let row = |card: &str, rating, kind, state: &str, days| {
+            (card.to_string(), rating, kind, state.to_string(), days)
+        };
+        let rows = vec![
+            // Reviewed from new, reset, then learnt again from scratch
+            row("a", 3, ReviewKind::Review, "new", 0),
+            row("a", 1, ReviewKind::Review, "review", 4),
+            row("a", 0, ReviewKind::Reset, "relearning", 0),
+            row("a", 4, ReviewKind::Review, "new", 2),
+            // History predating the log: dropped until a forget gives a clean start
+            row("b", 3, ReviewKind::Review, "review", 9),
+            row("b", 0, ReviewKind::Forget, "review", 0),
+            row("b", 2, ReviewKind::Review, "new", 1),
+            // Reset before any review: no empty sequence
+            row("c", 0, ReviewKind::Reset, "new", 0),
+            row("c", 3, ReviewKind::Review, "new", 0),
+        ];
+
+        assert_eq!(
+            FsrsOptimizer::group_sequences(rows),
+            vec![
+                vec![(Rating::Good, 0), (Rating::Again, 4)],
+                vec![(Rating::Easy, 2)],
+                vec![(Rating::Hard, 1)],
+                vec![(Rating::Good, 0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_scores_only_spaced_reviews() {
+        let history = vec![
+            vec![(Rating::Good, 0), (Rating::Good, 0), (Rating::Good, 3)],
+            vec![(Rating::Again, 0), (Rating::Again, 5)],
+        ];
+        let predictions = FsrsOptimizer::predictions(&DEFAULT_W, &history);
+        assert_eq!(predictions.len(), 2);
+
+        // Single card: Good, then Good three days later
+        let (log_loss, _) =
+            FsrsOptimizer::evaluate(&DEFAULT_W, &[vec![(Rating::Good, 0), (Rating::Good, 3)]]);
+        assert!((log_loss - 0.100154).abs() < 1e-5);
+
+        let (log_loss, _) = FsrsOptimizer::evaluate(
+            &DEFAULT_W,
+            &[
+                vec![(Rating::Good, 0), (Rating::Good, 3)],
+                vec![(Rating::Again, 0), (Rating::Again, 5)],
+            ],
+        );
+        assert!((log_loss - 0.402175).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_train_improves_fit_within_bounds() {
+        let history = strong_memory_history();
+        let (loss_before, rmse_before) = FsrsOptimizer::evaluate(&DEFAULT_W, &history);
+
+        let trained = FsrsOptimizer::train(&DEFAULT_W, &history);
+        let (loss_after, rmse_after) = FsrsOptimizer::evaluate(&trained, &history);
+
+        assert!(loss_after < loss_before);
+        assert!(rmse_after < rmse_before);
+        for (value, (low, high)) in trained.iter().zip(WEIGHT_BOUNDS) {
+            assert!(*value >= low && *value <= high);
+        }
+        // Initial stability after "Good" must have grown to explain the long retention
+        assert!(trained[2] > DEFAULT_W[2]);
+    }
+}
@@ -0,0 +1,478 @@
+use crate::error::AppError;
+use crate::models::fsrs::{
+    CreateFsrsParameterSetRequest, FsrsParameterSet, FsrsParameters, UpdateFsrsParameterSetRequest,
+    DEFAULT_W, WEIGHT_BOUNDS,
+};
+use crate::services::SubjectService;
+use sqlx::types::Json;
+use sqlx::SqlitePool;
+
+pub struct FsrsParameterService;
+
+const GLOBAL_ID: &str = "global";
+
+impl FsrsParameterService {
+    /// Get the global parameter set, creating it with the FSRS-5 defaults on first use
+    pub async fn get_global(pool: &SqlitePool) -> Result<FsrsParameterSet, AppError> {
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO fsrs_parameters (id, name, subject_id, weights, created_at, updated_at) 
+             VALUES (?, 'Default', NULL, ?, ?, ?)",
+        )
+        .bind(GLOBAL_ID)
+        .bind(Json(DEFAULT_W.to_vec()))
+        .bind(&now)
+        .bind(&now)
+        .execute(pool)
+        .await?;
+
+        Self::get_by_id(pool, GLOBAL_ID.to_string()).await
+    }
+
+    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<FsrsParameterSet, AppError> {
+        sqlx::query_as::<_, FsrsParameterSet>(
+            "SELECT id, name, subject_id, weights, desired_retention, maximum_interval, learning_steps, relearning_steps, enable_fuzz, enable_load_balance, error_policy, leech_threshold, leech_action, total_reviews, log_loss, rmse, created_at, updated_at 
+             FROM fsrs_parameters WHERE id = ?"
+        )
+        .bind(&id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("fsrs_parameter_set", &id))
+    }
+
+    /// Global set first, then subject overrides by name
+    pub async fn list_all(pool: &SqlitePool) -> Result<Vec<FsrsParameterSet>, AppError> {
+        Self::get_global(pool).await?;
+
+        sqlx::query_as::<_, FsrsParameterSet>(
+            "SELECT id, name, subject_id, weights, desired_retention, maximum_interval, learning_steps, relearning_steps, enable_fuzz, enable_load_balance, error_policy, leech_threshold, leech_action, total_reviews, log_loss, rmse, created_at, updated_at 
+             FROM fsrs_parameters ORDER BY subject_id IS NOT NULL, name"
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// Override for a subject, if one exists
+    pub async fn get_subject_override(
+        pool: &SqlitePool,
+        subject_id: String,
+    ) -> Result<Option<FsrsParameterSet>, AppError> {
+        sqlx::query_as::<_, FsrsParameterSet>(
+            "SELECT id, name, subject_id, weights, desired_retention, maximum_interval, learning_steps, relearning_steps, enable_fuzz, enable_load_balance, error_policy, leech_threshold, leech_action, total_reviews, log_loss, rmse, created_at, updated_at 
+             FROM fsrs_parameters WHERE subject_id = ?"
+        )
+        .bind(subject_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// Parameter set in effect for a subject: its override, else the global set
+    pub async fn get_for_subject(
+        pool: &SqlitePool,
+        subject_id: String,
+    ) -> Result<FsrsParameterSet, AppError> {
+        match Self::get_subject_override(pool, subject_id).await? {
+            Some(set) => Ok(set),
+            None => Self::get_global(pool).await,
+        }
+    }
+
+    /// Parameters the scheduler should use for a problem, resolved through its subject
+    pub async fn resolve_for_problem(
+        pool: &SqlitePool,
+        problem_id: &str,
+    ) -> Result<FsrsParameters, AppError> {
+        let subject: Option<(String,)> = sqlx::query_as(
+            "SELECT t.subject_id FROM problems p 
+             JOIN topics t ON p.topic_id = t.id 
+             WHERE p.id = ?",
+        )
+        .bind(problem_id)
+        .fetch_optional(pool)
+        .await?;
+
+        let set = match subject {
+            Some((subject_id,)) => Self::get_for_subject(pool, subject_id).await?,
+            None => Self::get_global(pool).await?,
+        };
+
+        Ok(set.to_parameters())
+    }
+
+    /// Create a subject override. Unspecified fields are copied from the global set.
+    pub async fn create(
+        pool: &SqlitePool,
+        req: CreateFsrsParameterSetRequest,
+    ) -> Result<FsrsParameterSet, AppError> {
+        // Fails early on an unknown subject
+        SubjectService::get_by_id(pool, req.subject_id.clone()).await?;
+        let global = Self::get_global(pool).await?;
+
+        let weights = req.weights.unwrap_or(global.weights);
+        let desired_retention = req.desired_retention.unwrap_or(global.desired_retention);
+        let maximum_interval = req.maximum_interval.unwrap_or(global.maximum_interval);
+        let learning_steps = req.learning_steps.unwrap_or(global.learning_steps);
+        let relearning_steps = req.relearning_steps.unwrap_or(global.relearning_steps);
+        let enable_fuzz = req.enable_fuzz.unwrap_or(global.enable_fuzz);
+        let enable_load_balance = req
+            .enable_load_balance
+            .unwrap_or(global.enable_load_balance);
+        let error_policy = req.error_policy.unwrap_or(global.error_policy);
+        let leech_threshold = req.leech_threshold.unwrap_or(global.leech_threshold);
+        let leech_action = req.leech_action.unwrap_or(global.leech_action);
+        Self::validate(&weights, desired_retention, maximum_interval)?;
+        Self::validate_steps(&learning_steps, &relearning_steps)?;
+        Self::validate_leech_threshold(leech_threshold)?;
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            "INSERT INTO fsrs_parameters (id, name, subject_id, weights, desired_retention, maximum_interval, learning_steps, relearning_steps, enable_fuzz, enable_load_balance, error_policy, leech_threshold, leech_action, total_reviews, created_at, updated_at) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?)"
+        )
+        .bind(&id)
+        .bind(&req.name)
+        .bind(&req.subject_id)
+        .bind(Json(&weights))
+        .bind(desired_retention)
+        .bind(maximum_interval)
+        .bind(Json(&learning_steps))
+        .bind(Json(&relearning_steps))
+        .bind(enable_fuzz)
+        .bind(enable_load_balance)
+        .bind(error_policy)
+        .bind(leech_threshold)
+        .bind(leech_action)
+        .bind(&now)
+        .bind(&now)
+        .execute(pool)
+        .await?;
+
+        Ok(FsrsParameterSet {
+            id,
+            name: req.name,
+            subject_id: Some(req.subject_id),
+            weights,
+            desired_retention,
+            maximum_interval,
+            learning_steps,
+            relearning_steps,
+            enable_fuzz,
+            enable_load_balance,
+            error_policy,
+            leech_threshold,
+            leech_action,
+            total_reviews: 0,
+            log_loss: None,
+            rmse: None,
+            created_at: now.clone(),
+            updated_at: now,
+        })
+    }
+
+    pub async fn update(
+        pool: &SqlitePool,
+        id: String,
+        req: UpdateFsrsParameterSetRequest,
+    ) -> Result<FsrsParameterSet, AppError> {
+        let set = Self::get_by_id(pool, id.clone()).await?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let name = req.name.unwrap_or(set.name);
+        let weights_changed = req.weights.is_some();
+        let weights = req.weights.unwrap_or(set.weights);
+        let desired_retention = req.desired_retention.unwrap_or(set.desired_retention);
+        let maximum_interval = req.maximum_interval.unwrap_or(set.maximum_interval);
+        let learning_steps = req.learning_steps.unwrap_or(set.learning_steps);
+        let relearning_steps = req.relearning_steps.unwrap_or(set.relearning_steps);
+        let enable_fuzz = req.enable_fuzz.unwrap_or(set.enable_fuzz);
+        let enable_load_balance = req.enable_load_balance.unwrap_or(set.enable_load_balance);
+        let error_policy = req.error_policy.unwrap_or(set.error_policy);
+        let leech_threshold = req.leech_threshold.unwrap_or(set.leech_threshold);
+        let leech_action = req.leech_action.unwrap_or(set.leech_action);
+        Self::validate(&weights, desired_retention, maximum_interval)?;
+        Self::validate_steps(&learning_steps, &relearning_steps)?;
+        Self::validate_leech_threshold(leech_threshold)?;
+
+        // Hand-edited weights no longer carry the optimizer's fit metrics
+        let (total_reviews, log_loss, rmse) = if weights_changed {
+            (0, None, None)
+        } else {
+            (set.total_reviews, set.log_loss, set.rmse)
+        };
+
+        sqlx::query(
+            "UPDATE fsrs_parameters 
+             SET name = ?, weights = ?, desired_retention = ?, maximum_interval = ?, 
+                 learning_steps = ?, relearning_steps = ?, enable_fuzz = ?, enable_load_balance = ?, 
+                 error_policy = ?, leech_threshold = ?, leech_action = ?, total_reviews = ?, log_loss = ?, rmse = ?, updated_at = ? 
+             WHERE id = ?",
+        )
+        .bind(&name)
+        .bind(Json(&weights))
+        .bind(desired_retention)
+        .bind(maximum_interval)
+        .bind(Json(&learning_steps))
+        .bind(Json(&relearning_steps))
+        .bind(enable_fuzz)
+        .bind(enable_load_balance)
+        .bind(error_policy)
+        .bind(leech_threshold)
+        .bind(leech_action)
+        .bind(total_reviews)
+        .bind(log_loss)
+        .bind(rmse)
+        .bind(&now)
+        .bind(&id)
+        .execute(pool)
+        .await?;
+
+        Ok(FsrsParameterSet {
+            id,
+            name,
+            subject_id: set.subject_id,
+            weights,
+            desired_retention,
+            maximum_interval,
+            learning_steps,
+            relearning_steps,
+            enable_fuzz,
+            enable_load_balance,
+            error_policy,
+            leech_threshold,
+            leech_action,
+            total_reviews,
+            log_loss,
+            rmse,
+            created_at: set.created_at,
+            updated_at: now,
+        })
+    }
+
+    /// Delete a subject override; its subject falls back to the global set
+    pub async fn delete(pool: &SqlitePool, id: String) -> Result<(), AppError> {
+        if id == GLOBAL_ID {
+            return Err(AppError::conflict(
+                "fsrs_parameter_set",
+                "The global parameter set cannot be deleted",
+            ));
+        }
+
+        sqlx::query("DELETE FROM fsrs_parameters WHERE id = ?")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Store optimizer output in a parameter set, keeping its retention settings
+    pub async fn save_trained_weights(
+        pool: &SqlitePool,
+        id: String,
+        weights: &[f64; 19],
+        total_reviews: i64,
+        log_loss: f64,
+        rmse: f64,
+    ) -> Result<FsrsParameterSet, AppError> {
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            "UPDATE fsrs_parameters 
+             SET weights = ?, total_reviews = ?, log_loss = ?, rmse = ?, updated_at = ? 
+             WHERE id = ?",
+        )
+        .bind(Json(weights.to_vec()))
+        .bind(total_reviews)
+        .bind(log_loss)
+        .bind(rmse)
+        .bind(&now)
+        .bind(&id)
+        .execute(pool)
+        .await?;
+
+        Self::get_by_id(pool, id).await
+    }
+
+    fn validate(
+        weights: &[f64],
+        desired_retention: f64,
+        maximum_interval: i32,
+    ) -> Result<(), AppError> {
+        if weights.len() != 19 || weights.iter().any(|w| !w.is_finite()) {
+            return Err(AppError::validation(
+                "weights",
+                format!("Expected 19 finite FSRS weights, got {}", weights.len()),
+            ));
+        }
+        if let Some((i, (w, (low, high)))) = weights
+            .iter()
+            .zip(WEIGHT_BOUNDS)
+            .enumerate()
+            .find(|(_, (w, (low, high)))| !(*low..=*high).contains(*w))
+        {
+            return Err(AppError::validation(
+                "weights",
+                format!(
+                    "Weight w{} must be between {} and {}, got {}",
+                    i, low, high, w
+                ),
+            ));
+        }
+        if !(0.7..=0.99).contains(&desired_retention) {
+            return Err(AppError::validation(
+                "desired_retention",
+                format!(
+                    "Desired retention must be between 0.70 and 0.99, got {}",
+                    desired_retention
+                ),
+            ));
+        }
+        if maximum_interval < 1 {
+            return Err(AppError::validation(
+                "maximum_interval",
+                "Maximum interval must be at least 1 day",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Steps are in minutes; an empty list skips (re)learning entirely
+    fn validate_steps(learning_steps: &[u32], relearning_steps: &[u32]) -> Result<(), AppError> {
+        if learning_steps
+            .iter()
+            .chain(relearning_steps)
+            .any(|&m| m == 0)
+        {
+            return Err(AppError::validation(
+                "learning_steps",
+                "Learning steps must be at least 1 minute",
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_leech_threshold(leech_threshold: i32) -> Result<(), AppError> {
+        if leech_threshold < 0 {
+            return Err(AppError::validation(
+                "leech_threshold",
+                format!(
+                    "Leech threshold must be 0 (off) or more, got {}",
+                    leech_threshold
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{seed_problem, test_pool};
+    use crate::services::TopicService;
+
+    async fn retention(pool: &SqlitePool, problem_id: &str) -> f64 {
+        FsrsParameterService::resolve_for_problem(pool, problem_id)
+            .await
+            .unwrap()
+            .desired_retention
+    }
+
+    #[tokio::test]
+    async fn test_subject_override_resolves_for_its_problems() {
+        let pool = test_pool().await;
+        let problem = seed_problem(&pool).await;
+        let subject_id = TopicService::get_by_id(&pool, problem.topic_id)
+            .await
+            .unwrap()
+            .subject_id;
+
+        // No override: the global set
+        assert_eq!(retention(&pool, &problem.id).await, 0.9);
+
+        let create = |desired_retention| CreateFsrsParameterSetRequest {
+            name: "Exam".into(),
+            subject_id: subject_id.clone(),
+            desired_retention: Some(desired_retention),
+            ..Default::default()
+        };
+        let invalid = FsrsParameterService::create(&pool, create(0.5)).await;
+        assert!(
+            matches!(invalid, Err(AppError::Validation { ref field, .. }) if field == "desired_retention")
+        );
+        let set = FsrsParameterService::create(&pool, create(0.95))
+            .await
+            .unwrap();
+        let global = FsrsParameterService::get_global(&pool).await.unwrap();
+        assert_eq!(set.maximum_interval, global.maximum_interval);
+        assert_eq!(retention(&pool, &problem.id).await, 0.95);
+        assert!(matches!(
+            FsrsParameterService::create(&pool, create(0.8)).await,
+            Err(AppError::Conflict { .. })
+        ));
+
+        let update = |req| FsrsParameterService::update(&pool, set.id.clone(), req);
+        let mut out_of_bounds = DEFAULT_W.to_vec();
+        out_of_bounds[4] = 0.5;
+        let rejected = update(UpdateFsrsParameterSetRequest {
+            weights: Some(out_of_bounds),
+            ..Default::default()
+        })
+        .await;
+        assert!(matches!(
+            rejected,
+            Err(AppError::Validation { ref field, ref message })
+                if field == "weights" && message == "Weight w4 must be between 1 and 10, got 0.5"
+        ));
+        for (req, field) in [
+            (
+                UpdateFsrsParameterSetRequest {
+                    weights: Some(vec![1.0; 3]),
+                    ..Default::default()
+                },
+                "weights",
+            ),
+            (
+                UpdateFsrsParameterSetRequest {
+                    learning_steps: Some(vec![10, 0]),
+                    ..Default::default()
+                },
+                "learning_steps",
+            ),
+            (
+                UpdateFsrsParameterSetRequest {
+                    leech_threshold: Some(-1),
+                    ..Default::default()
+                },
+                "leech_threshold",
+            ),
+        ] {
+            assert!(
+                matches!(update(req).await, Err(AppError::Validation { field: ref f, .. }) if f == field)
+            );
+        }
+        let updated = update(UpdateFsrsParameterSetRequest {
+            desired_retention: Some(0.85),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            (updated.name.as_str(), retention(&pool, &problem.id).await),
+            ("Exam", 0.85)
+        );
+
+        // Dropping the override falls back to the global set, which can't be dropped
+        FsrsParameterService::delete(&pool, set.id).await.unwrap();
+        assert_eq!(retention(&pool, &problem.id).await, 0.9);
+        assert!(matches!(
+            FsrsParameterService::delete(&pool, global.id).await,
+            Err(AppError::Conflict { .. })
+        ));
+    }
+}
@@ -1,17 +1,38 @@
-use crate::models::fsrs::{FsrsCard, FsrsStats, ReviewRequest, ReviewResult};
-use sqlx::SqlitePool;
+use crate::error::AppError;
+use crate::models::fsrs::{
+    CardRetrievability, CardUpdate, ErrorPolicy, ForgettingCurvePoint, FsrsCard, FsrsParameters,
+    FsrsStats, LeechAction, Rating, RatingPreview, ReviewRequest, ReviewResult,
+};
+use crate::models::review_log::ReviewKind;
+use crate::models::ReviewLog;
+use crate::services::{ErrorService, FsrsParameterService, ReviewLogService};
+use chrono::{DateTime, Local, Utc};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::HashMap;
 
 pub struct FsrsService;
 
-// FSRS v3 Default Parameters
-const W: [f64; 19] = [
-    0.40, 1.86, 4.93, 0.94, 0.86, 0.01, 1.49, 0.04, 0.36, 0.86, 0.20, 2.50, 0.14, 0.94, 0.16, 0.10,
-    0.29, 0.34, 3.73,
+// FSRS-5 forgetting curve: R(t, S) = (1 + FACTOR * t / S) ^ DECAY, with R(S, S) = 0.9
+const DECAY: f64 = -0.5;
+const FACTOR: f64 = 19.0 / 81.0;
+
+// Interval fuzz: (from day, to day, share of that stretch added to the +/- range)
+const FUZZ_RANGES: [(f64, f64, f64); 3] = [
+    (2.5, 7.0, 0.15),
+    (7.0, 20.0, 0.1),
+    (20.0, f64::INFINITY, 0.05),
 ];
 
+// Upper bound of the stability divisor, so a long list of errors can't wipe
+// the memory state out entirely
+const ERROR_DIVISOR_MAX: f64 = 4.0;
+
 impl FsrsService {
     /// Initialize FSRS card for a new problem (first attempt)
-    pub async fn create_card(pool: &SqlitePool, problem_id: String) -> Result<FsrsCard, String> {
+    pub async fn create_card(
+        conn: &mut SqliteConnection,
+        problem_id: String,
+    ) -> Result<FsrsCard, AppError> {
         let id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
         let due = now.clone(); // Due immediately for first review
@@ -25,9 +46,8 @@ impl FsrsService {
         .bind(&due)
         .bind(&now)
         .bind(&now)
-        .execute(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .execute(conn)
+        .await?;
 
         Ok(FsrsCard {
             id,
@@ -40,6 +60,11 @@ impl FsrsService {
             lapses: 0,
             elapsed_days: 0,
             scheduled_days: 1,
+            step: 0,
+            is_leech: false,
+            is_suspended: false,
+            buried_until: None,
+            last_review: None,
             created_at: now.clone(),
             updated_at: now,
         })
@@ -47,20 +72,19 @@ impl FsrsService {
 
     /// Get or create card for problem
     pub async fn get_or_create_card(
-        pool: &SqlitePool,
+        conn: &mut SqliteConnection,
         problem_id: String,
-    ) -> Result<FsrsCard, String> {
+    ) -> Result<FsrsCard, AppError> {
         // Try to fetch existing card
         match sqlx::query_as::<_, FsrsCard>(
-            "SELECT id, problem_id, due, stability, difficulty, state, reps, lapses, elapsed_days, scheduled_days, created_at, updated_at 
+            "SELECT id, problem_id, due, stability, difficulty, state, reps, lapses, elapsed_days, scheduled_days, step, is_leech, is_suspended, buried_until, last_review, created_at, updated_at 
              FROM fsrs_cards WHERE problem_id = ?"
         )
         .bind(&problem_id)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| e.to_string())? {
+        .fetch_optional(&mut *conn)
+        .await? {
             Some(card) => Ok(card),
-            None => Self::create_card(pool, problem_id).await,
+            None => Self::create_card(conn, problem_id).await,
         }
     }
 
@@ -68,208 +92,901 @@ impl FsrsService {
     pub async fn process_review(
         pool: &SqlitePool,
         req: ReviewRequest,
-    ) -> Result<ReviewResult, String> {
-        let card = Self::get_or_create_card(pool, req.problem_id.clone()).await?;
+    ) -> Result<ReviewResult, AppError> {
+        let params = FsrsParameterService::resolve_for_problem(pool, &req.problem_id).await?;
 
-        // Calculate new state using FSRS algorithm
-        let (new_state, new_difficulty, new_stability, new_interval) =
-            Self::calculate_fsrs_update(&card, req.attempt_is_solved, req.quality);
+        let mut tx = pool.begin().await?;
+        let result = Self::review(&mut tx, req, &params).await?;
+        tx.commit().await?;
 
-        let next_due = chrono::Utc::now()
-            .checked_add_signed(chrono::Duration::days(new_interval as i64))
-            .unwrap()
-            .to_rfc3339();
+        Ok(result)
+    }
 
-        let now = chrono::Utc::now().to_rfc3339();
-        let is_correct = req.quality >= 3; // Quality 3+ is considered passing
+    /// Review step of a larger write: creates the card if needed, updates it and
+    /// logs the review on `conn`, so errors logged earlier in the same
+    /// transaction count towards the error policy
+    pub(crate) async fn review(
+        conn: &mut SqliteConnection,
+        req: ReviewRequest,
+        params: &FsrsParameters,
+    ) -> Result<ReviewResult, AppError> {
+        // A failed attempt is always an "Again", whatever grade was picked
+        let rating = if req.attempt_is_solved {
+            Rating::from_u8(req.rating).ok_or_else(|| {
+                AppError::validation(
+                    "rating",
+                    format!("Invalid rating {}, expected 1-4", req.rating),
+                )
+            })?
+        } else {
+            Rating::Again
+        };
+
+        let card = Self::get_or_create_card(conn, req.problem_id.clone()).await?;
+        let now = Utc::now();
+
+        let error_weight = match &req.attempt_id {
+            Some(attempt_id) => ErrorService::get_attempt_error_weight(conn, attempt_id).await?,
+            None => 0.0,
+        };
+        let rating = Self::rating_with_errors(
+            params.error_policy,
+            rating,
+            req.attempt_is_solved,
+            error_weight,
+        );
+
+        let mut update = Self::calculate_fsrs_update(&card, rating, params, now);
+        let stability_penalty =
+            Self::apply_error_weight(&card, params, error_weight, now, &mut update);
+        Self::balance_load(conn, &card, params, now, &mut update).await?;
+        let is_lapse = card.state == "review" && rating == Rating::Again;
+        let became_leech =
+            is_lapse && !card.is_leech && Self::is_leech(card.lapses + 1, params.leech_threshold);
+        let suspend = became_leech && params.leech_action == LeechAction::Suspend;
+        let now = now.to_rfc3339();
+
+        let log = ReviewLog {
+            id: uuid::Uuid::new_v4().to_string(),
+            card_id: card.id.clone(),
+            problem_id: card.problem_id.clone(),
+            rating: rating as i32,
+            kind: ReviewKind::Review,
+            state_before: card.state.clone(),
+            state_after: update.state.clone(),
+            stability_before: card.stability,
+            stability_after: update.stability,
+            difficulty_before: card.difficulty,
+            difficulty_after: update.difficulty,
+            stability_penalty,
+            elapsed_days: update.elapsed_days,
+            scheduled_days: update.scheduled_days,
+            duration_seconds: req.time_spent_seconds.max(0),
+            reviewed_at: now.clone(),
+        };
 
-        // Update card in database
         sqlx::query(
             "UPDATE fsrs_cards 
-             SET state = ?, difficulty = ?, stability = ?, due = ?, 
+             SET state = ?, step = ?, difficulty = ?, stability = ?, due = ?, 
                  reps = reps + 1, lapses = lapses + ?, scheduled_days = ?, 
-                 elapsed_days = ?, updated_at = ? 
+                 elapsed_days = ?, is_leech = is_leech OR ?, is_suspended = is_suspended OR ?, 
+                 last_review = ?, updated_at = ? 
              WHERE id = ?",
         )
-        .bind(&new_state)
-        .bind(new_difficulty)
-        .bind(new_stability)
-        .bind(&next_due)
-        .bind(if req.quality < 3 { 1 } else { 0 }) // Increment lapses on fail
-        .bind(new_interval)
-        .bind(Self::calculate_elapsed_days(req.time_spent_seconds))
+        .bind(&update.state)
+        .bind(update.step)
+        .bind(update.difficulty)
+        .bind(update.stability)
+        .bind(&update.due)
+        .bind(is_lapse as i32)
+        .bind(update.scheduled_days)
+        .bind(update.elapsed_days)
+        .bind(became_leech)
+        .bind(suspend)
+        .bind(&now)
         .bind(&now)
         .bind(&card.id)
-        .execute(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .execute(&mut *conn)
+        .await?;
+
+        ReviewLogService::insert(conn, &log, &card).await?;
 
         Ok(ReviewResult {
             problem_id: req.problem_id,
             card_id: card.id,
-            new_state,
-            new_stability,
-            new_difficulty,
-            new_interval_days: new_interval,
-            next_due,
-            is_correct,
+            review_id: log.id,
+            new_state: update.state,
+            new_stability: update.stability,
+            new_difficulty: update.difficulty,
+            new_interval_days: update.scheduled_days,
+            new_interval_minutes: update.scheduled_minutes,
+            next_due: update.due,
+            is_correct: rating != Rating::Again,
+            became_leech,
+        })
+    }
+
+    /// Undo the most recent review or manual action (of one card, or of any card),
+    /// restoring the card exactly as it was and dropping the log entry.
+    /// Repeated calls keep walking back.
+    pub async fn undo_last_review(
+        pool: &SqlitePool,
+        card_id: Option<String>,
+    ) -> Result<FsrsCard, AppError> {
+        let mut tx = pool.begin().await?;
+
+        // Nothing left to undo, for this card or at all
+        let scope = card_id.clone().unwrap_or_default();
+        let (review_id, card) = ReviewLogService::latest_with_snapshot(&mut tx, card_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("review", scope))?;
+
+        let card = FsrsCard {
+            updated_at: Utc::now().to_rfc3339(),
+            ..card
+        };
+        Self::save_card(&mut tx, &card).await?;
+        ReviewLogService::delete(&mut tx, &review_id).await?;
+
+        tx.commit().await?;
+
+        Ok(card)
+    }
+
+    /// Take a card out of rotation until it is unsuspended
+    pub async fn suspend_card(pool: &SqlitePool, card_id: String) -> Result<FsrsCard, AppError> {
+        Self::manual_action(pool, card_id, ReviewKind::Suspend, |card, _| {
+            card.is_suspended = true;
+        })
+        .await
+    }
+
+    pub async fn unsuspend_card(pool: &SqlitePool, card_id: String) -> Result<FsrsCard, AppError> {
+        Self::manual_action(pool, card_id, ReviewKind::Unsuspend, |card, _| {
+            card.is_suspended = false;
+        })
+        .await
+    }
+
+    /// Hide a card until the start of tomorrow (local time); its due date is untouched
+    pub async fn bury_card(pool: &SqlitePool, card_id: String) -> Result<FsrsCard, AppError> {
+        Self::manual_action(pool, card_id, ReviewKind::Bury, |card, now| {
+            let tomorrow = Self::local_day_start(now) + chrono::Duration::days(1);
+            card.buried_until = Some(tomorrow.to_rfc3339());
+        })
+        .await
+    }
+
+    /// Move a card's due date (RFC 3339); the memory state is kept
+    pub async fn set_card_due(
+        pool: &SqlitePool,
+        card_id: String,
+        due: String,
+    ) -> Result<FsrsCard, AppError> {
+        let due = Self::parse_timestamp(Some(due))?;
+
+        Self::manual_action(pool, card_id, ReviewKind::SetDue, |card, now| {
+            card.due = due.to_rfc3339();
+            card.scheduled_days = (due - now).num_days().max(0) as i32;
+            card.buried_until = None;
         })
+        .await
+    }
+
+    /// Back to "new" and due now; reps, lapses and the leech/suspension flags are kept
+    pub async fn reset_card(pool: &SqlitePool, card_id: String) -> Result<FsrsCard, AppError> {
+        Self::manual_action(pool, card_id, ReviewKind::Reset, Self::reset_schedule).await
+    }
+
+    /// Back to "new" as if never studied: counters and flags are cleared too.
+    /// The review history itself is kept.
+    pub async fn forget_card(pool: &SqlitePool, card_id: String) -> Result<FsrsCard, AppError> {
+        Self::manual_action(pool, card_id, ReviewKind::Forget, Self::forget_schedule).await
+    }
+
+    /// `forget_card` inside the caller's transaction
+    pub(crate) async fn forget(
+        conn: &mut SqliteConnection,
+        card_id: String,
+    ) -> Result<FsrsCard, AppError> {
+        Self::record_manual_action(conn, card_id, ReviewKind::Forget, Self::forget_schedule).await
+    }
+
+    fn forget_schedule(card: &mut FsrsCard, now: DateTime<Utc>) {
+        Self::reset_schedule(card, now);
+        card.reps = 0;
+        card.lapses = 0;
+        card.is_leech = false;
+        card.is_suspended = false;
+    }
+
+    fn reset_schedule(card: &mut FsrsCard, now: DateTime<Utc>) {
+        card.due = now.to_rfc3339();
+        card.stability = 1.0;
+        card.difficulty = 5.0;
+        card.state = "new".to_string();
+        card.step = 0;
+        card.elapsed_days = 0;
+        card.scheduled_days = 1;
+        card.buried_until = None;
+        card.last_review = None;
+    }
+
+    /// Apply a manual change to a card and record it in the review log (rating 0),
+    /// with a snapshot so it can be undone like a review
+    async fn manual_action(
+        pool: &SqlitePool,
+        card_id: String,
+        kind: ReviewKind,
+        change: impl FnOnce(&mut FsrsCard, DateTime<Utc>),
+    ) -> Result<FsrsCard, AppError> {
+        let mut tx = pool.begin().await?;
+        let card = Self::record_manual_action(&mut tx, card_id, kind, change).await?;
+        tx.commit().await?;
+
+        Ok(card)
+    }
+
+    async fn record_manual_action(
+        conn: &mut SqliteConnection,
+        card_id: String,
+        kind: ReviewKind,
+        change: impl FnOnce(&mut FsrsCard, DateTime<Utc>),
+    ) -> Result<FsrsCard, AppError> {
+        let before = Self::load_card(&mut *conn, &card_id).await?;
+        let now = Utc::now();
+
+        let mut card = before.clone();
+        change(&mut card, now);
+        card.updated_at = now.to_rfc3339();
+
+        let log = ReviewLog {
+            id: uuid::Uuid::new_v4().to_string(),
+            card_id: card.id.clone(),
+            problem_id: card.problem_id.clone(),
+            rating: 0,
+            kind,
+            state_before: before.state.clone(),
+            state_after: card.state.clone(),
+            stability_before: before.stability,
+            stability_after: card.stability,
+            difficulty_before: before.difficulty,
+            difficulty_after: card.difficulty,
+            stability_penalty: 1.0,
+            elapsed_days: 0,
+            scheduled_days: card.scheduled_days,
+            duration_seconds: 0,
+            reviewed_at: card.updated_at.clone(),
+        };
+
+        Self::save_card(&mut *conn, &card).await?;
+        ReviewLogService::insert(conn, &log, &before).await?;
+
+        Ok(card)
     }
 
-    /// Core FSRS algorithm: calculate new state based on quality
-    fn calculate_fsrs_update(
+    /// Write every mutable field of a card back to its row
+    pub(crate) async fn save_card(
+        conn: &mut SqliteConnection,
         card: &FsrsCard,
-        _attempt_solved: bool,
-        quality: u8,
-    ) -> (String, f64, f64, i32) {
-        let quality = quality.clamp(1, 5);
-
-        // Update difficulty (1-10 scale)
-        let new_difficulty = card.difficulty + (5.0 - quality as f64) * W[4];
-        let new_difficulty = new_difficulty.max(1.0).min(10.0);
-
-        // Update stability based on card state and quality
-        let new_stability = match card.state.as_str() {
-            "new" => {
-                if quality >= 3 {
-                    W[0] // Initial stability for "pass" on new card
-                } else {
-                    W[1] * (card.stability + 1.0) // Reduced for "fail"
-                }
-            }
-            "learning" => {
-                if quality >= 3 {
-                    W[2] * card.stability // Good progress
-                } else {
-                    W[1] * card.stability // Back to learning
-                }
-            }
-            "review" => {
-                if quality >= 3 {
-                    card.stability * (1.0 + W[6] * (quality as f64 - 3.0))
-                } else {
-                    card.stability * W[7] // Significant decay
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            "UPDATE fsrs_cards 
+             SET due = ?, stability = ?, difficulty = ?, state = ?, step = ?, reps = ?, lapses = ?, 
+                 elapsed_days = ?, scheduled_days = ?, is_leech = ?, is_suspended = ?, 
+                 buried_until = ?, last_review = ?, updated_at = ? 
+             WHERE id = ?",
+        )
+        .bind(&card.due)
+        .bind(card.stability)
+        .bind(card.difficulty)
+        .bind(&card.state)
+        .bind(card.step)
+        .bind(card.reps)
+        .bind(card.lapses)
+        .bind(card.elapsed_days)
+        .bind(card.scheduled_days)
+        .bind(card.is_leech)
+        .bind(card.is_suspended)
+        .bind(&card.buried_until)
+        .bind(&card.last_review)
+        .bind(&card.updated_at)
+        .bind(&card.id)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Outcome of every possible rating for a card, without touching the database
+    pub async fn preview_review(
+        pool: &SqlitePool,
+        card_id: String,
+    ) -> Result<Vec<RatingPreview>, AppError> {
+        let card = Self::get_card_by_id(pool, card_id).await?;
+        let params = FsrsParameterService::resolve_for_problem(pool, &card.problem_id).await?;
+        let now = Utc::now();
+
+        let mut conn = pool.acquire().await?;
+        let mut preview = Self::preview(&card, &params, now);
+        for p in preview.iter_mut() {
+            Self::balance_load(&mut conn, &card, &params, now, &mut p.update).await?;
+        }
+        Ok(preview)
+    }
+
+    pub fn preview(
+        card: &FsrsCard,
+        params: &FsrsParameters,
+        now: DateTime<Utc>,
+    ) -> Vec<RatingPreview> {
+        [Rating::Again, Rating::Hard, Rating::Good, Rating::Easy]
+            .into_iter()
+            .map(|rating| RatingPreview {
+                rating,
+                update: Self::calculate_fsrs_update(card, rating, params, now),
+            })
+            .collect()
+    }
+
+    /// Core FSRS-5 step: compute the card's next memory state for `rating` given at `now`
+    pub fn calculate_fsrs_update(
+        card: &FsrsCard,
+        rating: Rating,
+        params: &FsrsParameters,
+        now: DateTime<Utc>,
+    ) -> CardUpdate {
+        let elapsed_days = Self::elapsed_days_since(card.last_review.as_deref(), now);
+        let memory = (card.state != "new").then_some((card.stability, card.difficulty));
+        let (stability, difficulty) =
+            Self::next_memory_state(&params.w, memory, elapsed_days, rating);
+
+        // Graduating (or staying) in review schedules whole days from the memory model
+        let graduate = || {
+            (
+                "review",
+                0,
+                Self::review_interval(card, params, stability, elapsed_days) as i64 * 1440,
+            )
+        };
+
+        let (state, step, scheduled_minutes) = match card.state.as_str() {
+            "review" => match params.relearning_steps.first() {
+                Some(&first) if rating == Rating::Again => ("relearning", 0, first as i64),
+                _ => graduate(),
+            },
+            "relearning" => {
+                match Self::next_step(&params.relearning_steps, card.step.max(0) as usize, rating) {
+                    Some((step, minutes)) => ("relearning", step, minutes),
+                    None => graduate(),
                 }
             }
-            "relearning" => {
-                if quality >= 3 {
-                    W[2] * card.stability
+            // new and learning
+            _ => {
+                let step = if card.state == "new" {
+                    0
                 } else {
-                    W[1] * card.stability
+                    card.step.max(0) as usize
+                };
+                match Self::next_step(&params.learning_steps, step, rating) {
+                    Some((step, minutes)) => ("learning", step, minutes),
+                    None => graduate(),
                 }
             }
-            _ => card.stability,
         };
 
-        let new_stability = new_stability.max(0.1);
+        let due = (now + chrono::Duration::minutes(scheduled_minutes)).to_rfc3339();
 
-        // Determine new state and interval
-        let (new_state, new_interval) = match card.state.as_str() {
-            "new" => {
-                if quality >= 3 {
-                    ("learning".to_string(), 1) // Start learning phase
-                } else {
-                    ("learning".to_string(), 1) // Stay in learning
-                }
-            }
-            "learning" => {
-                if quality >= 3 {
-                    ("review".to_string(), ((new_stability * 10.0) as i32).max(1))
-                // Move to review
-                } else {
-                    ("learning".to_string(), 1) // More practice
-                }
+        CardUpdate {
+            state: state.to_string(),
+            step: step as i32,
+            stability,
+            difficulty,
+            elapsed_days,
+            scheduled_days: (scheduled_minutes / 1440) as i32,
+            scheduled_minutes,
+            due,
+        }
+    }
+
+    /// Rating after the error policy. Under `EffectiveRating` the error weight caps the
+    /// rating of a solved attempt; a failed attempt stays Again whatever its errors,
+    /// so it still lapses.
+    pub fn rating_with_errors(
+        policy: ErrorPolicy,
+        rating: Rating,
+        attempt_is_solved: bool,
+        error_weight: f64,
+    ) -> Rating {
+        if policy != ErrorPolicy::EffectiveRating || error_weight <= 0.0 {
+            return rating;
+        }
+
+        if !attempt_is_solved {
+            return rating;
+        }
+
+        let cap = if error_weight >= 1.5 {
+            Rating::Again
+        } else if error_weight >= 1.0 {
+            Rating::Hard
+        } else {
+            Rating::Good
+        };
+        rating.min(cap)
+    }
+
+    /// Under `ScaleStability`, divide the new stability by `1 + error weight` and reschedule
+    /// a review card from it. Any logged error shortens the interval relative to an
+    /// error-free review with the same rating, heavier ones (1.5) more than minor ones (0.7).
+    /// Returns the divisor applied, 1 when the stability was left alone.
+    pub fn apply_error_weight(
+        card: &FsrsCard,
+        params: &FsrsParameters,
+        error_weight: f64,
+        now: DateTime<Utc>,
+        update: &mut CardUpdate,
+    ) -> f64 {
+        if params.error_policy != ErrorPolicy::ScaleStability || error_weight <= 0.0 {
+            return 1.0;
+        }
+
+        let divisor = (1.0 + error_weight).min(ERROR_DIVISOR_MAX);
+        update.stability = Self::penalize_stability(update.stability, divisor);
+
+        // (Re)learning steps are fixed; only day intervals follow stability
+        if update.state == "review" {
+            let days = Self::review_interval(card, params, update.stability, update.elapsed_days);
+            update.scheduled_days = days;
+            update.scheduled_minutes = days as i64 * 1440;
+            update.due = (now + chrono::Duration::days(days as i64)).to_rfc3339();
+        }
+        divisor
+    }
+
+    /// Stability after an error penalty of `divisor`
+    pub fn penalize_stability(stability: f64, divisor: f64) -> f64 {
+        (stability / divisor).max(0.1)
+    }
+
+    /// Whether a card with `lapses` lapses is a leech (a threshold of 0 disables leeches)
+    pub fn is_leech(lapses: i32, leech_threshold: i32) -> bool {
+        leech_threshold > 0 && lapses >= leech_threshold
+    }
+
+    /// Next (re)learning step and its interval in minutes, or `None` when the card
+    /// graduates to review
+    fn next_step(steps: &[u32], step: usize, rating: Rating) -> Option<(usize, i64)> {
+        if steps.is_empty() || (step >= steps.len() && rating != Rating::Again) {
+            return None;
+        }
+
+        match rating {
+            Rating::Again => Some((0, steps[0] as i64)),
+            // Hard repeats the current step; on the first step it sits between the first two
+            Rating::Hard => {
+                let minutes = match (step, steps.len()) {
+                    (0, 1) => steps[0] as f64 * 1.5,
+                    (0, _) => (steps[0] + steps[1]) as f64 / 2.0,
+                    _ => steps[step] as f64,
+                };
+                Some((step, minutes.round() as i64))
             }
-            "review" => {
-                if quality >= 3 {
-                    ("review".to_string(), ((new_stability * 10.0) as i32).max(1))
-                // Continue review
-                } else {
-                    ("relearning".to_string(), 1) // Need relearning
-                }
+            Rating::Good if step + 1 < steps.len() => Some((step + 1, steps[step + 1] as i64)),
+            Rating::Good | Rating::Easy => None,
+        }
+    }
+
+    /// Memory state (stability, difficulty) after one review.
+    /// `memory` is `None` for a card that has never been reviewed.
+    pub fn next_memory_state(
+        w: &[f64; 19],
+        memory: Option<(f64, f64)>,
+        elapsed_days: i32,
+        rating: Rating,
+    ) -> (f64, f64) {
+        let Some((stability, difficulty)) = memory else {
+            return (
+                Self::init_stability(w, rating),
+                Self::init_difficulty(w, rating),
+            );
+        };
+
+        let next_stability = if elapsed_days == 0 {
+            // Same-day review: memory has not decayed yet
+            Self::next_short_term_stability(w, stability, rating)
+        } else {
+            let r = Self::retrievability(elapsed_days as f64, stability);
+            match rating {
+                Rating::Again => Self::next_forget_stability(w, difficulty, stability, r),
+                _ => Self::next_recall_stability(w, difficulty, stability, r, rating),
             }
-            "relearning" => {
-                if quality >= 3 {
-                    ("review".to_string(), ((new_stability * 5.0) as i32).max(1))
-                // Back to review
-                } else {
-                    ("relearning".to_string(), 1) // More relearning
-                }
+        };
+
+        (next_stability, Self::next_difficulty(w, difficulty, rating))
+    }
+
+    /// Probability of recall after `elapsed_days` for a memory of the given stability
+    pub fn retrievability(elapsed_days: f64, stability: f64) -> f64 {
+        (1.0 + FACTOR * elapsed_days.max(0.0) / stability).powf(DECAY)
+    }
+
+    /// A card's recall probability at `at`; a card never reviewed has nothing to recall yet
+    pub fn retrievability_at(card: &FsrsCard, at: DateTime<Utc>) -> f64 {
+        let last_review = card
+            .last_review
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+
+        match last_review {
+            Some(last_review) if card.state != "new" => {
+                let elapsed_days =
+                    (at - last_review.with_timezone(&Utc)).num_seconds() as f64 / 86400.0;
+                Self::retrievability(elapsed_days, card.stability)
             }
-            _ => ("new".to_string(), 1),
+            _ => 0.0,
+        }
+    }
+
+    /// Interval (days) at which retrievability drops to the desired retention
+    pub fn next_interval(params: &FsrsParameters, stability: f64) -> i32 {
+        let interval = stability / FACTOR * (params.desired_retention.powf(1.0 / DECAY) - 1.0);
+        (interval.round() as i32).clamp(1, params.maximum_interval.max(1))
+    }
+
+    /// Review interval in days, fuzzed within `fuzz_range` when enabled.
+    /// The fuzz is seeded by the card id and rep count, so it is reproducible.
+    pub fn review_interval(
+        card: &FsrsCard,
+        params: &FsrsParameters,
+        stability: f64,
+        elapsed_days: i32,
+    ) -> i32 {
+        let interval = Self::next_interval(params, stability);
+        if !params.enable_fuzz {
+            return interval;
+        }
+
+        let (min, max) = Self::fuzz_range(interval, elapsed_days, params.maximum_interval);
+        let offset = (Self::seeded_fraction(&card.id, card.reps) * (max - min + 1) as f64) as i32;
+        (min + offset).min(max)
+    }
+
+    /// Days a review interval may be moved to, by fuzz or load balancing
+    pub fn fuzz_range(interval: i32, elapsed_days: i32, maximum_interval: i32) -> (i32, i32) {
+        let ivl = interval as f64;
+        if ivl < FUZZ_RANGES[0].0 {
+            return (interval, interval);
+        }
+
+        let delta = 1.0
+            + FUZZ_RANGES
+                .iter()
+                .map(|&(start, end, factor)| factor * (ivl.min(end) - start).max(0.0))
+                .sum::<f64>();
+
+        let mut min = ((ivl - delta).round() as i32).max(2);
+        let max = ((ivl + delta).round() as i32).min(maximum_interval.max(1));
+        // Never fall back to (or before) the interval the card just waited out
+        if interval > elapsed_days {
+            min = min.max(elapsed_days + 1);
+        }
+        (min.min(max), max)
+    }
+
+    /// Uniform value in [0, 1) derived from a card id and a counter (rep count for fuzz)
+    pub(crate) fn seeded_fraction(card_id: &str, n: i32) -> f64 {
+        // FNV-1a, then a splitmix64 finalizer so similar ids land far apart
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in card_id.bytes().chain(n.to_le_bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        hash ^= hash >> 31;
+
+        (hash >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Move a review to the day in its fuzz range with the fewest cards already due.
+    /// No-op unless load balancing is enabled and the card lands in review.
+    async fn balance_load(
+        conn: &mut SqliteConnection,
+        card: &FsrsCard,
+        params: &FsrsParameters,
+        now: DateTime<Utc>,
+        update: &mut CardUpdate,
+    ) -> Result<(), AppError> {
+        if !params.enable_load_balance || update.state != "review" {
+            return Ok(());
+        }
+
+        let interval = Self::next_interval(params, update.stability);
+        let (min, max) = Self::fuzz_range(interval, update.elapsed_days, params.maximum_interval);
+        if min == max {
+            return Ok(());
+        }
+
+        let day = |offset: i32| {
+            (now + chrono::Duration::days(offset as i64))
+                .format("%Y-%m-%d")
+                .to_string()
         };
 
-        (new_state, new_difficulty, new_stability, new_interval)
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT substr(due, 1, 10) AS day, COUNT(*) FROM fsrs_cards 
+             WHERE id != ? AND due >= ? AND due < ? 
+             GROUP BY day",
+        )
+        .bind(&card.id)
+        .bind(day(min))
+        .bind(day(max + 1))
+        .fetch_all(conn)
+        .await?;
+
+        let load: HashMap<String, i64> = rows.into_iter().collect();
+        let counts: Vec<i64> = (min..=max)
+            .map(|offset| load.get(&day(offset)).copied().unwrap_or(0))
+            .collect();
+
+        let days = Self::least_loaded_day(min, &counts, update.scheduled_days);
+        update.scheduled_days = days;
+        update.scheduled_minutes = days as i64 * 1440;
+        update.due = (now + chrono::Duration::days(days as i64)).to_rfc3339();
+        Ok(())
+    }
+
+    /// Pick the interval with the lowest count (`counts[i]` is the load `min + i` days out).
+    /// Ties go to the day closest to `preferred`, then the earlier one.
+    pub fn least_loaded_day(min: i32, counts: &[i64], preferred: i32) -> i32 {
+        (min..)
+            .zip(counts)
+            .min_by_key(|&(days, &count)| (count, (days - preferred).abs(), days))
+            .map(|(days, _)| days)
+            .unwrap_or(preferred)
+    }
+
+    pub fn init_stability(w: &[f64; 19], rating: Rating) -> f64 {
+        w[rating as usize - 1].max(0.1)
+    }
+
+    pub fn init_difficulty(w: &[f64; 19], rating: Rating) -> f64 {
+        Self::raw_init_difficulty(w, rating.as_f64()).clamp(1.0, 10.0)
+    }
+
+    fn raw_init_difficulty(w: &[f64; 19], rating: f64) -> f64 {
+        w[4] - (w[5] * (rating - 1.0)).exp() + 1.0
+    }
+
+    /// Linear-damped difficulty change, mean-reverted towards the "Easy" initial difficulty
+    pub fn next_difficulty(w: &[f64; 19], difficulty: f64, rating: Rating) -> f64 {
+        let delta = -w[6] * (rating.as_f64() - 3.0);
+        let damped = difficulty + delta * (10.0 - difficulty) / 9.0;
+        let target = Self::raw_init_difficulty(w, Rating::Easy.as_f64());
+        (w[7] * target + (1.0 - w[7]) * damped).clamp(1.0, 10.0)
+    }
+
+    pub fn next_recall_stability(
+        w: &[f64; 19],
+        difficulty: f64,
+        stability: f64,
+        retrievability: f64,
+        rating: Rating,
+    ) -> f64 {
+        let hard_penalty = if rating == Rating::Hard { w[15] } else { 1.0 };
+        let easy_bonus = if rating == Rating::Easy { w[16] } else { 1.0 };
+
+        stability
+            * (1.0
+                + w[8].exp()
+                    * (11.0 - difficulty)
+                    * stability.powf(-w[9])
+                    * (((1.0 - retrievability) * w[10]).exp() - 1.0)
+                    * hard_penalty
+                    * easy_bonus)
+    }
+
+    pub fn next_forget_stability(
+        w: &[f64; 19],
+        difficulty: f64,
+        stability: f64,
+        retrievability: f64,
+    ) -> f64 {
+        let long_term = w[11]
+            * difficulty.powf(-w[12])
+            * ((stability + 1.0).powf(w[13]) - 1.0)
+            * ((1.0 - retrievability) * w[14]).exp();
+        // Post-lapse stability may never exceed what a same-day "Again" would give
+        let short_term_cap = stability / (w[17] * w[18]).exp();
+
+        long_term.min(short_term_cap)
+    }
+
+    pub fn next_short_term_stability(w: &[f64; 19], stability: f64, rating: Rating) -> f64 {
+        stability * (w[17] * (rating.as_f64() - 3.0 + w[18])).exp()
     }
 
-    /// Convert time spent to elapsed days
-    fn calculate_elapsed_days(seconds: i64) -> i32 {
-        ((seconds as f64) / 86400.0).ceil() as i32
+    /// Whole days since the previous review (0 for a card never reviewed)
+    fn elapsed_days_since(last_review: Option<&str>, now: DateTime<Utc>) -> i32 {
+        last_review
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| (now - t.with_timezone(&Utc)).num_days().max(0) as i32)
+            .unwrap_or(0)
     }
 
     /// Get card by ID
-    pub async fn get_card_by_id(pool: &SqlitePool, id: String) -> Result<FsrsCard, String> {
+    pub async fn get_card_by_id(pool: &SqlitePool, id: String) -> Result<FsrsCard, AppError> {
+        Self::load_card(&mut *pool.acquire().await?, &id).await
+    }
+
+    /// `get_card_by_id` on the caller's connection or transaction
+    pub(crate) async fn load_card(
+        conn: &mut SqliteConnection,
+        id: &str,
+    ) -> Result<FsrsCard, AppError> {
         sqlx::query_as::<_, FsrsCard>(
-            "SELECT id, problem_id, due, stability, difficulty, state, reps, lapses, elapsed_days, scheduled_days, created_at, updated_at 
+            "SELECT id, problem_id, due, stability, difficulty, state, reps, lapses, elapsed_days, scheduled_days, step, is_leech, is_suspended, buried_until, last_review, created_at, updated_at 
              FROM fsrs_cards WHERE id = ?"
         )
         .bind(id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| e.to_string())
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("fsrs_card", id))
     }
 
     /// Get due cards (problems to review)
-    pub async fn get_due_cards(pool: &SqlitePool) -> Result<Vec<FsrsCard>, String> {
+    pub async fn get_due_cards(pool: &SqlitePool) -> Result<Vec<FsrsCard>, AppError> {
         let now = chrono::Utc::now().to_rfc3339();
 
         sqlx::query_as::<_, FsrsCard>(
-            "SELECT id, problem_id, due, stability, difficulty, state, reps, lapses, elapsed_days, scheduled_days, created_at, updated_at 
-             FROM fsrs_cards WHERE due <= ? ORDER BY due"
+            "SELECT id, problem_id, due, stability, difficulty, state, reps, lapses, elapsed_days, scheduled_days, step, is_leech, is_suspended, buried_until, last_review, created_at, updated_at 
+             FROM fsrs_cards 
+             WHERE due <= ? AND is_suspended = 0 AND (buried_until IS NULL OR buried_until <= ?) 
+             ORDER BY due"
         )
         .bind(&now)
+        .bind(&now)
         .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+    }
+
+    /// Recall probability of one card at `at` (RFC 3339, defaults to now)
+    pub async fn get_retrievability(
+        pool: &SqlitePool,
+        card_id: String,
+        at: Option<String>,
+    ) -> Result<CardRetrievability, AppError> {
+        let at = Self::parse_timestamp(at)?;
+        let card = Self::get_card_by_id(pool, card_id).await?;
+
+        Ok(Self::card_retrievability(card, at))
+    }
+
+    /// Recall probability of every card, optionally restricted to a subject
+    pub async fn list_retrievability(
+        pool: &SqlitePool,
+        subject_id: Option<String>,
+        at: Option<String>,
+    ) -> Result<Vec<CardRetrievability>, AppError> {
+        let at = Self::parse_timestamp(at)?;
+
+        let cards = sqlx::query_as::<_, FsrsCard>(
+            "SELECT c.id, c.problem_id, c.due, c.stability, c.difficulty, c.state, c.reps, c.lapses, c.elapsed_days, c.scheduled_days, c.step, c.is_leech, c.is_suspended, c.buried_until, c.last_review, c.created_at, c.updated_at 
+             FROM fsrs_cards c
+             JOIN problems p ON c.problem_id = p.id
+             JOIN topics t ON p.topic_id = t.id
+             WHERE ? IS NULL OR t.subject_id = ?
+             ORDER BY c.due"
+        )
+        .bind(&subject_id)
+        .bind(&subject_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(cards
+            .into_iter()
+            .map(|card| Self::card_retrievability(card, at))
+            .collect())
+    }
+
+    /// Forgetting curve of a card sampled once a day, from today up to `days` ahead,
+    /// assuming no further review
+    pub async fn get_forgetting_curve(
+        pool: &SqlitePool,
+        card_id: String,
+        days: u32,
+    ) -> Result<Vec<ForgettingCurvePoint>, AppError> {
+        let card = Self::get_card_by_id(pool, card_id).await?;
+
+        Ok(Self::forgetting_curve(&card, Utc::now(), days))
+    }
+
+    pub fn forgetting_curve(
+        card: &FsrsCard,
+        from: DateTime<Utc>,
+        days: u32,
+    ) -> Vec<ForgettingCurvePoint> {
+        (0..=days as i32)
+            .map(|day| {
+                let at = from + chrono::Duration::days(day as i64);
+                ForgettingCurvePoint {
+                    day,
+                    date: at.to_rfc3339(),
+                    retrievability: Self::retrievability_at(card, at),
+                }
+            })
+            .collect()
+    }
+
+    fn card_retrievability(card: FsrsCard, at: DateTime<Utc>) -> CardRetrievability {
+        CardRetrievability {
+            retrievability: Self::retrievability_at(&card, at),
+            card_id: card.id,
+            problem_id: card.problem_id,
+            state: card.state,
+            stability: card.stability,
+            at: at.to_rfc3339(),
+        }
+    }
+
+    /// Midnight of the user's local day, as a UTC instant
+    pub fn local_day_start(now: DateTime<Utc>) -> DateTime<Utc> {
+        now.with_timezone(&Local)
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+            .map(|midnight| midnight.with_timezone(&Utc))
+            .unwrap_or(now)
+    }
+
+    fn parse_timestamp(at: Option<String>) -> Result<DateTime<Utc>, AppError> {
+        match at {
+            Some(at) => DateTime::parse_from_rfc3339(&at)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| {
+                    AppError::validation("at", format!("Invalid timestamp {}: {}", at, e))
+                }),
+            None => Ok(Utc::now()),
+        }
     }
 
     /// Get FSRS statistics
-    pub async fn get_stats(pool: &SqlitePool) -> Result<FsrsStats, String> {
+    pub async fn get_stats(pool: &SqlitePool) -> Result<FsrsStats, AppError> {
         let now = chrono::Utc::now().to_rfc3339();
 
         let (total,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM fsrs_cards")
             .fetch_one(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         let (new,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM fsrs_cards WHERE state = 'new'")
             .fetch_one(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         let (learning,): (i64,) =
             sqlx::query_as("SELECT COUNT(*) FROM fsrs_cards WHERE state = 'learning'")
                 .fetch_one(pool)
-                .await
-                .map_err(|e| e.to_string())?;
+                .await?;
 
         let (review,): (i64,) =
             sqlx::query_as("SELECT COUNT(*) FROM fsrs_cards WHERE state = 'review'")
                 .fetch_one(pool)
-                .await
-                .map_err(|e| e.to_string())?;
+                .await?;
 
         let (relearning,): (i64,) =
             sqlx::query_as("SELECT COUNT(*) FROM fsrs_cards WHERE state = 'relearning'")
                 .fetch_one(pool)
-                .await
-                .map_err(|e| e.to_string())?;
+                .await?;
 
-        let (due,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM fsrs_cards WHERE due <= ?")
-            .bind(&now)
-            .fetch_one(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+        let (due,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM fsrs_cards
+             WHERE due <= ? AND is_suspended = 0 AND (buried_until IS NULL OR buried_until <= ?)",
+        )
+        .bind(&now)
+        .bind(&now)
+        .fetch_one(pool)
+        .await?;
 
         let retention_rate = if review + relearning > 0 {
             ((review as f64) / (total as f64) * 100.0).min(100.0)
@@ -292,14 +1009,515 @@ impl FsrsService {
     pub async fn get_cards_by_state(
         pool: &SqlitePool,
         state: String,
-    ) -> Result<Vec<FsrsCard>, String> {
+    ) -> Result<Vec<FsrsCard>, AppError> {
         sqlx::query_as::<_, FsrsCard>(
-            "SELECT id, problem_id, due, stability, difficulty, state, reps, lapses, elapsed_days, scheduled_days, created_at, updated_at 
+            "SELECT id, problem_id, due, stability, difficulty, state, reps, lapses, elapsed_days, scheduled_days, step, is_leech, is_suspended, buried_until, last_review, created_at, updated_at 
              FROM fsrs_cards WHERE state = ? ORDER BY due"
         )
         .bind(state)
         .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{seed_problem, test_pool};
+    use crate::models::fsrs::DEFAULT_W;
+    use crate::services::ProblemService;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn new_card() -> FsrsCard {
+        FsrsCard {
+            id: "c1".to_string(),
+            problem_id: "p1".to_string(),
+            due: "2024-01-01T00:00:00+00:00".to_string(),
+            stability: 1.0,
+            difficulty: 5.0,
+            state: "new".to_string(),
+            reps: 0,
+            lapses: 0,
+            elapsed_days: 0,
+            scheduled_days: 1,
+            step: 0,
+            is_leech: false,
+            is_suspended: false,
+            buried_until: None,
+            last_review: None,
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+        }
+    }
+
+    /// Apply an update the same way `process_review` persists it
+    fn apply(card: &mut FsrsCard, update: CardUpdate, now: DateTime<Utc>) {
+        card.state = update.state;
+        card.step = update.step;
+        card.stability = update.stability;
+        card.difficulty = update.difficulty;
+        card.scheduled_days = update.scheduled_days;
+        card.elapsed_days = update.elapsed_days;
+        card.due = update.due;
+        card.last_review = Some(now.to_rfc3339());
+        card.reps += 1;
+    }
+
+    #[test]
+    fn test_retrievability_is_ninety_percent_at_stability() {
+        assert_close(FsrsService::retrievability(10.0, 10.0), 0.9);
+        assert_close(FsrsService::retrievability(0.0, 10.0), 1.0);
+        assert!(FsrsService::retrievability(20.0, 10.0) < 0.9);
+    }
+
+    #[test]
+    fn test_initial_memory_state() {
+        let expected_d = [7.1949, 6.488305, 5.282434, 3.224502];
+        for (i, rating) in [Rating::Again, Rating::Hard, Rating::Good, Rating::Easy]
+            .into_iter()
+            .enumerate()
+        {
+            assert_close(
+                FsrsService::init_stability(&DEFAULT_W, rating),
+                DEFAULT_W[i],
+            );
+            assert_close(
+                FsrsService::init_difficulty(&DEFAULT_W, rating),
+                expected_d[i],
+            );
+        }
+    }
+
+    #[test]
+    fn test_review_state_transitions() {
+        // S = 10, D = 5, reviewed exactly when due
+        let r = FsrsService::retrievability(10.0, 10.0);
+
+        assert_close(
+            FsrsService::next_forget_stability(&DEFAULT_W, 5.0, 10.0, r),
+            2.107696,
+        );
+        assert_close(
+            FsrsService::next_recall_stability(&DEFAULT_W, 5.0, 10.0, r, Rating::Hard),
+            15.313912,
+        );
+        assert_close(
+            FsrsService::next_recall_stability(&DEFAULT_W, 5.0, 10.0, r, Rating::Good),
+            32.954264,
+        );
+        assert_close(
+            FsrsService::next_recall_stability(&DEFAULT_W, 5.0, 10.0, r, Rating::Easy),
+            78.628658,
+        );
+
+        assert_close(
+            FsrsService::next_difficulty(&DEFAULT_W, 5.0, Rating::Again),
+            6.607035,
+        );
+        assert_close(
+            FsrsService::next_difficulty(&DEFAULT_W, 5.0, Rating::Hard),
+            5.799434,
+        );
+        assert_close(
+            FsrsService::next_difficulty(&DEFAULT_W, 5.0, Rating::Good),
+            4.991833,
+        );
+        assert_close(
+            FsrsService::next_difficulty(&DEFAULT_W, 5.0, Rating::Easy),
+            4.184232,
+        );
+    }
+
+    #[test]
+    fn test_interval_follows_desired_retention() {
+        let mut params = FsrsParameters::default();
+        assert_eq!(FsrsService::next_interval(&params, 10.0), 10);
+
+        params.desired_retention = 0.95;
+        assert_eq!(FsrsService::next_interval(&params, 10.0), 5);
+
+        params.desired_retention = 0.85;
+        assert_eq!(FsrsService::next_interval(&params, 10.0), 16);
+
+        params.maximum_interval = 12;
+        assert_eq!(FsrsService::next_interval(&params, 10.0), 12);
+    }
+
+    #[test]
+    fn test_good_review_sequence() {
+        let params = FsrsParameters {
+            enable_fuzz: false,
+            ..Default::default()
+        };
+        let mut card = new_card();
+        let mut now = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        // First sight moves to the 10 minute learning step...
+        let update = FsrsService::calculate_fsrs_update(&card, Rating::Good, &params, now);
+        assert_eq!((update.state.as_str(), update.step), ("learning", 1));
+        assert_eq!((update.scheduled_days, update.scheduled_minutes), (0, 10));
+        apply(&mut card, update, now);
+
+        // ...and the same-day short-term review graduates it
+        now += chrono::Duration::minutes(10);
+        let update = FsrsService::calculate_fsrs_update(&card, Rating::Good, &params, now);
+        apply(&mut card, update, now);
+        assert_eq!(card.state, "review");
+        assert_close(card.stability, 4.466858);
+        assert_close(card.difficulty, 5.272968);
+
+        // Then every review happens exactly on its due date
+        let mut intervals = vec![card.scheduled_days];
+        for _ in 0..4 {
+            now += chrono::Duration::days(card.scheduled_days as i64);
+            let update = FsrsService::calculate_fsrs_update(&card, Rating::Good, &params, now);
+            apply(&mut card, update, now);
+            intervals.push(card.scheduled_days);
+        }
+        assert_eq!(intervals, vec![4, 14, 44, 125, 328]);
+        assert_close(card.stability, 328.473441);
+
+        // A lapse sends the card to relearning with a collapsed stability
+        now += chrono::Duration::days(card.scheduled_days as i64);
+        let update = FsrsService::calculate_fsrs_update(&card, Rating::Again, &params, now);
+        assert_eq!(update.state, "relearning");
+        assert_eq!(update.elapsed_days, 328);
+        assert_close(update.stability, 9.255949);
+        assert_close(update.difficulty, 6.765400);
+        assert_eq!((update.scheduled_days, update.scheduled_minutes), (0, 10));
+        assert_eq!(
+            update.due,
+            (now + chrono::Duration::minutes(10)).to_rfc3339()
+        );
+    }
+
+    #[test]
+    fn test_learning_steps() {
+        let mut params = FsrsParameters {
+            enable_fuzz: false,
+            ..Default::default()
+        };
+        let now = DateTime::parse_from_rfc3339("2024-03-01T09:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        let card = new_card();
+
+        let minutes = |card: &FsrsCard, rating, params: &FsrsParameters| {
+            let update = FsrsService::calculate_fsrs_update(card, rating, params, now);
+            (update.state, update.step, update.scheduled_minutes)
+        };
+
+        // Default steps: 1m, 10m
+        assert_eq!(
+            minutes(&card, Rating::Again, &params),
+            ("learning".to_string(), 0, 1)
+        );
+        assert_eq!(
+            minutes(&card, Rating::Hard, &params),
+            ("learning".to_string(), 0, 6)
+        );
+        assert_eq!(
+            minutes(&card, Rating::Good, &params),
+            ("learning".to_string(), 1, 10)
+        );
+        assert_eq!(minutes(&card, Rating::Easy, &params).0, "review");
+
+        // Last step: Hard repeats it, Good graduates
+        let mut learning = new_card();
+        learning.state = "learning".to_string();
+        learning.step = 1;
+        learning.stability = 3.173;
+        learning.difficulty = 5.28;
+        learning.last_review = Some(now.to_rfc3339());
+        assert_eq!(
+            minutes(&learning, Rating::Hard, &params),
+            ("learning".to_string(), 1, 10)
+        );
+        assert_eq!(
+            minutes(&learning, Rating::Again, &params),
+            ("learning".to_string(), 0, 1)
+        );
+        assert_eq!(
+            minutes(&learning, Rating::Good, &params),
+            ("review".to_string(), 0, 4 * 1440)
+        );
+
+        // Hours-long single step: Hard is 1.5x
+        params.learning_steps = vec![60];
+        assert_eq!(
+            minutes(&card, Rating::Hard, &params),
+            ("learning".to_string(), 0, 90)
+        );
+
+        // No steps at all: every first review graduates to whole days
+        params.learning_steps.clear();
+        let (state, _, scheduled) = minutes(&card, Rating::Again, &params);
+        assert_eq!((state.as_str(), scheduled), ("review", 1440));
+
+        // No relearning steps: a lapse stays in review
+        params.relearning_steps.clear();
+        let mut review = learning.clone();
+        review.state = "review".to_string();
+        review.last_review = Some((now - chrono::Duration::days(3)).to_rfc3339());
+        assert_eq!(minutes(&review, Rating::Again, &params).0, "review");
+    }
+
+    #[test]
+    fn test_fuzz_is_reproducible_and_bounded() {
+        let params = FsrsParameters::default();
+        let now = DateTime::parse_from_rfc3339("2024-03-01T09:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(FsrsService::fuzz_range(2, 0, 36500), (2, 2));
+        assert_eq!(FsrsService::fuzz_range(10, 0, 36500), (8, 12));
+        assert_eq!(FsrsService::fuzz_range(100, 0, 36500), (93, 107));
+        assert_eq!(FsrsService::fuzz_range(100, 0, 102), (93, 102));
+        assert_eq!(FsrsService::fuzz_range(10, 8, 36500), (9, 12));
+
+        let mut card = new_card();
+        card.state = "review".to_string();
+        card.stability = 30.0;
+        card.difficulty = 5.0;
+        card.reps = 4;
+        card.last_review = Some("2024-02-01T09:00:00+00:00".to_string());
+
+        let base = FsrsService::calculate_fsrs_update(
+            &card,
+            Rating::Good,
+            &FsrsParameters {
+                enable_fuzz: false,
+                ..Default::default()
+            },
+            now,
+        );
+        let (min, max) = FsrsService::fuzz_range(base.scheduled_days, 29, 36500);
+
+        let mut seen = std::collections::HashSet::new();
+        for n in 0..50 {
+            card.id = format!("card-{}", n);
+            let first = FsrsService::calculate_fsrs_update(&card, Rating::Good, &params, now);
+            let again = FsrsService::calculate_fsrs_update(&card, Rating::Good, &params, now);
+            assert_eq!(first.due, again.due);
+            assert!((min..=max).contains(&first.scheduled_days));
+            assert_close(first.stability, base.stability);
+            seen.insert(first.scheduled_days);
+        }
+        // Cards with identical histories no longer share one due date
+        assert!(seen.len() > 3);
+
+        // The next rep draws a fresh offset
+        let draws: std::collections::HashSet<i32> = (0..20)
+            .map(|reps| {
+                card.reps = reps;
+                FsrsService::calculate_fsrs_update(&card, Rating::Good, &params, now).scheduled_days
+            })
+            .collect();
+        assert!(draws.len() > 1);
+    }
+
+    #[test]
+    fn test_error_policy_effective_rating() {
+        let rate = |rating, solved, weight| {
+            FsrsService::rating_with_errors(ErrorPolicy::EffectiveRating, rating, solved, weight)
+        };
+
+        // Solved attempts: the error weight caps the chosen rating
+        assert_eq!(rate(Rating::Easy, true, 0.0), Rating::Easy);
+        assert_eq!(rate(Rating::Easy, true, 0.7), Rating::Good);
+        assert_eq!(rate(Rating::Good, true, 1.0), Rating::Hard);
+        assert_eq!(rate(Rating::Good, true, 1.5), Rating::Again);
+        assert_eq!(rate(Rating::Hard, true, 0.7), Rating::Hard);
+        // Two minor errors weigh as much as a typo
+        assert_eq!(rate(Rating::Good, true, 1.4), Rating::Hard);
+
+        // Failed attempts stay Again, minor errors or not
+        assert_eq!(rate(Rating::Again, false, 0.7), Rating::Again);
+        assert_eq!(rate(Rating::Again, false, 1.0), Rating::Again);
+        assert_eq!(rate(Rating::Again, false, 0.0), Rating::Again);
+
+        // Other policies leave the rating alone
+        for policy in [ErrorPolicy::None, ErrorPolicy::ScaleStability] {
+            assert_eq!(
+                FsrsService::rating_with_errors(policy, Rating::Good, true, 1.5),
+                Rating::Good
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_policy_scales_stability() {
+        let mut params = FsrsParameters {
+            enable_fuzz: false,
+            ..Default::default()
+        };
+        let now = DateTime::parse_from_rfc3339("2024-03-01T09:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut card = new_card();
+        card.state = "review".to_string();
+        card.stability = 10.0;
+        card.difficulty = 5.0;
+        card.last_review = Some("2024-02-20T09:00:00+00:00".to_string());
+
+        let review = |params: &FsrsParameters, rating, weight| {
+            let mut update = FsrsService::calculate_fsrs_update(&card, rating, params, now);
+            FsrsService::apply_error_weight(&card, params, weight, now, &mut update);
+            update
+        };
+
+        let clean = review(&params, Rating::Good, 0.0);
+        let typo = review(&params, Rating::Good, 1.0);
+        let conceptual = review(&params, Rating::Good, 1.5);
+        let unoptimized = review(&params, Rating::Good, 0.7);
+
+        assert_close(typo.stability, clean.stability / 2.0);
+        assert_close(conceptual.stability, clean.stability / 2.5);
+        assert_close(unoptimized.stability, clean.stability / 1.7);
+        // Every error shortens the interval, heavier ones more
+        assert!(unoptimized.scheduled_days < clean.scheduled_days);
+        assert!(typo.scheduled_days <= unoptimized.scheduled_days);
+        assert!(conceptual.scheduled_days <= typo.scheduled_days);
+        assert!(conceptual.scheduled_days < clean.scheduled_days);
+        assert_eq!(
+            conceptual.scheduled_days,
+            FsrsService::next_interval(&params, conceptual.stability)
+        );
+
+        // The divisor is bounded
+        let pile = review(&params, Rating::Good, 10.0);
+        assert_close(pile.stability, clean.stability / ERROR_DIVISOR_MAX);
+
+        // A lapse keeps its relearning step, only the stability drops
+        let lapse = review(&params, Rating::Again, 1.5);
+        assert_eq!(
+            (lapse.state.as_str(), lapse.scheduled_minutes),
+            ("relearning", 10)
+        );
+        assert_close(
+            lapse.stability,
+            review(&params, Rating::Again, 0.0).stability / 2.5,
+        );
+
+        params.error_policy = ErrorPolicy::None;
+        assert_close(
+            review(&params, Rating::Good, 1.5).stability,
+            clean.stability,
+        );
+    }
+
+    #[test]
+    fn test_leech_threshold() {
+        assert!(!FsrsService::is_leech(7, 8));
+        assert!(FsrsService::is_leech(8, 8));
+        assert!(FsrsService::is_leech(9, 8));
+        assert!(!FsrsService::is_leech(50, 0));
+    }
+
+    #[test]
+    fn test_least_loaded_day() {
+        // Days 8..=12 out
+        assert_eq!(FsrsService::least_loaded_day(8, &[5, 3, 4, 3, 9], 10), 9);
+        assert_eq!(FsrsService::least_loaded_day(8, &[5, 3, 4, 3, 9], 11), 11);
+        assert_eq!(FsrsService::least_loaded_day(8, &[0, 0, 0, 0, 0], 10), 10);
+        assert_eq!(FsrsService::least_loaded_day(8, &[], 10), 10);
+    }
+
+    #[test]
+    fn test_card_forgetting_curve() {
+        let from = DateTime::parse_from_rfc3339("2024-03-01T09:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut card = new_card();
+        assert_eq!(FsrsService::retrievability_at(&card, from), 0.0);
+
+        card.state = "review".to_string();
+        card.stability = 10.0;
+        card.last_review = Some(from.to_rfc3339());
+
+        let curve = FsrsService::forgetting_curve(&card, from, 20);
+        assert_eq!(curve.len(), 21);
+        assert_close(curve[0].retrievability, 1.0);
+        assert_close(curve[10].retrievability, 0.9);
+        assert!(curve
+            .windows(2)
+            .all(|w| w[1].retrievability < w[0].retrievability));
+
+        // Half a day in, the curve is between its daily samples
+        let noon = from + chrono::Duration::hours(12);
+        let r = FsrsService::retrievability_at(&card, noon);
+        assert!(r < curve[0].retrievability && r > curve[1].retrievability);
+    }
+
+    #[test]
+    fn test_preview_matches_each_rating() {
+        let params = FsrsParameters {
+            enable_fuzz: false,
+            ..Default::default()
+        };
+        let now = DateTime::parse_from_rfc3339("2024-03-01T09:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut card = new_card();
+        card.state = "review".to_string();
+        card.stability = 10.0;
+        card.difficulty = 5.0;
+        card.last_review = Some("2024-02-20T09:00:00+00:00".to_string());
+
+        let preview = FsrsService::preview(&card, &params, now);
+        assert_eq!(preview.len(), 4);
+
+        let intervals: Vec<i32> = preview.iter().map(|p| p.update.scheduled_days).collect();
+        assert_eq!(intervals, vec![0, 15, 33, 79]);
+        assert_eq!(preview[0].update.scheduled_minutes, 10);
+        assert_eq!(preview[0].update.state, "relearning");
+
+        for p in &preview {
+            let update = FsrsService::calculate_fsrs_update(&card, p.rating, &params, now);
+            assert_eq!(p.update.due, update.due);
+            assert_close(p.update.stability, update.stability);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stats_count_only_reviewable_cards_as_due() {
+        let pool = test_pool().await;
+        let problem = seed_problem(&pool).await;
+        ProblemService::create(
+            &pool,
+            problem.topic_id,
+            None,
+            "Sandwich".into(),
+            None,
+            None,
+            2,
+        )
+        .await
+        .unwrap();
+        // Every problem starts with a new card, due right away
+        let cards = FsrsService::get_due_cards(&pool).await.unwrap();
+        let due_today = || async { FsrsService::get_stats(&pool).await.unwrap().due_today };
+        assert_eq!(due_today().await, 2);
+
+        // Suspended and buried cards aren't up for review, so they aren't due either
+        FsrsService::suspend_card(&pool, cards[0].id.clone())
+            .await
+            .unwrap();
+        FsrsService::bury_card(&pool, cards[1].id.clone())
+            .await
+            .unwrap();
+        assert_eq!(due_today().await, 0);
+        assert!(FsrsService::get_due_cards(&pool).await.unwrap().is_empty());
     }
 }
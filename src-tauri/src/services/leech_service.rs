@@ -0,0 +1,167 @@
+use crate::error::AppError;
+use crate::models::leech::Leech;
+use crate::models::problem::UpdateProblemRequest;
+use crate::models::FsrsCard;
+use crate::services::{ErrorService, FsrsService, ProblemService};
+use sqlx::SqlitePool;
+
+pub struct LeechService;
+
+impl LeechService {
+    /// Leech cards with their error history, most lapses first
+    pub async fn list(
+        pool: &SqlitePool,
+        subject_id: Option<String>,
+    ) -> Result<Vec<Leech>, AppError> {
+        let cards = sqlx::query_as::<_, FsrsCard>(
+            "SELECT c.id, c.problem_id, c.due, c.stability, c.difficulty, c.state, c.reps, c.lapses, c.elapsed_days, c.scheduled_days, c.step, c.is_leech, c.is_suspended, c.buried_until, c.last_review, c.created_at, c.updated_at 
+             FROM fsrs_cards c
+             JOIN problems p ON c.problem_id = p.id
+             JOIN topics t ON p.topic_id = t.id
+             WHERE c.is_leech = 1 AND (? IS NULL OR t.subject_id = ?)
+             ORDER BY c.lapses DESC, c.updated_at DESC"
+        )
+        .bind(&subject_id)
+        .bind(&subject_id)
+        .fetch_all(pool)
+        .await?;
+
+        let mut leeches = Vec::with_capacity(cards.len());
+        for card in cards {
+            leeches.push(Self::with_details(pool, card).await?);
+        }
+        Ok(leeches)
+    }
+
+    /// Give the problem a fresh start: back to new, flags and lapse count cleared
+    pub async fn reset(pool: &SqlitePool, card_id: String) -> Result<FsrsCard, AppError> {
+        FsrsService::forget_card(pool, card_id).await
+    }
+
+    /// Rewrite the problem (usually to split or clarify it) and reset its card,
+    /// in one transaction
+    pub async fn rewrite(
+        pool: &SqlitePool,
+        card_id: String,
+        req: UpdateProblemRequest,
+    ) -> Result<Leech, AppError> {
+        let mut tx = pool.begin().await?;
+        let card = FsrsService::load_card(&mut tx, &card_id).await?;
+
+        ProblemService::apply_update(
+            &mut tx,
+            card.problem_id,
+            req.title,
+            req.description,
+            req.image_url,
+            req.difficulty,
+        )
+        .await?;
+        let card = FsrsService::forget(&mut tx, card_id).await?;
+        tx.commit().await?;
+
+        Self::with_details(pool, card).await
+    }
+
+    async fn with_details(pool: &SqlitePool, card: FsrsCard) -> Result<Leech, AppError> {
+        let problem = ProblemService::get_by_id(pool, card.problem_id.clone()).await?;
+        let errors = ErrorService::get_errors_by_problem(pool, card.problem_id.clone()).await?;
+
+        Ok(Leech {
+            card,
+            problem,
+            errors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{seed_problem, test_pool};
+    use crate::models::fsrs::{LeechAction, ReviewRequest, UpdateFsrsParameterSetRequest};
+    use crate::services::FsrsParameterService;
+
+    #[tokio::test]
+    async fn test_suspended_leech_is_listed_and_rewritten() {
+        let pool = test_pool().await;
+        let problem = seed_problem(&pool).await;
+        let global = FsrsParameterService::get_global(&pool).await.unwrap();
+        FsrsParameterService::update(
+            &pool,
+            global.id,
+            UpdateFsrsParameterSetRequest {
+                leech_threshold: Some(2),
+                leech_action: Some(LeechAction::Suspend),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let review = |rating| {
+            FsrsService::process_review(
+                &pool,
+                ReviewRequest {
+                    problem_id: problem.id.clone(),
+                    attempt_is_solved: rating > 1,
+                    rating,
+                    time_spent_seconds: 60,
+                    attempt_id: None,
+                },
+            )
+        };
+        // Graduate (two learning steps), lapse, relearn (one step), lapse again
+        let mut outcomes = vec![];
+        for rating in [3, 3, 1, 3, 1] {
+            let result = review(rating).await.unwrap();
+            outcomes.push((result.new_state, result.became_leech));
+        }
+        assert_eq!(
+            outcomes,
+            [
+                ("learning", false),
+                ("review", false),
+                ("relearning", false),
+                ("review", false),
+                ("relearning", true),
+            ]
+            .map(|(state, leech)| (state.to_string(), leech))
+        );
+
+        let leeches = LeechService::list(&pool, None).await.unwrap();
+        assert_eq!(leeches.len(), 1);
+        let card = &leeches[0].card;
+        assert!(card.is_leech && card.is_suspended);
+        assert_eq!(card.lapses, 2);
+
+        // Suspended: not due even once its relearning step is up
+        let card = FsrsService::set_card_due(&pool, card.id.clone(), "2024-01-01T00:00:00Z".into())
+            .await
+            .unwrap();
+        assert!(card.is_suspended);
+        assert!(FsrsService::get_due_cards(&pool).await.unwrap().is_empty());
+
+        // Rewriting it starts it over: new, due now, no longer a leech
+        let rewritten = LeechService::rewrite(
+            &pool,
+            card.id.clone(),
+            UpdateProblemRequest {
+                title: Some("Squeeze, one bound at a time".into()),
+                description: None,
+                image_url: None,
+                difficulty: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(rewritten.problem.title, "Squeeze, one bound at a time");
+        assert_eq!(rewritten.card.state, "new");
+        assert!(!rewritten.card.is_leech && !rewritten.card.is_suspended);
+        assert_eq!(rewritten.card.lapses, 0);
+        assert!(LeechService::list(&pool, None).await.unwrap().is_empty());
+        let due = FsrsService::get_due_cards(&pool).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, card.id);
+    }
+}
@@ -1,15 +1,37 @@
 pub mod attempt_service;
 pub mod error_log_service;
+pub mod fsrs_optimizer;
+pub mod fsrs_parameter_service;
 pub mod fsrs_service;
+pub mod leech_service;
 pub mod problem_service;
+pub mod reschedule_service;
+pub mod review_log_service;
+pub mod review_queue_service;
+pub mod settings_service;
+pub mod study_phase_service;
+pub mod study_session_service;
 pub mod subject_service;
 pub mod theory_service;
+pub mod time_budget_service;
 pub mod topic_service;
+pub mod workload_simulator;
 
 pub use attempt_service::AttemptService;
 pub use error_log_service::ErrorService;
+pub use fsrs_optimizer::FsrsOptimizer;
+pub use fsrs_parameter_service::FsrsParameterService;
 pub use fsrs_service::FsrsService;
+pub use leech_service::LeechService;
 pub use problem_service::ProblemService;
+pub use reschedule_service::RescheduleService;
+pub use review_log_service::ReviewLogService;
+pub use review_queue_service::ReviewQueueService;
+pub use settings_service::SettingsService;
+pub use study_phase_service::StudyPhaseService;
+pub use study_session_service::StudySessionService;
 pub use subject_service::SubjectService;
 pub use theory_service::TheoryService;
+pub use time_budget_service::TimeBudgetService;
 pub use topic_service::TopicService;
+pub use workload_simulator::WorkloadSimulator;
@@ -1,5 +1,7 @@
-use crate::models::Problem;
-use sqlx::SqlitePool;
+use crate::error::AppError;
+use crate::models::{Problem, ProblemMastery, ProblemTag};
+use crate::services::FsrsService;
+use sqlx::{SqliteConnection, SqlitePool};
 
 pub struct ProblemService;
 
@@ -12,10 +14,13 @@ impl ProblemService {
         description: Option<String>,
         image_url: Option<String>,
         difficulty: i32,
-    ) -> Result<Problem, String> {
+    ) -> Result<Problem, AppError> {
         let id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
 
+        // A problem never exists without its card
+        let mut tx = pool.begin().await?;
+
         sqlx::query(
             "INSERT INTO problems (id, topic_id, theory_id, title, description, image_url, difficulty, is_solved, total_unresolved_errors, created_at, updated_at) 
              VALUES (?, ?, ?, ?, ?, ?, ?, 0, 0, ?, ?)"
@@ -29,9 +34,11 @@ impl ProblemService {
         .bind(difficulty)
         .bind(&now)
         .bind(&now)
-        .execute(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .execute(&mut *tx)
+        .await?;
+
+        FsrsService::create_card(&mut tx, id.clone()).await?;
+        tx.commit().await?;
 
         Ok(Problem {
             id,
@@ -48,20 +55,24 @@ impl ProblemService {
         })
     }
 
-    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<Problem, String> {
+    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<Problem, AppError> {
+        Self::load(&mut *pool.acquire().await?, &id).await
+    }
+
+    async fn load(conn: &mut SqliteConnection, id: &str) -> Result<Problem, AppError> {
         sqlx::query_as::<_, Problem>(
             "SELECT id, topic_id, theory_id, title, description, image_url, difficulty, is_solved, total_unresolved_errors, created_at, updated_at FROM problems WHERE id = ?"
         )
         .bind(id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| e.to_string())
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("problem", id))
     }
 
     pub async fn list_by_topic(
         pool: &SqlitePool,
         topic_id: String,
-    ) -> Result<Vec<Problem>, String> {
+    ) -> Result<Vec<Problem>, AppError> {
         sqlx::query_as::<_, Problem>(
             "SELECT id, topic_id, theory_id, title, description, image_url, difficulty, is_solved, total_unresolved_errors, created_at, updated_at 
              FROM problems WHERE topic_id = ? ORDER BY created_at DESC"
@@ -69,13 +80,13 @@ impl ProblemService {
         .bind(topic_id)
         .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
     }
 
     pub async fn list_by_theory(
         pool: &SqlitePool,
         theory_id: String,
-    ) -> Result<Vec<Problem>, String> {
+    ) -> Result<Vec<Problem>, AppError> {
         sqlx::query_as::<_, Problem>(
             "SELECT id, topic_id, theory_id, title, description, image_url, difficulty, is_solved, total_unresolved_errors, created_at, updated_at 
              FROM problems WHERE theory_id = ? ORDER BY created_at DESC"
@@ -83,36 +94,46 @@ impl ProblemService {
         .bind(theory_id)
         .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+    }
+
+    pub async fn mark_solved(pool: &SqlitePool, id: String) -> Result<Problem, AppError> {
+        Self::set_solved(&mut *pool.acquire().await?, &id).await?;
+        Self::get_by_id(pool, id).await
     }
 
-    pub async fn mark_solved(pool: &SqlitePool, id: String) -> Result<Problem, String> {
+    pub(crate) async fn set_solved(conn: &mut SqliteConnection, id: &str) -> Result<(), AppError> {
         let now = chrono::Utc::now().to_rfc3339();
 
         sqlx::query("UPDATE problems SET is_solved = 1, updated_at = ? WHERE id = ?")
             .bind(&now)
-            .bind(&id)
-            .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .bind(id)
+            .execute(conn)
+            .await?;
 
-        Self::get_by_id(pool, id).await
+        Ok(())
     }
 
-    pub async fn update_unresolved_errors(
-        pool: &SqlitePool,
-        problem_id: String,
-        count: i32,
-    ) -> Result<(), String> {
+    /// Recompute the denormalized `total_unresolved_errors`; run it in the same
+    /// transaction as the error write so the two can't drift apart
+    pub(crate) async fn recount_unresolved_errors(
+        conn: &mut SqliteConnection,
+        problem_id: &str,
+    ) -> Result<(), AppError> {
         let now = chrono::Utc::now().to_rfc3339();
 
-        sqlx::query("UPDATE problems SET total_unresolved_errors = ?, updated_at = ? WHERE id = ?")
-            .bind(count)
-            .bind(&now)
-            .bind(&problem_id)
-            .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+        sqlx::query(
+            "UPDATE problems SET total_unresolved_errors = (
+                 SELECT COUNT(*) FROM attempt_errors ae
+                 JOIN attempts a ON ae.attempt_id = a.id
+                 WHERE a.problem_id = problems.id AND ae.is_resolved = 0
+             ), updated_at = ?
+             WHERE id = ?",
+        )
+        .bind(&now)
+        .bind(problem_id)
+        .execute(conn)
+        .await?;
 
         Ok(())
     }
@@ -120,7 +141,7 @@ impl ProblemService {
     pub async fn get_unresolved_error_count(
         pool: &SqlitePool,
         problem_id: String,
-    ) -> Result<i32, String> {
+    ) -> Result<i32, AppError> {
         let (count,): (i32,) = sqlx::query_as(
             "SELECT COUNT(*) FROM attempt_errors ae
              JOIN attempts a ON ae.attempt_id = a.id
@@ -128,8 +149,7 @@ impl ProblemService {
         )
         .bind(&problem_id)
         .fetch_one(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
         Ok(count)
     }
@@ -141,8 +161,25 @@ impl ProblemService {
         description: Option<String>,
         image_url: Option<String>,
         difficulty: Option<i32>,
-    ) -> Result<Problem, String> {
-        let problem = Self::get_by_id(pool, id.clone()).await?;
+    ) -> Result<Problem, AppError> {
+        let mut tx = pool.begin().await?;
+        let problem =
+            Self::apply_update(&mut tx, id, title, description, image_url, difficulty).await?;
+        tx.commit().await?;
+
+        Ok(problem)
+    }
+
+    /// `update` inside the caller's transaction; unset fields are kept
+    pub(crate) async fn apply_update(
+        conn: &mut SqliteConnection,
+        id: String,
+        title: Option<String>,
+        description: Option<String>,
+        image_url: Option<String>,
+        difficulty: Option<i32>,
+    ) -> Result<Problem, AppError> {
+        let problem = Self::load(&mut *conn, &id).await?;
         let now = chrono::Utc::now().to_rfc3339();
 
         let new_title = title.unwrap_or(problem.title.clone());
@@ -159,9 +196,8 @@ impl ProblemService {
         .bind(new_difficulty)
         .bind(&now)
         .bind(&id)
-        .execute(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .execute(conn)
+        .await?;
 
         Ok(Problem {
             id,
@@ -178,13 +214,146 @@ impl ProblemService {
         })
     }
 
-    pub async fn delete(pool: &SqlitePool, id: String) -> Result<(), String> {
+    pub async fn delete(pool: &SqlitePool, id: String) -> Result<(), AppError> {
         sqlx::query("DELETE FROM problems WHERE id = ?")
             .bind(id)
             .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         Ok(())
     }
+
+    /// Tag a problem; tagging it again with the same name is a no-op
+    pub async fn add_tag(
+        pool: &SqlitePool,
+        problem_id: String,
+        tag_name: String,
+    ) -> Result<Vec<ProblemTag>, AppError> {
+        let tag_name = tag_name.trim().to_lowercase();
+        if tag_name.is_empty() {
+            return Err(AppError::validation("tag_name", "Tag name cannot be empty"));
+        }
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO problem_tags (id, problem_id, tag_name, created_at) 
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&problem_id)
+        .bind(&tag_name)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(pool)
+        .await?;
+
+        Self::list_tags(pool, problem_id).await
+    }
+
+    pub async fn remove_tag(
+        pool: &SqlitePool,
+        problem_id: String,
+        tag_name: String,
+    ) -> Result<Vec<ProblemTag>, AppError> {
+        sqlx::query("DELETE FROM problem_tags WHERE problem_id = ? AND tag_name = ?")
+            .bind(&problem_id)
+            .bind(tag_name.trim().to_lowercase())
+            .execute(pool)
+            .await?;
+
+        Self::list_tags(pool, problem_id).await
+    }
+
+    pub async fn list_tags(
+        pool: &SqlitePool,
+        problem_id: String,
+    ) -> Result<Vec<ProblemTag>, AppError> {
+        sqlx::query_as::<_, ProblemTag>(
+            "SELECT id, problem_id, tag_name, created_at FROM problem_tags 
+             WHERE problem_id = ? ORDER BY tag_name",
+        )
+        .bind(problem_id)
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    pub async fn list_by_tag(
+        pool: &SqlitePool,
+        tag_name: String,
+    ) -> Result<Vec<Problem>, AppError> {
+        sqlx::query_as::<_, Problem>(
+            "SELECT p.id, p.topic_id, p.theory_id, p.title, p.description, p.image_url, p.difficulty, p.is_solved, p.total_unresolved_errors, p.created_at, p.updated_at 
+             FROM problems p JOIN problem_tags t ON t.problem_id = p.id 
+             WHERE t.tag_name = ? ORDER BY p.created_at DESC"
+        )
+        .bind(tag_name.trim().to_lowercase())
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// Attempt statistics, or `None` for a problem that was never attempted
+    pub async fn get_mastery(
+        pool: &SqlitePool,
+        problem_id: String,
+    ) -> Result<Option<ProblemMastery>, AppError> {
+        sqlx::query_as::<_, ProblemMastery>(
+            "SELECT problem_id, solved, mastery_percent, attempt_count, updated_at 
+             FROM problem_mastery WHERE problem_id = ?",
+        )
+        .bind(problem_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// Full-text search over titles, descriptions and tags, best matches first.
+    /// Every word must match, as a prefix.
+    pub async fn search(
+        pool: &SqlitePool,
+        query: String,
+        limit: Option<i64>,
+    ) -> Result<Vec<Problem>, AppError> {
+        let Some(query) = Self::fts_query(&query) else {
+            return Ok(Vec::new());
+        };
+
+        sqlx::query_as::<_, Problem>(
+            "SELECT p.id, p.topic_id, p.theory_id, p.title, p.description, p.image_url, p.difficulty, p.is_solved, p.total_unresolved_errors, p.created_at, p.updated_at 
+             FROM problems_fts f JOIN problems p ON p.id = f.id 
+             WHERE problems_fts MATCH ? ORDER BY f.rank LIMIT ?"
+        )
+        .bind(query)
+        .bind(limit.unwrap_or(50))
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// User input as an FTS5 query: each word quoted (so operators and punctuation
+    /// are taken literally) and matched as a prefix
+    fn fts_query(input: &str) -> Option<String> {
+        let terms: Vec<String> = input
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect();
+        (!terms.is_empty()).then(|| terms.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query_quotes_terms() {
+        assert_eq!(
+            ProblemService::fts_query("  chain rule "),
+            Some("\"chain\"* \"rule\"*".to_string())
+        );
+        assert_eq!(
+            ProblemService::fts_query("x^2 \"AND\" OR"),
+            Some("\"x^2\"* \"\"\"AND\"\"\"* \"OR\"*".to_string())
+        );
+        assert_eq!(ProblemService::fts_query("   "), None);
+    }
 }
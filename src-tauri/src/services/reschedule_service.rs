@@ -0,0 +1,421 @@
+use crate::error::AppError;
+use crate::models::fsrs::{FsrsParameters, Rating};
+use crate::models::reschedule::{RescheduleSummary, RescheduledCard};
+use crate::models::review_log::ReviewKind;
+use crate::models::{FsrsCard, ReviewLog};
+use crate::services::{FsrsParameterService, FsrsService, ReviewLogService};
+use chrono::{DateTime, Local, Utc};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+
+pub struct RescheduleService;
+
+/// Review log row: (card_id, rating, kind, state_before, elapsed_days, stability_penalty)
+type LogRow = (String, i32, ReviewKind, String, i32, f64);
+
+#[derive(sqlx::FromRow)]
+struct SubjectCard {
+    #[sqlx(flatten)]
+    card: FsrsCard,
+    subject_id: String,
+}
+
+impl RescheduleService {
+    /// What `apply` would change, without writing anything
+    pub async fn preview(
+        pool: &SqlitePool,
+        subject_id: Option<String>,
+    ) -> Result<RescheduleSummary, AppError> {
+        Self::reschedule_all(pool, subject_id, false).await
+    }
+
+    /// Recompute every card under the parameters now in effect for its subject.
+    /// All cards are updated in one transaction; each changed card gets a `reschedule`
+    /// log entry, so it can be undone like a review.
+    pub async fn apply(
+        pool: &SqlitePool,
+        subject_id: Option<String>,
+    ) -> Result<RescheduleSummary, AppError> {
+        Self::reschedule_all(pool, subject_id, true).await
+    }
+
+    async fn reschedule_all(
+        pool: &SqlitePool,
+        subject_id: Option<String>,
+        apply: bool,
+    ) -> Result<RescheduleSummary, AppError> {
+        let global = FsrsParameterService::get_global(pool)
+            .await?
+            .to_parameters();
+        let mut params_by_subject: HashMap<String, FsrsParameters> = HashMap::new();
+        for set in FsrsParameterService::list_all(pool).await? {
+            if let Some(id) = set.subject_id.clone() {
+                params_by_subject.insert(id, set.to_parameters());
+            }
+        }
+        let now = Utc::now();
+
+        // A preview runs the same way and simply never commits
+        let mut tx = pool.begin().await?;
+
+        let cards = sqlx::query_as::<_, SubjectCard>(
+            "SELECT c.id, c.problem_id, c.due, c.stability, c.difficulty, c.state, c.reps, c.lapses, c.elapsed_days, c.scheduled_days, c.step, c.is_leech, c.is_suspended, c.buried_until, c.last_review, c.created_at, c.updated_at,
+                    t.subject_id
+             FROM fsrs_cards c
+             JOIN problems p ON c.problem_id = p.id
+             JOIN topics t ON p.topic_id = t.id
+             WHERE c.state != 'new' AND (? IS NULL OR t.subject_id = ?)
+             ORDER BY c.due",
+        )
+        .bind(&subject_id)
+        .bind(&subject_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let history = Self::load_history(&mut tx, &subject_id).await?;
+
+        let total_cards = cards.len() as i64;
+        let mut replayed = 0;
+        let mut changed = Vec::new();
+
+        for SubjectCard { card, subject_id } in cards {
+            let params = params_by_subject.get(&subject_id).unwrap_or(&global);
+            let memory = history
+                .get(&card.id)
+                .and_then(|rows| Self::replay(&params.w, rows));
+            replayed += memory.is_some() as i64;
+
+            let after = Self::reschedule(&card, memory, params);
+            if !Self::differs(&card, &after) {
+                continue;
+            }
+
+            if apply {
+                let after = FsrsCard {
+                    updated_at: now.to_rfc3339(),
+                    ..after.clone()
+                };
+                let log = ReviewLog {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    card_id: card.id.clone(),
+                    problem_id: card.problem_id.clone(),
+                    rating: 0,
+                    kind: ReviewKind::Reschedule,
+                    state_before: card.state.clone(),
+                    state_after: after.state.clone(),
+                    stability_before: card.stability,
+                    stability_after: after.stability,
+                    difficulty_before: card.difficulty,
+                    difficulty_after: after.difficulty,
+                    stability_penalty: 1.0,
+                    elapsed_days: 0,
+                    scheduled_days: after.scheduled_days,
+                    duration_seconds: 0,
+                    reviewed_at: after.updated_at.clone(),
+                };
+                FsrsService::save_card(&mut tx, &after).await?;
+                ReviewLogService::insert(&mut tx, &log, &card).await?;
+            }
+
+            changed.push(RescheduledCard {
+                card_id: card.id,
+                problem_id: card.problem_id,
+                replayed: memory.is_some(),
+                stability_before: card.stability,
+                stability_after: after.stability,
+                difficulty_before: card.difficulty,
+                difficulty_after: after.difficulty,
+                interval_before: card.scheduled_days,
+                interval_after: after.scheduled_days,
+                due_before: card.due,
+                due_after: after.due,
+            });
+        }
+
+        if apply {
+            tx.commit().await?;
+        }
+
+        let day = |due: &str| {
+            DateTime::parse_from_rfc3339(due)
+                .ok()
+                .map(|t| t.with_timezone(&Local).date_naive())
+        };
+        let shifts: Vec<_> = changed
+            .iter()
+            .map(|c| day(&c.due_after).cmp(&day(&c.due_before)))
+            .collect();
+
+        Ok(RescheduleSummary {
+            applied: apply,
+            total_cards,
+            replayed,
+            moved: shifts.iter().filter(|s| s.is_ne()).count() as i64,
+            earlier: shifts.iter().filter(|s| s.is_lt()).count() as i64,
+            later: shifts.iter().filter(|s| s.is_gt()).count() as i64,
+            cards: changed,
+        })
+    }
+
+    /// Review log rows that can be replayed, grouped by card in review order
+    async fn load_history(
+        conn: &mut SqliteConnection,
+        subject_id: &Option<String>,
+    ) -> Result<HashMap<String, Vec<LogRow>>, AppError> {
+        let rows: Vec<LogRow> = sqlx::query_as(
+            "SELECT r.card_id, r.rating, r.kind, r.state_before, r.elapsed_days, r.stability_penalty FROM fsrs_reviews r
+             JOIN problems p ON r.problem_id = p.id
+             JOIN topics t ON p.topic_id = t.id
+             WHERE ((r.kind = 'review' AND r.rating BETWEEN 1 AND 4) OR r.kind IN ('reset', 'forget'))
+               AND (? IS NULL OR t.subject_id = ?)
+             ORDER BY r.card_id, r.reviewed_at, r.rowid",
+        )
+        .bind(subject_id)
+        .bind(subject_id)
+        .fetch_all(conn)
+        .await?;
+
+        let mut history: HashMap<String, Vec<LogRow>> = HashMap::new();
+        for row in rows {
+            history.entry(row.0.clone()).or_default().push(row);
+        }
+        Ok(history)
+    }
+
+    /// Memory state after replaying a card's log with `w`, from its last reset or forget.
+    /// `None` when the log doesn't reach back to the card's first review.
+    /// Error penalties are re-applied as logged, not re-derived from today's error weights.
+    pub fn replay(w: &[f64; 19], rows: &[LogRow]) -> Option<(f64, f64)> {
+        let start = rows
+            .iter()
+            .rposition(|(_, _, kind, _, _, _)| *kind != ReviewKind::Review)
+            .map_or(0, |i| i + 1);
+        let reviews = &rows[start..];
+        if reviews.first()?.3 != "new" {
+            return None;
+        }
+
+        let mut memory = None;
+        for (_, rating, _, _, elapsed_days, penalty) in reviews {
+            let rating = Rating::from_u8(*rating as u8)?;
+            let (mut stability, difficulty) =
+                FsrsService::next_memory_state(w, memory, *elapsed_days, rating);
+            if *penalty > 1.0 {
+                stability = FsrsService::penalize_stability(stability, *penalty);
+            }
+            memory = Some((stability, difficulty));
+        }
+        memory
+    }
+
+    /// The card with its memory state (when replayed) and review interval recomputed.
+    /// A review whose interval still lies within the fuzz range of the new one keeps
+    /// its due date, so fuzz and load balancing aren't undone for nothing.
+    /// Learning cards keep their step schedule.
+    pub fn reschedule(
+        card: &FsrsCard,
+        memory: Option<(f64, f64)>,
+        params: &FsrsParameters,
+    ) -> FsrsCard {
+        let mut after = card.clone();
+        if let Some((stability, difficulty)) = memory {
+            after.stability = stability;
+            after.difficulty = difficulty;
+        }
+
+        let last_review = card
+            .last_review
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+        let Some(last_review) = last_review.filter(|_| card.state == "review") else {
+            return after;
+        };
+
+        let interval = FsrsService::next_interval(params, after.stability);
+        let (min, max) = if params.enable_fuzz || params.enable_load_balance {
+            FsrsService::fuzz_range(interval, card.elapsed_days, params.maximum_interval)
+        } else {
+            (interval, interval)
+        };
+        if !(min..=max).contains(&card.scheduled_days) {
+            let interval =
+                FsrsService::review_interval(card, params, after.stability, card.elapsed_days);
+            after.scheduled_days = interval;
+            after.due = (last_review.with_timezone(&Utc) + chrono::Duration::days(interval as i64))
+                .to_rfc3339();
+        }
+        after
+    }
+
+    fn differs(before: &FsrsCard, after: &FsrsCard) -> bool {
+        before.due != after.due
+            || (before.stability - after.stability).abs() > 1e-9
+            || (before.difficulty - after.difficulty).abs() > 1e-9
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{seed_problem, test_pool};
+    use crate::models::attempt::{NewAttemptError, SubmitAttemptRequest};
+    use crate::models::fsrs::DEFAULT_W;
+    use crate::services::AttemptService;
+
+    fn row(rating: i32, kind: ReviewKind, state_before: &str, elapsed_days: i32) -> LogRow {
+        (
+            "card".to_string(),
+            rating,
+            kind,
+            state_before.to_string(),
+            elapsed_days,
+            1.0,
+        )
+    }
+
+    fn review_card(stability: f64, scheduled_days: i32) -> FsrsCard {
+        let last_review = "2024-03-01T09:00:00+00:00";
+        FsrsCard {
+            id: "card".to_string(),
+            problem_id: "problem".to_string(),
+            due: (DateTime::parse_from_rfc3339(last_review).unwrap()
+                + chrono::Duration::days(scheduled_days as i64))
+            .to_rfc3339(),
+            stability,
+            difficulty: 5.0,
+            state: "review".to_string(),
+            reps: 4,
+            lapses: 0,
+            elapsed_days: 3,
+            scheduled_days,
+            step: 0,
+            is_leech: false,
+            is_suspended: false,
+            buried_until: None,
+            last_review: Some(last_review.to_string()),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_replay_from_last_restart() {
+        let w = DEFAULT_W;
+        let first = FsrsService::next_memory_state(&w, None, 0, Rating::Good);
+        let expected = FsrsService::next_memory_state(&w, Some(first), 4, Rating::Good);
+
+        let rows = vec![
+            row(3, ReviewKind::Review, "new", 0),
+            row(3, ReviewKind::Review, "review", 4),
+        ];
+        assert_eq!(RescheduleService::replay(&w, &rows), Some(expected));
+
+        // Only what follows a reset counts
+        let rows = vec![
+            row(1, ReviewKind::Review, "new", 0),
+            row(0, ReviewKind::Reset, "review", 0),
+            row(3, ReviewKind::Review, "new", 0),
+            row(3, ReviewKind::Review, "review", 4),
+        ];
+        assert_eq!(RescheduleService::replay(&w, &rows), Some(expected));
+
+        // A logged error penalty is applied again
+        let mut penalized = row(3, ReviewKind::Review, "review", 4);
+        penalized.5 = 2.5;
+        let rows = vec![row(3, ReviewKind::Review, "new", 0), penalized];
+        assert_eq!(
+            RescheduleService::replay(&w, &rows),
+            Some((expected.0 / 2.5, expected.1))
+        );
+
+        // History that starts mid-way can't be replayed
+        let rows = vec![row(3, ReviewKind::Review, "review", 5)];
+        assert_eq!(RescheduleService::replay(&w, &rows), None);
+        assert_eq!(RescheduleService::replay(&w, &[]), None);
+    }
+
+    #[test]
+    fn test_reschedule_follows_retention() {
+        let params = FsrsParameters {
+            enable_fuzz: false,
+            ..Default::default()
+        };
+        let interval = FsrsService::next_interval(&params, 20.0);
+        let card = review_card(20.0, interval);
+
+        // Nothing changed: the card stays put
+        let after = RescheduleService::reschedule(&card, None, &params);
+        assert!(!RescheduleService::differs(&card, &after));
+
+        // Higher retention pulls the review in, counted from the last review
+        let strict = FsrsParameters {
+            desired_retention: 0.95,
+            ..params.clone()
+        };
+        let after = RescheduleService::reschedule(&card, None, &strict);
+        assert!(after.scheduled_days < card.scheduled_days);
+        assert_eq!(
+            after.scheduled_days,
+            FsrsService::next_interval(&strict, 20.0)
+        );
+        assert!(after.due < card.due);
+
+        // A replayed memory state replaces the stored one
+        let after = RescheduleService::reschedule(&card, Some((60.0, 4.0)), &params);
+        assert_eq!((after.stability, after.difficulty), (60.0, 4.0));
+        assert!(after.scheduled_days > card.scheduled_days);
+
+        // Within the fuzz range of the new interval, the due date is kept
+        let fuzzed = FsrsParameters {
+            enable_fuzz: true,
+            ..params.clone()
+        };
+        let after = RescheduleService::reschedule(&card, None, &fuzzed);
+        assert_eq!(after.due, card.due);
+
+        // Learning cards only get their memory state updated
+        let learning = FsrsCard {
+            state: "learning".to_string(),
+            ..card.clone()
+        };
+        let after = RescheduleService::reschedule(&learning, Some((2.0, 6.0)), &strict);
+        assert_eq!((after.due, after.stability), (learning.due, 2.0));
+    }
+
+    #[tokio::test]
+    async fn test_penalized_reviews_replay_unchanged() {
+        let pool = test_pool().await;
+        let problem = seed_problem(&pool).await;
+        let submit = |error_type_id: Option<i32>| SubmitAttemptRequest {
+            problem_id: problem.id.clone(),
+            is_solved: true,
+            commentary: None,
+            errors: error_type_id
+                .map(|error_type_id| NewAttemptError {
+                    error_type_id,
+                    description: None,
+                })
+                .into_iter()
+                .collect(),
+            rating: Some(3),
+            time_spent_seconds: 60,
+        };
+        AttemptService::submit(&pool, submit(None)).await.unwrap();
+        AttemptService::submit(&pool, submit(Some(1)))
+            .await
+            .unwrap();
+
+        let (penalty,): (f64,) = sqlx::query_as(
+            "SELECT stability_penalty FROM fsrs_reviews ORDER BY reviewed_at DESC, rowid DESC",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(penalty > 1.0);
+
+        // With the parameters unchanged, the replay lands where the reviews left the card
+        let summary = RescheduleService::preview(&pool, None).await.unwrap();
+        assert_eq!(summary.replayed, 1);
+        assert!(summary.cards.is_empty());
+    }
+}
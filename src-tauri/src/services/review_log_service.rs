@@ -0,0 +1,265 @@
+use crate::error::AppError;
+use crate::models::review_log::{ReviewKind, ReviewLog, ReviewLogFilter};
+use crate::models::FsrsCard;
+use sqlx::{SqliteConnection, SqlitePool};
+
+pub struct ReviewLogService;
+
+impl ReviewLogService {
+    /// Insert a log entry; takes a connection so callers can run it inside their transaction.
+    /// `card_before` is snapshotted so the review can be undone exactly. Reviews are
+    /// tied to the running study session, if there is one; manual actions and
+    /// reschedules never are.
+    pub async fn insert(
+        conn: &mut SqliteConnection,
+        log: &ReviewLog,
+        card_before: &FsrsCard,
+    ) -> Result<(), AppError> {
+        let snapshot = serde_json::to_string(card_before)?;
+
+        sqlx::query(
+            "INSERT INTO fsrs_reviews (id, card_id, problem_id, rating, kind, state_before, state_after, stability_before, stability_after, difficulty_before, difficulty_after, stability_penalty, elapsed_days, scheduled_days, duration_seconds, reviewed_at, card_before, session_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+                     CASE WHEN ? THEN (SELECT id FROM study_sessions WHERE status = 'running') END)"
+        )
+        .bind(&log.id)
+        .bind(&log.card_id)
+        .bind(&log.problem_id)
+        .bind(log.rating)
+        .bind(log.kind)
+        .bind(&log.state_before)
+        .bind(&log.state_after)
+        .bind(log.stability_before)
+        .bind(log.stability_after)
+        .bind(log.difficulty_before)
+        .bind(log.difficulty_after)
+        .bind(log.stability_penalty)
+        .bind(log.elapsed_days)
+        .bind(log.scheduled_days)
+        .bind(log.duration_seconds)
+        .bind(&log.reviewed_at)
+        .bind(&snapshot)
+        .bind(log.kind == ReviewKind::Review)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Full history of one card, oldest first
+    pub async fn list_by_card(
+        pool: &SqlitePool,
+        card_id: String,
+    ) -> Result<Vec<ReviewLog>, AppError> {
+        sqlx::query_as::<_, ReviewLog>(
+            "SELECT id, card_id, problem_id, rating, kind, state_before, state_after, stability_before, stability_after, difficulty_before, difficulty_after, stability_penalty, elapsed_days, scheduled_days, duration_seconds, reviewed_at 
+             FROM fsrs_reviews WHERE card_id = ? ORDER BY reviewed_at, rowid"
+        )
+        .bind(card_id)
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// Global log, newest first
+    pub async fn list(
+        pool: &SqlitePool,
+        filter: ReviewLogFilter,
+    ) -> Result<Vec<ReviewLog>, AppError> {
+        sqlx::query_as::<_, ReviewLog>(
+            "SELECT r.id, r.card_id, r.problem_id, r.rating, r.kind, r.state_before, r.state_after, r.stability_before, r.stability_after, r.difficulty_before, r.difficulty_after, r.stability_penalty, r.elapsed_days, r.scheduled_days, r.duration_seconds, r.reviewed_at
+             FROM fsrs_reviews r
+             JOIN problems p ON r.problem_id = p.id
+             JOIN topics t ON p.topic_id = t.id
+             WHERE (? IS NULL OR t.subject_id = ?)
+               AND (? IS NULL OR p.topic_id = ?)
+               AND (? IS NULL OR r.problem_id = ?)
+               AND (? IS NULL OR r.rating = ?)
+               AND (? IS NULL OR r.reviewed_at >= ?)
+               AND (? IS NULL OR r.reviewed_at < ?)
+             ORDER BY r.reviewed_at DESC, r.rowid DESC
+             LIMIT ? OFFSET ?",
+        )
+        .bind(&filter.subject_id)
+        .bind(&filter.subject_id)
+        .bind(&filter.topic_id)
+        .bind(&filter.topic_id)
+        .bind(&filter.problem_id)
+        .bind(&filter.problem_id)
+        .bind(filter.rating)
+        .bind(filter.rating)
+        .bind(&filter.from)
+        .bind(&filter.from)
+        .bind(&filter.to)
+        .bind(&filter.to)
+        .bind(filter.limit.unwrap_or(-1)) // SQLite: negative LIMIT means no limit
+        .bind(filter.offset.unwrap_or(0))
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// Most recent log entry, review or manual action, (optionally for one card) together with the card
+    /// snapshot taken before it; entries logged without a snapshot are skipped
+    pub async fn latest_with_snapshot(
+        conn: &mut SqliteConnection,
+        card_id: Option<String>,
+    ) -> Result<Option<(String, FsrsCard)>, AppError> {
+        let row: Option<(String, String)> = sqlx::query_as(
+            "SELECT id, card_before FROM fsrs_reviews 
+             WHERE card_before IS NOT NULL AND (? IS NULL OR card_id = ?) 
+             ORDER BY reviewed_at DESC, rowid DESC LIMIT 1",
+        )
+        .bind(&card_id)
+        .bind(&card_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        match row {
+            Some((id, snapshot)) => {
+                let card = serde_json::from_str(&snapshot)?;
+                Ok(Some((id, card)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn delete(conn: &mut SqliteConnection, id: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM fsrs_reviews WHERE id = ?")
+            .bind(id)
+            .execute(conn)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{seed_problem, test_pool};
+    use crate::services::{FsrsService, ProblemService};
+
+    fn log(
+        id: &str,
+        card: &FsrsCard,
+        kind: ReviewKind,
+        rating: i32,
+        reviewed_at: &str,
+    ) -> ReviewLog {
+        ReviewLog {
+            id: id.to_string(),
+            card_id: card.id.clone(),
+            problem_id: card.problem_id.clone(),
+            rating,
+            kind,
+            state_before: card.state.clone(),
+            state_after: card.state.clone(),
+            stability_before: card.stability,
+            stability_after: card.stability,
+            difficulty_before: card.difficulty,
+            difficulty_after: card.difficulty,
+            stability_penalty: 1.0,
+            elapsed_days: 0,
+            scheduled_days: 1,
+            duration_seconds: 60,
+            reviewed_at: reviewed_at.to_string(),
+        }
+    }
+
+    fn ids(logs: &[ReviewLog]) -> Vec<&str> {
+        logs.iter().map(|l| l.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_lists_filter_and_order_the_log() {
+        let pool = test_pool().await;
+        let problem = seed_problem(&pool).await;
+        let other = ProblemService::create(
+            &pool,
+            problem.topic_id,
+            None,
+            "Sandwich".into(),
+            None,
+            None,
+            2,
+        )
+        .await
+        .unwrap();
+        let card = |problem_id: String| async {
+            let mut conn = pool.acquire().await.unwrap();
+            FsrsService::get_or_create_card(&mut conn, problem_id)
+                .await
+                .unwrap()
+        };
+        let (a, b) = (card(problem.id).await, card(other.id.clone()).await);
+
+        let mut conn = pool.acquire().await.unwrap();
+        for (id, card, kind, rating, reviewed_at) in [
+            ("a1", &a, ReviewKind::Review, 3, "2024-03-01T09:00:00+00:00"),
+            (
+                "a2",
+                &a,
+                ReviewKind::Suspend,
+                0,
+                "2024-03-02T00:00:00+00:00",
+            ),
+            ("b1", &b, ReviewKind::Review, 4, "2024-03-02T12:00:00+00:00"),
+            ("a3", &a, ReviewKind::Review, 1, "2024-03-03T09:00:00+00:00"),
+        ] {
+            let entry = log(id, card, kind, rating, reviewed_at);
+            ReviewLogService::insert(&mut conn, &entry, card)
+                .await
+                .unwrap();
+        }
+        drop(conn);
+
+        // One card's history, manual actions included, oldest first
+        let history = ReviewLogService::list_by_card(&pool, a.id.clone())
+            .await
+            .unwrap();
+        assert_eq!(ids(&history), ["a1", "a2", "a3"]);
+        assert_eq!(
+            history.iter().map(|l| l.kind).collect::<Vec<_>>(),
+            [ReviewKind::Review, ReviewKind::Suspend, ReviewKind::Review]
+        );
+
+        let list = |filter| ReviewLogService::list(&pool, filter);
+        assert_eq!(
+            ids(&list(ReviewLogFilter::default()).await.unwrap()),
+            ["a3", "b1", "a2", "a1"]
+        );
+        let by_problem = list(ReviewLogFilter {
+            problem_id: Some(other.id),
+            ..Default::default()
+        });
+        assert_eq!(ids(&by_problem.await.unwrap()), ["b1"]);
+        // Manual actions are the entries rated 0
+        let manual = list(ReviewLogFilter {
+            rating: Some(0),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        assert_eq!(ids(&manual), ["a2"]);
+        assert_eq!(manual[0].kind, ReviewKind::Suspend);
+        // From is inclusive, to exclusive
+        let day = list(ReviewLogFilter {
+            from: Some("2024-03-02T00:00:00+00:00".into()),
+            to: Some("2024-03-03T09:00:00+00:00".into()),
+            ..Default::default()
+        });
+        assert_eq!(ids(&day.await.unwrap()), ["b1", "a2"]);
+        let page = list(ReviewLogFilter {
+            limit: Some(2),
+            offset: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(ids(&page.await.unwrap()), ["b1", "a2"]);
+        let elsewhere = list(ReviewLogFilter {
+            subject_id: Some("missing".into()),
+            ..Default::default()
+        });
+        assert!(elsewhere.await.unwrap().is_empty());
+    }
+}
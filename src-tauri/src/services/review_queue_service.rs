@@ -0,0 +1,312 @@
+use crate::error::AppError;
+use crate::models::review_queue::{
+    QueueOptions, QueueOrder, QueuedCard, ReviewQueue, StudiedToday,
+};
+use crate::services::FsrsService;
+use chrono::{DateTime, Datelike, Local, Utc};
+use sqlx::SqlitePool;
+use std::collections::{HashMap, VecDeque};
+
+pub struct ReviewQueueService;
+
+impl ReviewQueueService {
+    /// Assemble today's study session from the due and new cards
+    pub async fn build(pool: &SqlitePool, options: QueueOptions) -> Result<ReviewQueue, AppError> {
+        let now = Utc::now();
+        let day_start = FsrsService::local_day_start(now);
+        let day_end = day_start + chrono::Duration::days(1);
+
+        let studied = Self::studied_since(pool, options.subject_id.clone(), day_start).await?;
+
+        let candidates = sqlx::query_as::<_, QueuedCard>(
+            "SELECT c.id, c.problem_id, c.due, c.stability, c.difficulty, c.state, c.reps, c.lapses, c.elapsed_days, c.scheduled_days, c.step, c.is_leech, c.is_suspended, c.buried_until, c.last_review, c.created_at, c.updated_at,
+                    t.id AS topic_id
+             FROM fsrs_cards c
+             JOIN problems p ON c.problem_id = p.id
+             JOIN topics t ON p.topic_id = t.id
+             WHERE (? IS NULL OR t.subject_id = ?) AND c.is_suspended = 0
+               AND (c.buried_until IS NULL OR c.buried_until <= ?)
+               AND (c.state = 'new' OR c.due < ?)
+             ORDER BY c.due, c.created_at"
+        )
+        .bind(&options.subject_id)
+        .bind(&options.subject_id)
+        .bind(now.to_rfc3339())
+        .bind(day_end.to_rfc3339())
+        .fetch_all(pool)
+        .await?;
+
+        Ok(Self::assemble(
+            candidates, &options, studied, now, day_start,
+        ))
+    }
+
+    /// Cards already answered since `since`, counted by the state they were in
+    pub async fn studied_since(
+        pool: &SqlitePool,
+        subject_id: Option<String>,
+        since: DateTime<Utc>,
+    ) -> Result<StudiedToday, AppError> {
+        sqlx::query_as::<_, StudiedToday>(
+            "SELECT COUNT(CASE WHEN r.state_before = 'new' THEN 1 END) AS new_cards,
+                    COUNT(CASE WHEN r.state_before = 'review' THEN 1 END) AS reviews,
+                    COUNT(CASE WHEN r.state_before IN ('learning', 'relearning') THEN 1 END) AS learning
+             FROM fsrs_reviews r
+             JOIN problems p ON r.problem_id = p.id
+             JOIN topics t ON p.topic_id = t.id
+             WHERE r.kind = 'review' AND r.reviewed_at >= ? AND (? IS NULL OR t.subject_id = ?)",
+        )
+        .bind(since.to_rfc3339())
+        .bind(&subject_id)
+        .bind(&subject_id)
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// Split candidates into queue sections, apply the daily limits and ordering.
+    /// Learning cards are only shown once due; reviews due any time today count.
+    pub fn assemble(
+        candidates: Vec<QueuedCard>,
+        options: &QueueOptions,
+        studied: StudiedToday,
+        now: DateTime<Utc>,
+        day_start: DateTime<Utc>,
+    ) -> ReviewQueue {
+        let day_end = day_start + chrono::Duration::days(1);
+        let due_before = |card: &QueuedCard, limit: DateTime<Utc>| {
+            DateTime::parse_from_rfc3339(&card.card.due)
+                .map(|due| due.with_timezone(&Utc) <= limit)
+                .unwrap_or(true)
+        };
+
+        let mut relearning = Vec::new();
+        let mut learning = Vec::new();
+        let mut reviews = Vec::new();
+        let mut new_cards = Vec::new();
+
+        for mut card in candidates {
+            card.retrievability = FsrsService::retrievability_at(&card.card, now);
+            match card.card.state.as_str() {
+                "new" => new_cards.push(card),
+                "review" if due_before(&card, day_end) => reviews.push(card),
+                "relearning" if due_before(&card, now) => relearning.push(card),
+                "learning" if due_before(&card, now) => learning.push(card),
+                _ => {}
+            }
+        }
+
+        let new_limit = (options.new_per_day - studied.new_cards).max(0);
+        let review_limit = (options.reviews_per_day - studied.reviews).max(0);
+
+        // Same shuffle for the whole day, so rebuilding the queue doesn't reorder it
+        let seed = day_start.with_timezone(&Local).num_days_from_ce();
+        Self::sort(&mut reviews, options.order, seed);
+        reviews.truncate(review_limit as usize);
+
+        // New cards have no memory state to order by: creation order unless shuffled
+        new_cards.sort_by(|a, b| a.card.created_at.cmp(&b.card.created_at));
+        if options.order == QueueOrder::Random {
+            Self::sort(&mut new_cards, QueueOrder::Random, seed);
+        }
+        new_cards.truncate(new_limit as usize);
+
+        if options.interleave_topics {
+            reviews = Self::interleave_topics(reviews);
+            new_cards = Self::interleave_topics(new_cards);
+        }
+
+        let new_remaining = new_limit - new_cards.len() as i64;
+        let reviews_remaining = review_limit - reviews.len() as i64;
+
+        let mut cards = relearning;
+        cards.extend(learning);
+        cards.extend(reviews);
+        cards.extend(new_cards);
+
+        ReviewQueue {
+            cards,
+            studied_today: studied,
+            new_remaining,
+            reviews_remaining,
+        }
+    }
+
+    fn sort(cards: &mut [QueuedCard], order: QueueOrder, seed: i32) {
+        match order {
+            QueueOrder::Due => cards.sort_by(|a, b| a.card.due.cmp(&b.card.due)),
+            QueueOrder::Retrievability => {
+                cards.sort_by(|a, b| a.retrievability.total_cmp(&b.retrievability))
+            }
+            QueueOrder::Difficulty => {
+                cards.sort_by(|a, b| b.card.difficulty.total_cmp(&a.card.difficulty))
+            }
+            QueueOrder::Random => cards.sort_by_cached_key(|c| {
+                (FsrsService::seeded_fraction(&c.card.id, seed) * u64::MAX as f64) as u64
+            }),
+        }
+    }
+
+    /// Round-robin across topics, keeping each topic's cards in their sorted order
+    fn interleave_topics(cards: Vec<QueuedCard>) -> Vec<QueuedCard> {
+        let total = cards.len();
+        let mut groups: Vec<VecDeque<QueuedCard>> = Vec::new();
+        let mut by_topic: HashMap<String, usize> = HashMap::new();
+
+        for card in cards {
+            let index = *by_topic.entry(card.topic_id.clone()).or_insert_with(|| {
+                groups.push(VecDeque::new());
+                groups.len() - 1
+            });
+            groups[index].push_back(card);
+        }
+
+        let mut interleaved = Vec::with_capacity(total);
+        while interleaved.len() < total {
+            interleaved.extend(groups.iter_mut().filter_map(|group| group.pop_front()));
+        }
+        interleaved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FsrsCard;
+
+    fn queued(id: &str, topic: &str, state: &str, due: &str, difficulty: f64) -> QueuedCard {
+        QueuedCard {
+            card: FsrsCard {
+                id: id.to_string(),
+                problem_id: format!("problem-{}", id),
+                due: due.to_string(),
+                stability: difficulty,
+                difficulty,
+                state: state.to_string(),
+                reps: 3,
+                lapses: 0,
+                elapsed_days: 0,
+                scheduled_days: 5,
+                step: 0,
+                is_leech: false,
+                is_suspended: false,
+                buried_until: None,
+                last_review: Some("2024-03-01T09:00:00+00:00".to_string()),
+                created_at: "2024-01-01T00:00:00+00:00".to_string(),
+                updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+            },
+            topic_id: topic.to_string(),
+            retrievability: 0.0,
+        }
+    }
+
+    fn ids(queue: &ReviewQueue) -> Vec<&str> {
+        queue.cards.iter().map(|c| c.card.id.as_str()).collect()
+    }
+
+    fn time(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn candidates() -> Vec<QueuedCard> {
+        vec![
+            queued("r1", "a", "review", "2024-03-05T08:00:00+00:00", 4.0),
+            queued("r2", "a", "review", "2024-03-06T08:00:00+00:00", 9.0),
+            queued("r3", "b", "review", "2024-03-10T08:00:00+00:00", 6.0),
+            queued("r4", "a", "review", "2024-03-10T20:00:00+00:00", 2.0),
+            queued("l1", "b", "learning", "2024-03-10T09:30:00+00:00", 5.0),
+            queued("l2", "b", "learning", "2024-03-10T11:00:00+00:00", 5.0),
+            queued("x1", "a", "relearning", "2024-03-10T09:50:00+00:00", 5.0),
+            queued("n1", "a", "new", "2024-03-01T00:00:00+00:00", 5.0),
+            queued("n2", "a", "new", "2024-03-01T00:00:00+00:00", 5.0),
+            queued("n3", "b", "new", "2024-03-01T00:00:00+00:00", 5.0),
+        ]
+    }
+
+    #[test]
+    fn test_sections_and_limits() {
+        let now = time("2024-03-10T10:00:00+00:00");
+        let day_start = time("2024-03-10T00:00:00+00:00");
+        let options = QueueOptions {
+            new_per_day: 3,
+            reviews_per_day: 10,
+            interleave_topics: false,
+            ..Default::default()
+        };
+
+        // Relearning first, learning only once due, then reviews due today, then new
+        let queue = ReviewQueueService::assemble(
+            candidates(),
+            &options,
+            StudiedToday::default(),
+            now,
+            day_start,
+        );
+        assert_eq!(
+            ids(&queue),
+            vec!["x1", "l1", "r1", "r2", "r3", "r4", "n1", "n2", "n3"]
+        );
+        assert_eq!((queue.new_remaining, queue.reviews_remaining), (0, 6));
+
+        // What was studied earlier today comes off the allowance
+        let studied = StudiedToday {
+            new_cards: 2,
+            reviews: 8,
+            learning: 5,
+        };
+        let queue = ReviewQueueService::assemble(candidates(), &options, studied, now, day_start);
+        assert_eq!(ids(&queue), vec!["x1", "l1", "r1", "r2", "n1"]);
+        assert_eq!((queue.new_remaining, queue.reviews_remaining), (0, 0));
+
+        let studied = StudiedToday {
+            new_cards: 7,
+            reviews: 12,
+            learning: 0,
+        };
+        let queue = ReviewQueueService::assemble(candidates(), &options, studied, now, day_start);
+        assert_eq!(ids(&queue), vec!["x1", "l1"]);
+    }
+
+    #[test]
+    fn test_ordering_and_interleaving() {
+        let now = time("2024-03-10T10:00:00+00:00");
+        let day_start = time("2024-03-10T00:00:00+00:00");
+        let reviews = |order, interleave_topics| {
+            let options = QueueOptions {
+                order,
+                interleave_topics,
+                new_per_day: 0,
+                ..Default::default()
+            };
+            let queue = ReviewQueueService::assemble(
+                candidates(),
+                &options,
+                StudiedToday::default(),
+                now,
+                day_start,
+            );
+            ids(&queue)
+                .into_iter()
+                .filter(|id| id.starts_with('r'))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            reviews(QueueOrder::Difficulty, false),
+            ["r2", "r3", "r1", "r4"]
+        );
+        // Reviewed at the same time, so the least stable card is the least retrievable
+        assert_eq!(
+            reviews(QueueOrder::Retrievability, false),
+            ["r4", "r1", "r3", "r2"]
+        );
+        assert_eq!(reviews(QueueOrder::Due, true), ["r1", "r3", "r2", "r4"]);
+
+        // Shuffled, but the same shuffle every time within a day
+        let mut shuffled = reviews(QueueOrder::Random, false);
+        assert_eq!(shuffled, reviews(QueueOrder::Random, false));
+        shuffled.sort();
+        assert_eq!(shuffled, ["r1", "r2", "r3", "r4"]);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::error::AppError;
+use crate::models::Setting;
+use sqlx::SqlitePool;
+
+pub struct SettingsService;
+
+impl SettingsService {
+    pub async fn get(pool: &SqlitePool, key: String) -> Result<Option<Setting>, AppError> {
+        sqlx::query_as::<_, Setting>("SELECT key, value, updated_at FROM settings WHERE key = ?")
+            .bind(key)
+            .fetch_optional(pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    pub async fn list_all(pool: &SqlitePool) -> Result<Vec<Setting>, AppError> {
+        sqlx::query_as::<_, Setting>("SELECT key, value, updated_at FROM settings ORDER BY key")
+            .fetch_all(pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// Create or overwrite a setting
+    pub async fn set(pool: &SqlitePool, key: String, value: String) -> Result<Setting, AppError> {
+        if key.trim().is_empty() {
+            return Err(AppError::validation("key", "Setting key cannot be empty"));
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            "INSERT INTO settings (key, value, updated_at) VALUES (?, ?, ?) 
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        )
+        .bind(&key)
+        .bind(&value)
+        .bind(&now)
+        .execute(pool)
+        .await?;
+
+        Ok(Setting {
+            key,
+            value,
+            updated_at: now,
+        })
+    }
+
+    pub async fn delete(pool: &SqlitePool, key: String) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM settings WHERE key = ?")
+            .bind(key)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,395 @@
+use crate::error::AppError;
+use crate::models::study_phase::{
+    AdvancePhaseRequest, PhaseAdvanceResult, PhaseTimeSummary, StudyPhase, StudyPhaseEntry,
+    StudyPhaseProgress, StudySessionData, UpdatePhaseTimeRequest,
+};
+use crate::models::theory::TheoryWithPhase;
+use crate::services::{ProblemService, TheoryService};
+use sqlx::SqlitePool;
+
+pub struct StudyPhaseService;
+
+impl StudyPhaseService {
+    pub async fn get_progress(
+        pool: &SqlitePool,
+        problem_id: String,
+    ) -> Result<Option<StudyPhaseProgress>, AppError> {
+        sqlx::query_as::<_, StudyPhaseProgress>(
+            "SELECT id, problem_id, current_phase, current_step, phase_time_total, phase_elapsed, completed_at, created_at, updated_at
+             FROM study_phase_progress WHERE problem_id = ?"
+        )
+        .bind(problem_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    pub async fn get_session(
+        pool: &SqlitePool,
+        problem_id: String,
+    ) -> Result<Option<StudySessionData>, AppError> {
+        Ok(Self::get_progress(pool, problem_id)
+            .await?
+            .map(|p| p.to_session_data()))
+    }
+
+    /// Theory pages of the problem's topic that belong to its current phase;
+    /// earlier phases' pages stay hidden, so Recall is done without them
+    pub async fn get_phase_theory(
+        pool: &SqlitePool,
+        problem_id: String,
+    ) -> Result<Vec<TheoryWithPhase>, AppError> {
+        let progress = Self::require(pool, &problem_id).await?;
+        let problem = ProblemService::get_by_id(pool, problem_id).await?;
+        TheoryService::list_for_study_phase(pool, problem.topic_id, &progress.get_current_phase())
+            .await
+    }
+
+    /// Begin studying a problem at Decode; an existing run is resumed as is
+    pub async fn start(
+        pool: &SqlitePool,
+        problem_id: String,
+    ) -> Result<StudySessionData, AppError> {
+        ProblemService::get_by_id(pool, problem_id.clone()).await?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO study_phase_progress (id, problem_id, current_phase, current_step, phase_time_total, phase_elapsed, created_at, updated_at)
+             VALUES (?, ?, 1, 1, 0, 0, ?, ?)"
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&problem_id)
+        .bind(&now)
+        .bind(&now)
+        .execute(pool)
+        .await?;
+
+        Ok(Self::require(pool, &problem_id).await?.to_session_data())
+    }
+
+    /// Finish the current phase and move to the next one; leaving Reflect completes the run
+    pub async fn advance(
+        pool: &SqlitePool,
+        req: AdvancePhaseRequest,
+    ) -> Result<PhaseAdvanceResult, AppError> {
+        let progress = Self::in_progress(pool, &req.problem_id).await?;
+        let next = progress.get_current_phase().next();
+        Self::finish_phase(pool, &progress, req.time_spent_seconds, req.notes, next).await
+    }
+
+    /// Complete the run; only allowed once Reflect has been reached
+    pub async fn complete(
+        pool: &SqlitePool,
+        problem_id: String,
+        time_spent_seconds: i32,
+        notes: Option<String>,
+    ) -> Result<PhaseAdvanceResult, AppError> {
+        let progress = Self::in_progress(pool, &problem_id).await?;
+        let phase = progress.get_current_phase();
+        if phase != StudyPhase::Reflect {
+            return Err(AppError::validation(
+                "current_phase",
+                format!(
+                    "Cannot complete during {}; phases must be done in order",
+                    phase
+                ),
+            ));
+        }
+        Self::finish_phase(pool, &progress, time_spent_seconds, notes, None).await
+    }
+
+    /// Checkpoint the running timer of the current phase
+    pub async fn update_time(
+        pool: &SqlitePool,
+        req: UpdatePhaseTimeRequest,
+    ) -> Result<StudySessionData, AppError> {
+        Self::validate_seconds("elapsed_seconds", req.elapsed_seconds)?;
+        let progress = Self::in_progress(pool, &req.problem_id).await?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            "UPDATE study_phase_progress SET phase_elapsed = ?, updated_at = ? WHERE id = ?",
+        )
+        .bind(req.elapsed_seconds)
+        .bind(&now)
+        .bind(&progress.id)
+        .execute(pool)
+        .await?;
+
+        Ok(Self::require(pool, &req.problem_id)
+            .await?
+            .to_session_data())
+    }
+
+    /// Start over from Decode with no time recorded
+    pub async fn reset(
+        pool: &SqlitePool,
+        problem_id: String,
+    ) -> Result<StudySessionData, AppError> {
+        let progress = Self::require(pool, &problem_id).await?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            "UPDATE study_phase_progress
+             SET current_phase = 1, current_step = 1, phase_time_total = 0, phase_elapsed = 0,
+                 completed_at = NULL, updated_at = ?
+             WHERE id = ?",
+        )
+        .bind(&now)
+        .bind(&progress.id)
+        .execute(pool)
+        .await?;
+
+        Ok(Self::require(pool, &problem_id).await?.to_session_data())
+    }
+
+    /// Past passes of a problem's phases (or of one phase), newest first
+    pub async fn list_entries(
+        pool: &SqlitePool,
+        problem_id: String,
+        phase: Option<i32>,
+    ) -> Result<Vec<StudyPhaseEntry>, AppError> {
+        if let Some(phase) = phase {
+            Self::validate_phase(phase)?;
+        }
+
+        sqlx::query_as::<_, StudyPhaseEntry>(
+            "SELECT id, problem_id, phase, time_spent_seconds, notes, created_at
+             FROM study_phase_entries
+             WHERE problem_id = ? AND (? IS NULL OR phase = ?)
+             ORDER BY created_at DESC",
+        )
+        .bind(problem_id)
+        .bind(phase)
+        .bind(phase)
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// Where a problem's study time went, per phase
+    pub async fn phase_times_by_problem(
+        pool: &SqlitePool,
+        problem_id: String,
+    ) -> Result<Vec<PhaseTimeSummary>, AppError> {
+        let rows: Vec<(i32, i64, i64)> = sqlx::query_as(
+            "SELECT phase, SUM(time_spent_seconds), COUNT(*) FROM study_phase_entries
+             WHERE problem_id = ? GROUP BY phase",
+        )
+        .bind(problem_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(PhaseTimeSummary::from_totals(&rows))
+    }
+
+    /// Where the study time of all of a topic's problems went, per phase
+    pub async fn phase_times_by_topic(
+        pool: &SqlitePool,
+        topic_id: String,
+    ) -> Result<Vec<PhaseTimeSummary>, AppError> {
+        let rows: Vec<(i32, i64, i64)> = sqlx::query_as(
+            "SELECT e.phase, SUM(e.time_spent_seconds), COUNT(*) FROM study_phase_entries e
+             JOIN problems p ON e.problem_id = p.id
+             WHERE p.topic_id = ? GROUP BY e.phase",
+        )
+        .bind(topic_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(PhaseTimeSummary::from_totals(&rows))
+    }
+
+    /// Add the phase's time to the total and move to `next`, or complete when there is none.
+    /// The pass is written to the ledger in the same transaction. The update only applies
+    /// if the progress is still where it was read, so two windows advancing at once
+    /// can't skip a phase.
+    async fn finish_phase(
+        pool: &SqlitePool,
+        progress: &StudyPhaseProgress,
+        time_spent_seconds: i32,
+        notes: Option<String>,
+        next: Option<StudyPhase>,
+    ) -> Result<PhaseAdvanceResult, AppError> {
+        Self::validate_seconds("time_spent_seconds", time_spent_seconds)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let to_phase = next
+            .as_ref()
+            .map_or(progress.current_phase, |p| p.to_number());
+        let completed_at = next.is_none().then(|| now.clone());
+        let notes = notes.filter(|n| !n.trim().is_empty());
+
+        let mut tx = pool.begin().await?;
+        let result = sqlx::query(
+            "UPDATE study_phase_progress
+             SET current_phase = ?, current_step = 1, phase_time_total = phase_time_total + ?,
+                 phase_elapsed = 0, completed_at = ?, updated_at = ?
+             WHERE id = ? AND current_phase = ? AND completed_at IS NULL",
+        )
+        .bind(to_phase)
+        .bind(time_spent_seconds)
+        .bind(&completed_at)
+        .bind(&now)
+        .bind(&progress.id)
+        .bind(progress.current_phase)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::conflict(
+                "study_phase_progress",
+                "Study progress changed in the meantime; reload it",
+            ));
+        }
+
+        sqlx::query(
+            "INSERT INTO study_phase_entries (id, problem_id, phase, time_spent_seconds, notes, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&progress.problem_id)
+        .bind(progress.current_phase)
+        .bind(time_spent_seconds)
+        .bind(&notes)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(PhaseAdvanceResult {
+            problem_id: progress.problem_id.clone(),
+            from_phase: progress.current_phase,
+            to_phase,
+            is_completed: completed_at.is_some(),
+        })
+    }
+
+    async fn require(pool: &SqlitePool, problem_id: &str) -> Result<StudyPhaseProgress, AppError> {
+        Self::get_progress(pool, problem_id.to_string())
+            .await?
+            .ok_or_else(|| AppError::not_found("study_phase_progress", problem_id))
+    }
+
+    async fn in_progress(
+        pool: &SqlitePool,
+        problem_id: &str,
+    ) -> Result<StudyPhaseProgress, AppError> {
+        let progress = Self::require(pool, problem_id).await?;
+        if progress.is_completed() {
+            return Err(AppError::conflict(
+                "study_phase_progress",
+                "All phases are already completed; reset to study again",
+            ));
+        }
+        Ok(progress)
+    }
+
+    fn validate_phase(phase: i32) -> Result<(), AppError> {
+        if !(1..=4).contains(&phase) {
+            return Err(AppError::validation(
+                "phase",
+                format!("Phase must be 1-4, got {}", phase),
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_seconds(field: &str, seconds: i32) -> Result<(), AppError> {
+        if seconds < 0 {
+            return Err(AppError::validation(
+                field,
+                format!("Time cannot be negative, got {}", seconds),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{seed_problem, test_pool};
+
+    #[tokio::test]
+    async fn test_phases_advance_in_order() {
+        let pool = test_pool().await;
+        let problem = seed_problem(&pool).await;
+        for phase_number in [1, 2, 5] {
+            TheoryService::create(
+                &pool,
+                problem.topic_id.clone(),
+                phase_number,
+                "Page".into(),
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        let id = problem.id;
+        let labels = |pages: Vec<TheoryWithPhase>| -> Vec<String> {
+            pages.into_iter().map(|p| p.phase_label).collect()
+        };
+
+        let session = StudyPhaseService::start(&pool, id.clone()).await.unwrap();
+        assert_eq!(session.current_phase, "decode");
+        let theory = StudyPhaseService::get_phase_theory(&pool, id.clone()).await;
+        assert_eq!(labels(theory.unwrap()), vec!["1.1", "1.2"]);
+
+        // Reflect can't be skipped to
+        let early = StudyPhaseService::complete(&pool, id.clone(), 10, None).await;
+        assert!(matches!(early, Err(AppError::Validation { .. })));
+
+        let advance = |t| AdvancePhaseRequest {
+            problem_id: id.clone(),
+            time_spent_seconds: t,
+            notes: None,
+        };
+        for (t, to) in [(60, 2), (90, 3), (30, 4)] {
+            let result = StudyPhaseService::advance(&pool, advance(t)).await.unwrap();
+            assert_eq!((result.to_phase, result.is_completed), (to, false));
+            if to == 3 {
+                // Recall only gets its own page, never the 1.x ones
+                let theory = StudyPhaseService::get_phase_theory(&pool, id.clone()).await;
+                assert_eq!(labels(theory.unwrap()), vec!["3"]);
+            }
+        }
+        let done = StudyPhaseService::advance(
+            &pool,
+            AdvancePhaseRequest {
+                notes: Some("Check the bounds first".into()),
+                ..advance(20)
+            },
+        )
+        .await
+        .unwrap();
+        assert!(done.is_completed);
+
+        let session = StudyPhaseService::get_session(&pool, id.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((session.total_time, session.total_completion), (200, 100.0));
+        assert!(matches!(
+            StudyPhaseService::advance(&pool, advance(5)).await,
+            Err(AppError::Conflict { .. })
+        ));
+
+        let session = StudyPhaseService::reset(&pool, id.clone()).await.unwrap();
+        assert_eq!(
+            (session.current_phase.as_str(), session.total_time),
+            ("decode", 0)
+        );
+
+        // The ledger outlives the reset
+        let times = StudyPhaseService::phase_times_by_problem(&pool, id.clone())
+            .await
+            .unwrap();
+        let seconds: Vec<i64> = times.iter().map(|p| p.total_seconds).collect();
+        assert_eq!(seconds, vec![60, 90, 30, 20]);
+        let reflect = StudyPhaseService::list_entries(&pool, id, Some(4))
+            .await
+            .unwrap();
+        assert_eq!(reflect[0].notes.as_deref(), Some("Check the bounds first"));
+    }
+}
@@ -0,0 +1,363 @@
+use crate::error::AppError;
+use crate::models::error_log::OVER_TIME_LIMIT_ERROR_TYPE;
+use crate::models::study_session::{SessionStatus, StudySession, StudySessionSummary, Timebox};
+use sqlx::SqlitePool;
+
+/// A session with its counts; callers bind the over-time error type and append the WHERE/ORDER BY
+const SUMMARY_SELECT: &str = "
+    SELECT s.id, s.status, s.started_at, s.ended_at, s.last_heartbeat_at, s.active_seconds,
+           s.idle_seconds, s.break_seconds, s.work_interval_seconds, s.break_interval_seconds,
+           s.created_at, s.updated_at,
+           (SELECT COUNT(*) FROM attempts a WHERE a.session_id = s.id) AS attempt_count,
+           (SELECT COUNT(*) FROM attempts a WHERE a.session_id = s.id AND a.is_solved = 1) AS solved_count,
+           (SELECT COUNT(DISTINCT a.id) FROM attempts a
+            JOIN attempt_errors e ON e.attempt_id = a.id
+            WHERE a.session_id = s.id AND e.error_type_id = ?) AS over_time_count,
+           (SELECT COUNT(*) FROM fsrs_reviews r WHERE r.session_id = s.id AND r.kind = 'review') AS review_count,
+           (SELECT COALESCE(SUM(r.duration_seconds), 0) FROM fsrs_reviews r
+            WHERE r.session_id = s.id AND r.kind = 'review') AS review_seconds,
+           (SELECT COUNT(*) FROM (
+                SELECT problem_id FROM attempts WHERE session_id = s.id
+                UNION
+                SELECT problem_id FROM fsrs_reviews WHERE session_id = s.id AND kind = 'review'
+           )) AS problem_count
+    FROM study_sessions s";
+
+pub struct StudySessionService;
+
+impl StudySessionService {
+    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<StudySession, AppError> {
+        sqlx::query_as::<_, StudySession>(
+            "SELECT id, status, started_at, ended_at, last_heartbeat_at, active_seconds, idle_seconds, break_seconds,
+                    work_interval_seconds, break_interval_seconds, created_at, updated_at
+             FROM study_sessions WHERE id = ?"
+        )
+        .bind(&id)
+        .fetch_optional(pool)
+        .await?
+        .map(StudySession::with_interval)
+        .ok_or_else(|| AppError::not_found("study_session", &id))
+    }
+
+    pub async fn get_summary(
+        pool: &SqlitePool,
+        id: String,
+    ) -> Result<StudySessionSummary, AppError> {
+        sqlx::query_as::<_, StudySessionSummary>(&format!("{} WHERE s.id = ?", SUMMARY_SELECT))
+            .bind(OVER_TIME_LIMIT_ERROR_TYPE)
+            .bind(&id)
+            .fetch_optional(pool)
+            .await?
+            .map(Self::with_interval)
+            .ok_or_else(|| AppError::not_found("study_session", &id))
+    }
+
+    /// The running or paused session, if any; this is how the frontend picks
+    /// a session back up after a restart
+    pub async fn get_current(pool: &SqlitePool) -> Result<Option<StudySessionSummary>, AppError> {
+        Ok(sqlx::query_as::<_, StudySessionSummary>(&format!(
+            "{} WHERE s.ended_at IS NULL",
+            SUMMARY_SELECT
+        ))
+        .bind(OVER_TIME_LIMIT_ERROR_TYPE)
+        .fetch_optional(pool)
+        .await?
+        .map(Self::with_interval))
+    }
+
+    /// Past and current sessions, newest first
+    pub async fn list(pool: &SqlitePool, limit: i64) -> Result<Vec<StudySessionSummary>, AppError> {
+        let sessions = sqlx::query_as::<_, StudySessionSummary>(&format!(
+            "{} ORDER BY s.started_at DESC LIMIT ?",
+            SUMMARY_SELECT
+        ))
+        .bind(OVER_TIME_LIMIT_ERROR_TYPE)
+        .bind(limit.max(0))
+        .fetch_all(pool)
+        .await?;
+
+        Ok(sessions.into_iter().map(Self::with_interval).collect())
+    }
+
+    /// Open a new running session; only one can be open at a time. With a
+    /// timebox the running time alternates between work and break intervals.
+    pub async fn start(
+        pool: &SqlitePool,
+        timebox: Option<Timebox>,
+    ) -> Result<StudySession, AppError> {
+        if let Some(timebox) = timebox {
+            Self::validate_interval("work_seconds", timebox.work_seconds)?;
+            Self::validate_interval("break_seconds", timebox.break_seconds)?;
+        }
+        if Self::get_current(pool).await?.is_some() {
+            return Err(Self::already_open());
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            "INSERT INTO study_sessions (id, status, started_at, last_heartbeat_at, active_seconds, idle_seconds, break_seconds,
+                                         work_interval_seconds, break_interval_seconds, created_at, updated_at)
+             VALUES (?, 'running', ?, ?, 0, 0, 0, ?, ?, ?, ?)"
+        )
+        .bind(&id)
+        .bind(&now)
+        .bind(&now)
+        .bind(timebox.map(|t| t.work_seconds))
+        .bind(timebox.map(|t| t.break_seconds))
+        .bind(&now)
+        .bind(&now)
+        .execute(pool)
+        .await
+        .map_err(|e| match e {
+            // One opened concurrently since the check above (idx_study_sessions_open)
+            sqlx::Error::Database(db) if db.is_unique_violation() => Self::already_open(),
+            e => e.into(),
+        })?;
+
+        Self::get_by_id(pool, id).await
+    }
+
+    /// Sent periodically by the frontend while the timer runs. Heartbeats that
+    /// arrive after a pause are ignored rather than rejected.
+    pub async fn heartbeat(pool: &SqlitePool, id: String) -> Result<StudySession, AppError> {
+        let session = Self::get_by_id(pool, id).await?;
+        match session.status {
+            SessionStatus::Running => {
+                Self::transition(pool, &session, SessionStatus::Running).await
+            }
+            SessionStatus::Paused => Ok(session),
+            SessionStatus::Stopped => Err(Self::stopped(&session)),
+        }
+    }
+
+    pub async fn pause(pool: &SqlitePool, id: String) -> Result<StudySession, AppError> {
+        let session = Self::get_by_id(pool, id).await?;
+        if session.status != SessionStatus::Running {
+            return Err(AppError::conflict(
+                "study_session",
+                "Only a running session can be paused",
+            ));
+        }
+        Self::transition(pool, &session, SessionStatus::Paused).await
+    }
+
+    /// Continue a paused session; the paused time is not counted
+    pub async fn resume(pool: &SqlitePool, id: String) -> Result<StudySession, AppError> {
+        let session = Self::get_by_id(pool, id).await?;
+        if session.status != SessionStatus::Paused {
+            return Err(AppError::conflict(
+                "study_session",
+                "Only a paused session can be resumed",
+            ));
+        }
+        Self::transition(pool, &session, SessionStatus::Running).await
+    }
+
+    /// Close the session for good and return what was done in it
+    pub async fn stop(pool: &SqlitePool, id: String) -> Result<StudySessionSummary, AppError> {
+        let session = Self::get_by_id(pool, id).await?;
+        if session.status == SessionStatus::Stopped {
+            return Err(Self::stopped(&session));
+        }
+        Self::transition(pool, &session, SessionStatus::Stopped).await?;
+        Self::get_summary(pool, session.id).await
+    }
+
+    /// Run at startup: a session left running whose last heartbeat is older than
+    /// the idle gap was interrupted (crash, app closed). It's paused with that gap
+    /// counted as idle, so the user can resume or stop it. A quick restart keeps
+    /// the session running.
+    pub async fn recover(pool: &SqlitePool) -> Result<Option<StudySession>, AppError> {
+        let Some(current) = Self::get_current(pool).await? else {
+            return Ok(None);
+        };
+        let session = current.session;
+        if session.split_gap(chrono::Utc::now()).idle == 0 {
+            return Ok(Some(session));
+        }
+        Self::transition(pool, &session, SessionStatus::Paused)
+            .await
+            .map(Some)
+    }
+
+    /// Account the time since the last heartbeat and move to `to`. The update only
+    /// applies if nothing else touched the session since it was read, so a gap is
+    /// never counted twice.
+    async fn transition(
+        pool: &SqlitePool,
+        session: &StudySession,
+        to: SessionStatus,
+    ) -> Result<StudySession, AppError> {
+        let now = chrono::Utc::now();
+        let gap = session.split_gap(now);
+        let now = now.to_rfc3339();
+        let ended_at = (to == SessionStatus::Stopped).then(|| now.clone());
+
+        let result = sqlx::query(
+            "UPDATE study_sessions
+             SET status = ?, active_seconds = active_seconds + ?, idle_seconds = idle_seconds + ?,
+                 break_seconds = break_seconds + ?, last_heartbeat_at = ?, ended_at = ?, updated_at = ?
+             WHERE id = ? AND status = ? AND last_heartbeat_at = ?",
+        )
+        .bind(to)
+        .bind(gap.active)
+        .bind(gap.idle)
+        .bind(gap.breaks)
+        .bind(&now)
+        .bind(&ended_at)
+        .bind(&now)
+        .bind(&session.id)
+        .bind(session.status)
+        .bind(&session.last_heartbeat_at)
+        .execute(pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::conflict(
+                "study_session",
+                "Study session changed in the meantime; reload it",
+            ));
+        }
+
+        Self::get_by_id(pool, session.id.clone()).await
+    }
+
+    fn with_interval(summary: StudySessionSummary) -> StudySessionSummary {
+        StudySessionSummary {
+            session: summary.session.with_interval(),
+            ..summary
+        }
+    }
+
+    fn validate_interval(field: &str, seconds: i64) -> Result<(), AppError> {
+        if seconds < 60 {
+            return Err(AppError::validation(
+                field,
+                format!("Intervals must be at least a minute, got {}s", seconds),
+            ));
+        }
+        Ok(())
+    }
+
+    fn already_open() -> AppError {
+        AppError::conflict(
+            "study_session",
+            "A study session is already open; resume or stop it first",
+        )
+    }
+
+    fn stopped(session: &StudySession) -> AppError {
+        AppError::conflict(
+            "study_session",
+            format!("Study session {} is already stopped", session.id),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{seed_problem, test_pool};
+    use crate::models::attempt::SubmitAttemptRequest;
+    use crate::services::{AttemptService, FsrsService};
+
+    #[tokio::test]
+    async fn test_session_lifecycle_and_recovery() {
+        let pool = test_pool().await;
+        let problem = seed_problem(&pool).await;
+        let submit = |is_solved| SubmitAttemptRequest {
+            problem_id: problem.id.clone(),
+            is_solved,
+            commentary: None,
+            errors: vec![],
+            rating: Some(if is_solved { 3 } else { 1 }),
+            time_spent_seconds: 120,
+        };
+
+        // Made before any session: not counted
+        AttemptService::submit(&pool, submit(false)).await.unwrap();
+
+        let session = StudySessionService::start(&pool, None).await.unwrap();
+        assert!(matches!(
+            StudySessionService::start(&pool, None).await,
+            Err(AppError::Conflict { .. })
+        ));
+        AttemptService::submit(&pool, submit(true)).await.unwrap();
+
+        // The app was closed an hour ago without stopping the session
+        let hour_ago = (chrono::Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        sqlx::query("UPDATE study_sessions SET last_heartbeat_at = ?")
+            .bind(&hour_ago)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let recovered = StudySessionService::recover(&pool).await.unwrap().unwrap();
+        assert_eq!(recovered.status, SessionStatus::Paused);
+        assert!(recovered.idle_seconds >= 3600);
+        assert_eq!(recovered.active_seconds, 0);
+
+        // Work done while paused isn't the session's
+        AttemptService::submit(&pool, submit(true)).await.unwrap();
+
+        // Heartbeats while paused are ignored; resume/pause follow the state
+        let id = session.id.clone();
+        let paused = StudySessionService::heartbeat(&pool, id.clone())
+            .await
+            .unwrap();
+        assert_eq!(paused.last_heartbeat_at, recovered.last_heartbeat_at);
+        assert!(matches!(
+            StudySessionService::pause(&pool, id.clone()).await,
+            Err(AppError::Conflict { .. })
+        ));
+        let running = StudySessionService::resume(&pool, id.clone())
+            .await
+            .unwrap();
+        assert_eq!(running.status, SessionStatus::Running);
+
+        // Neither are manual actions, even while running
+        let (card_id,): (String,) =
+            sqlx::query_as("SELECT id FROM fsrs_cards WHERE problem_id = ?")
+                .bind(&problem.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        FsrsService::suspend_card(&pool, card_id).await.unwrap();
+        let (claimed,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM fsrs_reviews WHERE session_id IS NOT NULL AND kind != 'review'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(claimed, 0);
+
+        let current = StudySessionService::get_current(&pool)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(current.session.id, id);
+
+        let summary = StudySessionService::stop(&pool, id.clone()).await.unwrap();
+        assert_eq!(summary.session.status, SessionStatus::Stopped);
+        assert!(summary.session.ended_at.is_some());
+        assert_eq!(
+            (
+                summary.attempt_count,
+                summary.solved_count,
+                summary.review_count,
+                summary.review_seconds,
+                summary.problem_count
+            ),
+            (1, 1, 1, 120, 1)
+        );
+        assert!(StudySessionService::get_current(&pool)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            StudySessionService::heartbeat(&pool, id).await,
+            Err(AppError::Conflict { .. })
+        ));
+    }
+}
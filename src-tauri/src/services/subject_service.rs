@@ -1,3 +1,4 @@
+use crate::error::AppError;
 use crate::models::Subject;
 use sqlx::SqlitePool;
 
@@ -8,7 +9,7 @@ impl SubjectService {
         pool: &SqlitePool,
         name: String,
         description: Option<String>,
-    ) -> Result<Subject, String> {
+    ) -> Result<Subject, AppError> {
         let id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
 
@@ -22,8 +23,7 @@ impl SubjectService {
         .bind(&now)
         .bind(&now)
         .execute(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
         Ok(Subject {
             id,
@@ -34,23 +34,23 @@ impl SubjectService {
         })
     }
 
-    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<Subject, String> {
+    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<Subject, AppError> {
         sqlx::query_as::<_, Subject>(
             "SELECT id, name, description, created_at, updated_at FROM subjects WHERE id = ?",
         )
-        .bind(id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| e.to_string())
+        .bind(&id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("subject", &id))
     }
 
-    pub async fn list_all(pool: &SqlitePool) -> Result<Vec<Subject>, String> {
+    pub async fn list_all(pool: &SqlitePool) -> Result<Vec<Subject>, AppError> {
         sqlx::query_as::<_, Subject>(
             "SELECT id, name, description, created_at, updated_at FROM subjects ORDER BY name",
         )
         .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
     }
 
     pub async fn update(
@@ -58,7 +58,7 @@ impl SubjectService {
         id: String,
         name: Option<String>,
         description: Option<String>,
-    ) -> Result<Subject, String> {
+    ) -> Result<Subject, AppError> {
         let subject = Self::get_by_id(pool, id.clone()).await?;
         let now = chrono::Utc::now().to_rfc3339();
 
@@ -71,8 +71,7 @@ impl SubjectService {
             .bind(&now)
             .bind(&id)
             .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         Ok(Subject {
             id,
@@ -83,12 +82,11 @@ impl SubjectService {
         })
     }
 
-    pub async fn delete(pool: &SqlitePool, id: String) -> Result<(), String> {
+    pub async fn delete(pool: &SqlitePool, id: String) -> Result<(), AppError> {
         sqlx::query("DELETE FROM subjects WHERE id = ?")
             .bind(id)
             .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         Ok(())
     }
@@ -1,3 +1,6 @@
+use crate::error::AppError;
+use crate::models::study_phase::StudyPhase;
+use crate::models::theory::TheoryWithPhase;
 use crate::models::Theory;
 use sqlx::SqlitePool;
 
@@ -10,7 +13,13 @@ impl TheoryService {
         phase_number: i32,
         title: String,
         content: Option<String>,
-    ) -> Result<Theory, String> {
+    ) -> Result<Theory, AppError> {
+        let label = Theory::phase_label(phase_number).ok_or_else(|| {
+            AppError::validation(
+                "phase_number",
+                format!("Phase number must be 1-6, got {}", phase_number),
+            )
+        })?;
         let id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
 
@@ -27,7 +36,14 @@ impl TheoryService {
         .bind(&now)
         .execute(pool)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| match e {
+            // UNIQUE(topic_id, phase_number)
+            sqlx::Error::Database(db) if db.is_unique_violation() => AppError::conflict(
+                "theory",
+                format!("Topic already has a theory for phase {}", label),
+            ),
+            e => e.into(),
+        })?;
 
         Ok(Theory {
             id,
@@ -40,17 +56,20 @@ impl TheoryService {
         })
     }
 
-    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<Theory, String> {
+    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<Theory, AppError> {
         sqlx::query_as::<_, Theory>(
             "SELECT id, topic_id, phase_number, title, content, created_at, updated_at FROM theories WHERE id = ?"
         )
-        .bind(id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| e.to_string())
+        .bind(&id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("theory", &id))
     }
 
-    pub async fn list_by_topic(pool: &SqlitePool, topic_id: String) -> Result<Vec<Theory>, String> {
+    pub async fn list_by_topic(
+        pool: &SqlitePool,
+        topic_id: String,
+    ) -> Result<Vec<Theory>, AppError> {
         sqlx::query_as::<_, Theory>(
             "SELECT id, topic_id, phase_number, title, content, created_at, updated_at 
              FROM theories WHERE topic_id = ? ORDER BY phase_number",
@@ -58,14 +77,14 @@ impl TheoryService {
         .bind(topic_id)
         .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
     }
 
     pub async fn get_by_phase(
         pool: &SqlitePool,
         topic_id: String,
         phase_number: i32,
-    ) -> Result<Option<Theory>, String> {
+    ) -> Result<Option<Theory>, AppError> {
         sqlx::query_as::<_, Theory>(
             "SELECT id, topic_id, phase_number, title, content, created_at, updated_at 
              FROM theories WHERE topic_id = ? AND phase_number = ?",
@@ -74,7 +93,22 @@ impl TheoryService {
         .bind(phase_number)
         .fetch_optional(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+    }
+
+    /// Theory pages of a topic shown during a study phase, e.g. 1.1 and 1.2 for Decode
+    pub async fn list_for_study_phase(
+        pool: &SqlitePool,
+        topic_id: String,
+        phase: &StudyPhase,
+    ) -> Result<Vec<TheoryWithPhase>, AppError> {
+        let mut pages = Vec::new();
+        for phase_number in phase.theory_phase_numbers() {
+            if let Some(theory) = Self::get_by_phase(pool, topic_id.clone(), phase_number).await? {
+                pages.extend(theory.with_phase());
+            }
+        }
+        Ok(pages)
     }
 
     pub async fn update(
@@ -82,7 +116,7 @@ impl TheoryService {
         id: String,
         title: Option<String>,
         content: Option<String>,
-    ) -> Result<Theory, String> {
+    ) -> Result<Theory, AppError> {
         let theory = Self::get_by_id(pool, id.clone()).await?;
         let now = chrono::Utc::now().to_rfc3339();
 
@@ -95,8 +129,7 @@ impl TheoryService {
             .bind(&now)
             .bind(&id)
             .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         Ok(Theory {
             id,
@@ -109,12 +142,11 @@ impl TheoryService {
         })
     }
 
-    pub async fn delete(pool: &SqlitePool, id: String) -> Result<(), String> {
+    pub async fn delete(pool: &SqlitePool, id: String) -> Result<(), AppError> {
         sqlx::query("DELETE FROM theories WHERE id = ?")
             .bind(id)
             .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         Ok(())
     }
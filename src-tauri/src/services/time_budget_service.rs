@@ -0,0 +1,115 @@
+use crate::error::AppError;
+use crate::models::time_budget::{DifficultyTimeBudget, TimeBudget};
+use sqlx::{SqliteConnection, SqlitePool};
+
+pub struct TimeBudgetService;
+
+impl TimeBudgetService {
+    pub async fn list_by_difficulty(
+        pool: &SqlitePool,
+    ) -> Result<Vec<DifficultyTimeBudget>, AppError> {
+        sqlx::query_as::<_, DifficultyTimeBudget>(
+            "SELECT difficulty, budget_seconds, updated_at FROM difficulty_time_budgets ORDER BY difficulty",
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// Change the default budget of a difficulty; problems with their own budget keep it
+    pub async fn set_for_difficulty(
+        pool: &SqlitePool,
+        difficulty: i32,
+        budget_seconds: i64,
+    ) -> Result<DifficultyTimeBudget, AppError> {
+        if !(1..=5).contains(&difficulty) {
+            return Err(AppError::validation(
+                "difficulty",
+                format!("Difficulty must be 1-5, got {}", difficulty),
+            ));
+        }
+        Self::validate_budget(budget_seconds)?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            "INSERT INTO difficulty_time_budgets (difficulty, budget_seconds, updated_at) VALUES (?, ?, ?)
+             ON CONFLICT(difficulty) DO UPDATE SET budget_seconds = excluded.budget_seconds, updated_at = excluded.updated_at",
+        )
+        .bind(difficulty)
+        .bind(budget_seconds)
+        .bind(&now)
+        .execute(pool)
+        .await?;
+
+        Ok(DifficultyTimeBudget {
+            difficulty,
+            budget_seconds,
+            updated_at: now,
+        })
+    }
+
+    pub async fn get_for_problem(
+        pool: &SqlitePool,
+        problem_id: String,
+    ) -> Result<Option<TimeBudget>, AppError> {
+        let mut conn = pool.acquire().await?;
+        Self::resolve(&mut conn, &problem_id).await
+    }
+
+    /// Give a problem its own budget, or None to fall back to its difficulty's
+    pub async fn set_for_problem(
+        pool: &SqlitePool,
+        problem_id: String,
+        budget_seconds: Option<i64>,
+    ) -> Result<Option<TimeBudget>, AppError> {
+        if let Some(seconds) = budget_seconds {
+            Self::validate_budget(seconds)?;
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let result =
+            sqlx::query("UPDATE problems SET time_budget_seconds = ?, updated_at = ? WHERE id = ?")
+                .bind(budget_seconds)
+                .bind(&now)
+                .bind(&problem_id)
+                .execute(pool)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("problem", &problem_id));
+        }
+        Self::get_for_problem(pool, problem_id).await
+    }
+
+    /// The budget attempts at a problem are held to
+    pub(crate) async fn resolve(
+        conn: &mut SqliteConnection,
+        problem_id: &str,
+    ) -> Result<Option<TimeBudget>, AppError> {
+        let (problem_budget, difficulty_budget): (Option<i64>, Option<i64>) = sqlx::query_as(
+            "SELECT p.time_budget_seconds, d.budget_seconds FROM problems p
+             LEFT JOIN difficulty_time_budgets d ON d.difficulty = p.difficulty
+             WHERE p.id = ?",
+        )
+        .bind(problem_id)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("problem", problem_id))?;
+
+        Ok(TimeBudget::resolve(
+            problem_id.to_string(),
+            problem_budget,
+            difficulty_budget,
+        ))
+    }
+
+    fn validate_budget(budget_seconds: i64) -> Result<(), AppError> {
+        if budget_seconds <= 0 {
+            return Err(AppError::validation(
+                "budget_seconds",
+                format!("Time budget must be positive, got {}", budget_seconds),
+            ));
+        }
+        Ok(())
+    }
+}
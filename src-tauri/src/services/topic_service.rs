@@ -1,3 +1,4 @@
+use crate::error::AppError;
 use crate::models::Topic;
 use sqlx::SqlitePool;
 
@@ -9,7 +10,7 @@ impl TopicService {
         subject_id: String,
         name: String,
         description: Option<String>,
-    ) -> Result<Topic, String> {
+    ) -> Result<Topic, AppError> {
         let id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
 
@@ -24,8 +25,7 @@ impl TopicService {
         .bind(&now)
         .bind(&now)
         .execute(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
         Ok(Topic {
             id,
@@ -37,20 +37,20 @@ impl TopicService {
         })
     }
 
-    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<Topic, String> {
+    pub async fn get_by_id(pool: &SqlitePool, id: String) -> Result<Topic, AppError> {
         sqlx::query_as::<_, Topic>(
             "SELECT id, subject_id, name, description, created_at, updated_at FROM topics WHERE id = ?"
         )
-        .bind(id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| e.to_string())
+        .bind(&id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("topic", &id))
     }
 
     pub async fn list_by_subject(
         pool: &SqlitePool,
         subject_id: String,
-    ) -> Result<Vec<Topic>, String> {
+    ) -> Result<Vec<Topic>, AppError> {
         sqlx::query_as::<_, Topic>(
             "SELECT id, subject_id, name, description, created_at, updated_at 
              FROM topics WHERE subject_id = ? ORDER BY name",
@@ -58,7 +58,7 @@ impl TopicService {
         .bind(subject_id)
         .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
     }
 
     pub async fn update(
@@ -66,7 +66,7 @@ impl TopicService {
         id: String,
         name: Option<String>,
         description: Option<String>,
-    ) -> Result<Topic, String> {
+    ) -> Result<Topic, AppError> {
         let topic = Self::get_by_id(pool, id.clone()).await?;
         let now = chrono::Utc::now().to_rfc3339();
 
@@ -79,8 +79,7 @@ impl TopicService {
             .bind(&now)
             .bind(&id)
             .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         Ok(Topic {
             id,
@@ -92,12 +91,11 @@ impl TopicService {
         })
     }
 
-    pub async fn delete(pool: &SqlitePool, id: String) -> Result<(), String> {
+    pub async fn delete(pool: &SqlitePool, id: String) -> Result<(), AppError> {
         sqlx::query("DELETE FROM topics WHERE id = ?")
             .bind(id)
             .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         Ok(())
     }
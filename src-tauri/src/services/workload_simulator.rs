@@ -0,0 +1,416 @@
+use crate::error::AppError;
+use crate::models::fsrs::{FsrsParameters, Rating};
+use crate::models::review_queue::DEFAULT_NEW_PER_DAY;
+use crate::models::workload::{
+    DailyLoad, ReviewCosts, SimulationRequest, WorkloadForecast, DEFAULT_SIMULATION_DAYS,
+    DEFAULT_SIMULATION_RUNS, MAX_SIMULATION_DAYS, MAX_SIMULATION_RUNS,
+};
+use crate::models::FsrsCard;
+use crate::services::{FsrsParameterService, FsrsService};
+use chrono::{DateTime, Local, Utc};
+use sqlx::SqlitePool;
+
+pub struct WorkloadSimulator;
+
+// Used while the review log has no timings of its own
+const DEFAULT_COSTS: ReviewCosts = ReviewCosts {
+    new_seconds: 600.0,
+    recall_seconds: 300.0,
+    lapse_seconds: 600.0,
+};
+
+/// Normalized simulation settings
+#[derive(Debug, Clone)]
+pub struct SimulationSettings {
+    pub days: u32,
+    pub runs: u32,
+    pub new_per_day: i64,
+    pub reviews_per_day: Option<i64>,
+    pub seed: u64,
+}
+
+/// A card as the simulator tracks it, in whole days relative to today
+#[derive(Debug, Clone)]
+struct SimCard {
+    memory: Option<(f64, f64)>, // (stability, difficulty); None while new
+    due_day: i64,
+    last_day: i64,
+}
+
+impl WorkloadSimulator {
+    /// Forecast the next days' reviews from the current cards, under the stored
+    /// parameters or the alternative settings in `req`
+    pub async fn simulate(
+        pool: &SqlitePool,
+        req: SimulationRequest,
+    ) -> Result<WorkloadForecast, AppError> {
+        let set = match &req.subject_id {
+            Some(subject_id) => {
+                FsrsParameterService::get_for_subject(pool, subject_id.clone()).await?
+            }
+            None => FsrsParameterService::get_global(pool).await?,
+        };
+        let mut params = set.to_parameters();
+        if let Some(desired_retention) = req.desired_retention {
+            if !(0.7..=0.99).contains(&desired_retention) {
+                return Err(AppError::validation(
+                    "desired_retention",
+                    format!(
+                        "Desired retention must be between 0.70 and 0.99, got {}",
+                        desired_retention
+                    ),
+                ));
+            }
+            params.desired_retention = desired_retention;
+        }
+
+        let settings = SimulationSettings {
+            days: req
+                .days
+                .unwrap_or(DEFAULT_SIMULATION_DAYS)
+                .clamp(1, MAX_SIMULATION_DAYS),
+            runs: req
+                .runs
+                .unwrap_or(DEFAULT_SIMULATION_RUNS)
+                .clamp(1, MAX_SIMULATION_RUNS),
+            new_per_day: req.new_per_day.unwrap_or(DEFAULT_NEW_PER_DAY).max(0),
+            reviews_per_day: req.reviews_per_day.map(|limit| limit.max(0)),
+            seed: req.seed.unwrap_or_else(|| Utc::now().timestamp() as u64),
+        };
+
+        let cards = sqlx::query_as::<_, FsrsCard>(
+            "SELECT c.id, c.problem_id, c.due, c.stability, c.difficulty, c.state, c.reps, c.lapses, c.elapsed_days, c.scheduled_days, c.step, c.is_leech, c.is_suspended, c.buried_until, c.last_review, c.created_at, c.updated_at
+             FROM fsrs_cards c
+             JOIN problems p ON c.problem_id = p.id
+             JOIN topics t ON p.topic_id = t.id
+             WHERE c.is_suspended = 0 AND (? IS NULL OR t.subject_id = ?)
+             ORDER BY c.due, c.created_at"
+        )
+        .bind(&req.subject_id)
+        .bind(&req.subject_id)
+        .fetch_all(pool)
+        .await?;
+
+        let costs = Self::review_costs(pool, req.subject_id).await?;
+        let today = FsrsService::local_day_start(Utc::now());
+
+        tokio::task::spawn_blocking(move || Self::run(&cards, &params, &settings, costs, today))
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// Average answer time by kind of review, from the review log
+    async fn review_costs(
+        pool: &SqlitePool,
+        subject_id: Option<String>,
+    ) -> Result<ReviewCosts, AppError> {
+        let (new_seconds, recall_seconds, lapse_seconds): (Option<f64>, Option<f64>, Option<f64>) =
+            sqlx::query_as(
+                "SELECT AVG(CASE WHEN r.state_before = 'new' THEN r.duration_seconds END),
+                        AVG(CASE WHEN r.state_before != 'new' AND r.rating > 1 THEN r.duration_seconds END),
+                        AVG(CASE WHEN r.state_before != 'new' AND r.rating = 1 THEN r.duration_seconds END)
+                 FROM fsrs_reviews r
+                 JOIN problems p ON r.problem_id = p.id
+                 JOIN topics t ON p.topic_id = t.id
+                 WHERE r.kind = 'review' AND r.duration_seconds > 0 AND (? IS NULL OR t.subject_id = ?)",
+            )
+            .bind(&subject_id)
+            .bind(&subject_id)
+            .fetch_one(pool)
+            .await?;
+
+        Ok(ReviewCosts {
+            new_seconds: new_seconds.unwrap_or(DEFAULT_COSTS.new_seconds),
+            recall_seconds: recall_seconds.unwrap_or(DEFAULT_COSTS.recall_seconds),
+            lapse_seconds: lapse_seconds.unwrap_or(DEFAULT_COSTS.lapse_seconds),
+        })
+    }
+
+    /// Monte-Carlo simulation, one review per due card per day. Each review is recalled
+    /// with the model's retrievability and answered Good, or Again on a miss; new cards are
+    /// introduced in order, answered Good. Sub-day (re)learning steps are folded into the
+    /// day's review.
+    pub fn run(
+        cards: &[FsrsCard],
+        params: &FsrsParameters,
+        settings: &SimulationSettings,
+        costs: ReviewCosts,
+        today: DateTime<Utc>,
+    ) -> WorkloadForecast {
+        let day_of = |timestamp: &str| {
+            DateTime::parse_from_rfc3339(timestamp).ok().map(|t| {
+                (t.with_timezone(&Utc) - today)
+                    .num_seconds()
+                    .div_euclid(86400)
+            })
+        };
+
+        let initial: Vec<SimCard> = cards
+            .iter()
+            .map(|card| SimCard {
+                memory: (card.state != "new").then_some((card.stability, card.difficulty)),
+                due_day: day_of(&card.due).unwrap_or(0).max(0),
+                last_day: card.last_review.as_deref().and_then(day_of).unwrap_or(0),
+            })
+            .collect();
+
+        let days = settings.days as usize;
+        let mut scheduled = vec![0i64; days];
+        for card in initial.iter().filter(|card| card.memory.is_some()) {
+            if let Some(count) = scheduled.get_mut(card.due_day as usize) {
+                *count += 1;
+            }
+        }
+
+        let mut reviews = vec![0.0; days];
+        let mut new_cards = vec![0.0; days];
+        let mut lapses = vec![0.0; days];
+        let mut seconds = vec![0.0; days];
+        let (mut reviewed, mut recalled) = (0u64, 0u64);
+        let mut rng = SplitMix64(settings.seed);
+
+        let schedule = |card: &mut SimCard, memory: (f64, f64), day: i64| {
+            card.memory = Some(memory);
+            card.last_day = day;
+            card.due_day = day + FsrsService::next_interval(params, memory.0) as i64;
+        };
+
+        for _ in 0..settings.runs {
+            let mut cards = initial.clone();
+
+            for day in 0..days {
+                let today = day as i64;
+                let mut reviews_left = settings.reviews_per_day.unwrap_or(i64::MAX);
+                let mut new_left = settings.new_per_day;
+
+                for card in cards.iter_mut() {
+                    match card.memory {
+                        Some((stability, difficulty))
+                            if card.due_day <= today && reviews_left > 0 =>
+                        {
+                            reviews_left -= 1;
+                            let elapsed = (today - card.last_day).max(0) as i32;
+                            let r = FsrsService::retrievability(elapsed as f64, stability);
+                            let success = rng.next_f64() < r;
+                            let rating = if success { Rating::Good } else { Rating::Again };
+
+                            reviews[day] += 1.0;
+                            reviewed += 1;
+                            if success {
+                                recalled += 1;
+                                seconds[day] += costs.recall_seconds;
+                            } else {
+                                lapses[day] += 1.0;
+                                seconds[day] += costs.lapse_seconds;
+                            }
+
+                            let memory = FsrsService::next_memory_state(
+                                &params.w,
+                                Some((stability, difficulty)),
+                                elapsed,
+                                rating,
+                            );
+                            schedule(card, memory, today);
+                        }
+                        None if new_left > 0 => {
+                            new_left -= 1;
+                            new_cards[day] += 1.0;
+                            seconds[day] += costs.new_seconds;
+
+                            let memory =
+                                FsrsService::next_memory_state(&params.w, None, 0, Rating::Good);
+                            schedule(card, memory, today);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        let runs = settings.runs as f64;
+        let days: Vec<DailyLoad> = (0..days)
+            .map(|day| DailyLoad {
+                day: day as u32,
+                date: (today + chrono::Duration::days(day as i64))
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d")
+                    .to_string(),
+                scheduled: scheduled[day],
+                expected_reviews: reviews[day] / runs,
+                expected_new: new_cards[day] / runs,
+                expected_lapses: lapses[day] / runs,
+                expected_minutes: seconds[day] / runs / 60.0,
+            })
+            .collect();
+
+        WorkloadForecast {
+            desired_retention: params.desired_retention,
+            new_per_day: settings.new_per_day,
+            reviews_per_day: settings.reviews_per_day,
+            runs: settings.runs,
+            costs,
+            total_reviews: days.iter().map(|d| d.expected_reviews).sum(),
+            total_minutes: days.iter().map(|d| d.expected_minutes).sum(),
+            expected_retention: if reviewed > 0 {
+                recalled as f64 / reviewed as f64
+            } else {
+                0.0
+            },
+            days,
+        }
+    }
+}
+
+/// Small seedable generator, so a simulation can be reproduced exactly
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_f64(&mut self) -> f64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-03-01T00:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn card(state: &str, stability: f64, due: &str, last_review: Option<&str>) -> FsrsCard {
+        FsrsCard {
+            id: uuid::Uuid::new_v4().to_string(),
+            problem_id: "problem".to_string(),
+            due: due.to_string(),
+            stability,
+            difficulty: 5.0,
+            state: state.to_string(),
+            reps: 0,
+            lapses: 0,
+            elapsed_days: 0,
+            scheduled_days: 0,
+            step: 0,
+            is_leech: false,
+            is_suspended: false,
+            buried_until: None,
+            last_review: last_review.map(str::to_string),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+        }
+    }
+
+    fn settings(days: u32, new_per_day: i64) -> SimulationSettings {
+        SimulationSettings {
+            days,
+            runs: 20,
+            new_per_day,
+            reviews_per_day: None,
+            seed: 7,
+        }
+    }
+
+    #[test]
+    fn test_scheduled_cards_and_new_card_limit() {
+        let mut cards: Vec<FsrsCard> = (0..10)
+            .map(|_| card("new", 1.0, "2024-03-01T00:00:00+00:00", None))
+            .collect();
+        // Very stable: reviewed once on its due day, then not again within the window
+        cards.push(card(
+            "review",
+            5000.0,
+            "2024-03-11T09:00:00+00:00",
+            Some("2024-01-01T00:00:00+00:00"),
+        ));
+        // Overdue cards land on today
+        cards.push(card(
+            "review",
+            5000.0,
+            "2024-02-20T09:00:00+00:00",
+            Some("2024-01-01T00:00:00+00:00"),
+        ));
+
+        // Retention at its floor keeps the new cards' intervals past the 12-day window
+        let params = FsrsParameters {
+            desired_retention: 0.7,
+            ..Default::default()
+        };
+        let forecast =
+            WorkloadSimulator::run(&cards, &params, &settings(12, 3), DEFAULT_COSTS, today());
+
+        let new: Vec<f64> = forecast.days.iter().map(|d| d.expected_new).collect();
+        assert_eq!(&new[..5], &[3.0, 3.0, 3.0, 1.0, 0.0]);
+
+        assert_eq!(forecast.days[0].scheduled, 1);
+        assert_eq!(forecast.days[10].scheduled, 1);
+        assert_eq!(forecast.days[0].expected_reviews, 1.0);
+        assert_eq!(forecast.days[10].expected_reviews, 1.0);
+        assert_eq!(forecast.total_reviews, 2.0);
+
+        // 3 new cards at 10 minutes plus one review at 5 on day 0
+        assert!((forecast.days[0].expected_minutes - 35.0).abs() < 1e-9);
+        assert_eq!(forecast.days[0].date.len(), 10);
+    }
+
+    #[test]
+    fn test_simulation_is_reproducible_and_follows_retention() {
+        let cards: Vec<FsrsCard> = (0..40)
+            .map(|_| card("new", 1.0, "2024-03-01T00:00:00+00:00", None))
+            .collect();
+        let simulate = |desired_retention| {
+            let params = FsrsParameters {
+                desired_retention,
+                ..Default::default()
+            };
+            WorkloadSimulator::run(&cards, &params, &settings(180, 10), DEFAULT_COSTS, today())
+        };
+
+        let first = simulate(0.9);
+        let again = simulate(0.9);
+        assert_eq!(first.total_reviews, again.total_reviews);
+        assert_eq!(first.expected_retention, again.expected_retention);
+        assert!((first.expected_retention - 0.9).abs() < 0.05);
+
+        // Asking to remember more costs more reviews
+        let strict = simulate(0.97);
+        let relaxed = simulate(0.8);
+        assert!(strict.total_reviews > first.total_reviews);
+        assert!(first.total_reviews > relaxed.total_reviews);
+        assert!(strict.expected_retention > relaxed.expected_retention);
+    }
+
+    #[test]
+    fn test_review_cap_postpones_the_backlog() {
+        let cards: Vec<FsrsCard> = (0..10)
+            .map(|_| {
+                card(
+                    "review",
+                    5000.0,
+                    "2024-02-28T00:00:00+00:00",
+                    Some("2024-01-01T00:00:00+00:00"),
+                )
+            })
+            .collect();
+        let settings = SimulationSettings {
+            reviews_per_day: Some(4),
+            ..settings(5, 0)
+        };
+        let forecast = WorkloadSimulator::run(
+            &cards,
+            &FsrsParameters::default(),
+            &settings,
+            DEFAULT_COSTS,
+            today(),
+        );
+
+        let reviews: Vec<f64> = forecast.days.iter().map(|d| d.expected_reviews).collect();
+        assert_eq!(reviews, vec![4.0, 4.0, 2.0, 0.0, 0.0]);
+    }
+}
@@ -1,9 +1,19 @@
 import React from "react";
 import { BaseCard } from "./BaseCard";
-import { useQuery } from "@tanstack/react-query";
-import { subjectAPI, topicAPI } from "@/lib/tauri-api";
+import { useQueries, useQuery } from "@tanstack/react-query";
+import { fsrsAPI, subjectAPI } from "@/lib/tauri-api";
 import { Crown, Zap } from "lucide-react";
-import type { Subject, Topic } from "@/types";
+import type { CardRetrievability, Subject } from "@/types";
+
+// A subject counts as mastered when its reviewed cards are, on average, at least
+// as likely to be recalled as the default desired retention
+const MASTERY_THRESHOLD = 0.9;
+
+const averageRetrievability = (cards: CardRetrievability[] = []) => {
+  const reviewed = cards.filter((c) => c.state !== "new");
+  if (reviewed.length === 0) return null;
+  return reviewed.reduce((sum, c) => sum + c.retrievability, 0) / reviewed.length;
+};
 
 export const MasteryCard: React.FC = () => {
   const { data: subjects } = useQuery({
@@ -11,12 +21,30 @@ export const MasteryCard: React.FC = () => {
     queryFn: () => subjectAPI.list(),
   });
 
-  const masteredSubjects = subjects?.filter((s: Subject) => Math.random() > 0.5) || [];
-  const masteredTopics = subjects
-    ?.flatMap((s: Subject) =>
-      s.id ? [s.id] : []
+  const retention = useQueries({
+    queries: (subjects || []).map((subject: Subject) => ({
+      queryKey: ["retrievability", subject.id],
+      queryFn: () => fsrsAPI.listRetrievability(subject.id),
+    })),
+  });
+
+  const subjectStrength = (subjects || [])
+    .map((subject: Subject, idx: number) => ({
+      subject,
+      strength: averageRetrievability(retention[idx]?.data),
+    }))
+    .filter(
+      (s): s is { subject: Subject; strength: number } => s.strength !== null
     )
-    .slice(0, 3) || [];
+    .sort((a, b) => b.strength - a.strength);
+
+  const masteredSubjects = subjectStrength.filter(
+    (s) => s.strength >= MASTERY_THRESHOLD
+  );
+  const weakestSubjects = subjectStrength
+    .filter((s) => s.strength < MASTERY_THRESHOLD)
+    .slice(-3)
+    .reverse();
 
   return (
     <BaseCard id="mastery" title="Mastery Progress" className="col-span-1">
@@ -29,13 +57,14 @@ export const MasteryCard: React.FC = () => {
           </div>
           <div className="space-y-1">
             {masteredSubjects.length > 0 ? (
-              masteredSubjects.map((subject: Subject) => (
+              masteredSubjects.map(({ subject, strength }) => (
                 <div
                   key={subject.id}
                   className="px-3 py-2 bg-gradient-to-r from-orange-500/20 to-red-500/20 border border-orange-500/30 rounded text-sm text-orange-300 flex items-center gap-2"
                 >
                   <div className="w-2 h-2 rounded-full bg-orange-400" />
                   {subject.name}
+                  <span className="ml-auto text-xs">{Math.round(strength * 100)}%</span>
                 </div>
               ))
             ) : (
@@ -44,25 +73,26 @@ export const MasteryCard: React.FC = () => {
           </div>
         </div>
 
-        {/* Mastered Topics */}
+        {/* Fading Subjects */}
         <div>
           <div className="flex items-center gap-2 mb-2">
             <Zap size={16} className="text-yellow-400" />
-            <h4 className="text-xs font-semibold text-slate-300">Mastered Topics</h4>
+            <h4 className="text-xs font-semibold text-slate-300">Fading Memory</h4>
           </div>
           <div className="space-y-1">
-            {masteredTopics.length > 0 ? (
-              masteredTopics.map((id: string, idx: number) => (
+            {weakestSubjects.length > 0 ? (
+              weakestSubjects.map(({ subject, strength }) => (
                 <div
-                  key={idx}
+                  key={subject.id}
                   className="px-3 py-2 bg-yellow-500/20 border border-yellow-500/30 rounded text-sm text-yellow-300 flex items-center gap-2"
                 >
                   <div className="w-2 h-2 rounded-full bg-yellow-400" />
-                  Topic {idx + 1}
+                  {subject.name}
+                  <span className="ml-auto text-xs">{Math.round(strength * 100)}%</span>
                 </div>
               ))
             ) : (
-              <p className="text-xs text-slate-500">No mastered topics yet</p>
+              <p className="text-xs text-slate-500">Nothing is fading right now</p>
             )}
           </div>
         </div>
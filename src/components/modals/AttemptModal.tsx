@@ -11,6 +11,7 @@ import { useSubmitAttempt } from "@/hooks/useProblems";
 import { useErrorTypes } from "@/hooks";
 import { Button } from "@/components/ui/button";
 import { Textarea } from "@/components/ui/textarea";
+import { RATINGS } from "@/lib/constants";
 import { Plus, Trash2 } from "lucide-react";
 import toast from "react-hot-toast";
 
@@ -24,7 +25,7 @@ export const AttemptModal: React.FC = () => {
   const [selectedErrors, setSelectedErrors] = useState<
     Array<{ error_type_id: number; description: string }>
   >([]);
-  const [quality, setQuality] = useState(3);
+  const [rating, setRating] = useState(3);
 
   const handleSubmit = () => {
     if (!isSolved && selectedErrors.length === 0) {
@@ -38,7 +39,7 @@ export const AttemptModal: React.FC = () => {
         isSolved,
         commentary,
         errors: selectedErrors,
-        quality,
+        rating,
         timeSpent: 300,
       },
       {
@@ -47,7 +48,7 @@ export const AttemptModal: React.FC = () => {
           setIsSolved(true);
           setCommentary("");
           setSelectedErrors([]);
-          setQuality(3);
+          setRating(3);
         },
       }
     );
@@ -81,16 +82,16 @@ export const AttemptModal: React.FC = () => {
                 Confidence
               </label>
               <div className="flex gap-2">
-                {[1, 2, 3, 4, 5].map((q) => (
+                {RATINGS.map(({ value, label }) => (
                   <button
-                    key={q}
-                    onClick={() => setQuality(q)}
-                    className={`flex-1 py-2 rounded border transition-colors ${quality === q
+                    key={value}
+                    onClick={() => setRating(value)}
+                    className={`flex-1 py-2 rounded border transition-colors ${rating === value
                       ? "bg-blue-500/20 border-blue-500/50 text-blue-300"
                       : "bg-slate-700/50 border-slate-600/50 text-slate-400"
                       }`}
                   >
-                    {q}
+                    {label}
                   </button>
                 ))}
               </div>
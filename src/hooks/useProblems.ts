@@ -1,6 +1,7 @@
 import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
-import { problemAPI, attemptAPI, fsrsAPI, errorAPI } from "@/lib/tauri-api";
+import { problemAPI, attemptAPI } from "@/lib/tauri-api";
 import type { Problem, Attempt } from "@/types";
+import { errorMessage } from "@/lib/utils";
 import toast from "react-hot-toast";
 
 export const useProblems = (topicId?: string) => {
@@ -38,8 +39,8 @@ export const useCreateProblem = () => {
       queryClient.invalidateQueries({ queryKey: ["problems"] });
       toast.success("Problem created!");
     },
-    onError: (error: any) => {
-      toast.error(error.message || "Failed to create problem");
+    onError: (error) => {
+      toast.error(errorMessage(error, "Failed to create problem"));
     },
   });
 };
@@ -52,34 +53,26 @@ export const useSubmitAttempt = () => {
       isSolved,
       commentary,
       errors,
-      quality,
+      rating,
       timeSpent,
     }: {
       problemId: string;
       isSolved: boolean;
       commentary?: string;
       errors?: Array<{ error_type_id: number; description?: string }>;
-      quality?: number;
+      rating?: number;
       timeSpent?: number;
     }) => {
-      // Create attempt
-      const attempt = await attemptAPI.create(problemId, isSolved, commentary);
-
-      // Log errors if not solved
-      if (!isSolved && errors) {
-        for (const error of errors) {
-          await errorAPI.log(
-            attempt.id,
-            error.error_type_id,
-            error.description
-          );
-        }
-      }
-
-      // Process FSRS review
-      if (quality !== undefined && timeSpent !== undefined) {
-        await fsrsAPI.processReview(problemId, isSolved, quality, timeSpent);
-      }
+      const reviewed = rating !== undefined && timeSpent !== undefined;
+      const { attempt } = await attemptAPI.submit({
+        problem_id: problemId,
+        is_solved: isSolved,
+        commentary,
+        // Errors are only logged on failed attempts
+        errors: !isSolved && errors ? errors : [],
+        rating: reviewed ? rating : undefined,
+        time_spent_seconds: reviewed ? timeSpent : undefined,
+      });
 
       return attempt;
     },
@@ -88,8 +81,8 @@ export const useSubmitAttempt = () => {
       queryClient.invalidateQueries({ queryKey: ["fsrs-stats"] });
       toast.success("Attempt recorded!");
     },
-    onError: (error: any) => {
-      toast.error(error.message || "Failed to submit attempt");
+    onError: (error) => {
+      toast.error(errorMessage(error, "Failed to submit attempt"));
     },
   });
 };
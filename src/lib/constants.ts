@@ -20,6 +20,13 @@ export const ERROR_TYPES = [
   { id: 10, name: "Over Time Limit", multiplier: 0.7 },
 ];
 
+export const RATINGS = [
+  { value: 1, label: "Again" },
+  { value: 2, label: "Hard" },
+  { value: 3, label: "Good" },
+  { value: 4, label: "Easy" },
+];
+
 export const GRID_BREAKPOINTS = {
   lg: 1200,
   md: 996,
@@ -4,11 +4,36 @@ import type {
   Topic,
   Theory,
   Problem,
+  ProblemMastery,
+  ProblemTag,
+  Setting,
   Attempt,
   ErrorType,
   AttemptError,
+  SubmittedAttempt,
   FsrsCard,
   FsrsStats,
+  IntegrityReport,
+  PhaseAdvanceResult,
+  PhaseTimeSummary,
+  StudyPhaseEntry,
+  StudySession,
+  StudySessionData,
+  StudySessionSummary,
+  Timebox,
+  TimeBudget,
+  DifficultyTimeBudget,
+  TheoryWithPhase,
+  CardRetrievability,
+  Leech,
+  QueueOptions,
+  ReviewQueue,
+  RatingPreview,
+  RescheduleSummary,
+  ReviewLog,
+  ReviewLogFilter,
+  SimulationRequest,
+  WorkloadForecast,
 } from "../types";
 
 // ============ SUBJECTS ============
@@ -98,6 +123,18 @@ export const problemAPI = {
   markSolved: (id: string) => invoke<Problem>("mark_problem_solved", { id }),
   getWithDetails: (id: string) =>
     invoke<any>("get_problem_with_details", { id }),
+  search: (query: string, limit?: number) =>
+    invoke<Problem[]>("search_problems", { query, limit }),
+  addTag: (problem_id: string, tag_name: string) =>
+    invoke<ProblemTag[]>("add_problem_tag", { problem_id, tag_name }),
+  removeTag: (problem_id: string, tag_name: string) =>
+    invoke<ProblemTag[]>("remove_problem_tag", { problem_id, tag_name }),
+  listTags: (problem_id: string) =>
+    invoke<ProblemTag[]>("list_problem_tags", { problem_id }),
+  listByTag: (tag_name: string) =>
+    invoke<Problem[]>("list_problems_by_tag", { tag_name }),
+  getMastery: (problem_id: string) =>
+    invoke<ProblemMastery | null>("get_problem_mastery", { problem_id }),
 };
 
 // ============ ATTEMPTS ============
@@ -106,6 +143,15 @@ export const attemptAPI = {
     invoke<Attempt>("create_attempt", {
       req: { problem_id, is_solved, commentary },
     }),
+  // Attempt, errors and review are saved together or not at all
+  submit: (req: {
+    problem_id: string;
+    is_solved: boolean;
+    commentary?: string;
+    errors?: Array<{ error_type_id: number; description?: string }>;
+    rating?: number;
+    time_spent_seconds?: number;
+  }) => invoke<SubmittedAttempt>("submit_attempt", { req }),
   get: (id: string) => invoke<Attempt>("get_attempt", { id }),
   listByProblem: (problem_id: string) =>
     invoke<Attempt[]>("list_attempts_by_problem", { problem_id }),
@@ -136,22 +182,149 @@ export const fsrsAPI = {
   processReview: (
     problem_id: string,
     attempt_is_solved: boolean,
-    quality: number,
-    time_spent_seconds: number
+    rating: number,
+    time_spent_seconds: number,
+    attempt_id?: string
   ) =>
     invoke<any>("process_review", {
       req: {
         problem_id,
         attempt_is_solved,
-        quality,
+        rating,
         time_spent_seconds,
+        attempt_id,
       },
     }),
+  // Next interval per rating, before answering
+  previewReview: (card_id: string) =>
+    invoke<RatingPreview[]>("preview_review", { card_id }),
   getDueCards: () => invoke<FsrsCard[]>("get_due_cards", {}),
+  // Oldest first
+  getReviewHistory: (card_id: string) =>
+    invoke<ReviewLog[]>("get_card_review_history", { card_id }),
+  listReviewLogs: (filter?: ReviewLogFilter) =>
+    invoke<ReviewLog[]>("list_review_logs", { filter }),
+  getReviewQueue: (options?: QueueOptions) =>
+    invoke<ReviewQueue>("get_review_queue", { options }),
+  simulateWorkload: (req?: SimulationRequest) =>
+    invoke<WorkloadForecast>("simulate_workload", { req }),
+  previewReschedule: (subject_id?: string) =>
+    invoke<RescheduleSummary>("preview_reschedule", { subject_id }),
+  reschedule: (subject_id?: string) =>
+    invoke<RescheduleSummary>("reschedule_cards", { subject_id }),
   getStats: () => invoke<FsrsStats>("get_fsrs_stats", {}),
   getCard: (card_id: string) => invoke<FsrsCard>("get_fsrs_card", { card_id }),
   getCardByProblem: (problem_id: string) =>
     invoke<FsrsCard>("get_fsrs_card_by_problem", { problem_id }),
   getCardsByState: (state: string) =>
     invoke<FsrsCard[]>("get_cards_by_state", { state }),
+  listRetrievability: (subject_id?: string, at?: string) =>
+    invoke<CardRetrievability[]>("list_card_retrievability", { subject_id, at }),
+  suspend: (card_id: string) => invoke<FsrsCard>("suspend_card", { card_id }),
+  unsuspend: (card_id: string) =>
+    invoke<FsrsCard>("unsuspend_card", { card_id }),
+  bury: (card_id: string) => invoke<FsrsCard>("bury_card", { card_id }),
+  setDue: (card_id: string, due: string) =>
+    invoke<FsrsCard>("set_card_due", { card_id, due }),
+  reset: (card_id: string) => invoke<FsrsCard>("reset_card", { card_id }),
+  forget: (card_id: string) => invoke<FsrsCard>("forget_card", { card_id }),
+  listLeeches: (subject_id?: string) =>
+    invoke<Leech[]>("list_leeches", { subject_id }),
+  resetLeech: (card_id: string) => invoke<FsrsCard>("reset_leech", { card_id }),
+  rewriteLeech: (
+    card_id: string,
+    req: {
+      title?: string;
+      description?: string;
+      image_url?: string;
+      difficulty?: number;
+    }
+  ) => invoke<Leech>("rewrite_leech", { card_id, req }),
+};
+
+// ============ SETTINGS ============
+export const settingsAPI = {
+  get: (key: string) => invoke<Setting | null>("get_setting", { key }),
+  list: () => invoke<Setting[]>("list_settings", {}),
+  set: (key: string, value: string) =>
+    invoke<Setting>("set_setting", { key, value }),
+  delete: (key: string) => invoke<void>("delete_setting", { key }),
+};
+
+// ============ STUDY PHASES ============
+export const studyPhaseAPI = {
+  start: (problem_id: string) =>
+    invoke<StudySessionData>("start_study_phase", { problem_id }),
+  getSession: (problem_id: string) =>
+    invoke<StudySessionData | null>("get_study_session", { problem_id }),
+  advance: (problem_id: string, time_spent_seconds: number, notes?: string) =>
+    invoke<PhaseAdvanceResult>("advance_study_phase", {
+      req: { problem_id, time_spent_seconds, notes },
+    }),
+  updateTime: (problem_id: string, elapsed_seconds: number) =>
+    invoke<StudySessionData>("update_study_phase_time", {
+      req: { problem_id, elapsed_seconds },
+    }),
+  complete: (problem_id: string, time_spent_seconds: number, notes?: string) =>
+    invoke<PhaseAdvanceResult>("complete_study_phase", {
+      problem_id,
+      time_spent_seconds,
+      notes,
+    }),
+  reset: (problem_id: string) =>
+    invoke<StudySessionData>("reset_study_phase", { problem_id }),
+  listEntries: (problem_id: string, phase?: number) =>
+    invoke<StudyPhaseEntry[]>("list_study_phase_entries", { problem_id, phase }),
+  timesByProblem: (problem_id: string) =>
+    invoke<PhaseTimeSummary[]>("get_phase_times_by_problem", { problem_id }),
+  timesByTopic: (topic_id: string) =>
+    invoke<PhaseTimeSummary[]>("get_phase_times_by_topic", { topic_id }),
+  // Theory pages of the current phase only
+  getTheory: (problem_id: string) =>
+    invoke<TheoryWithPhase[]>("get_study_phase_theory", { problem_id }),
+};
+
+// ============ STUDY SESSIONS ============
+export const studySessionAPI = {
+  // Pass a timebox for a pomodoro session
+  start: (timebox?: Timebox) =>
+    invoke<StudySession>("start_study_session", { timebox }),
+  // Send every ~30s while the timer runs; longer gaps count as idle
+  heartbeat: (id: string) =>
+    invoke<StudySession>("study_session_heartbeat", { id }),
+  pause: (id: string) => invoke<StudySession>("pause_study_session", { id }),
+  resume: (id: string) => invoke<StudySession>("resume_study_session", { id }),
+  stop: (id: string) =>
+    invoke<StudySessionSummary>("stop_study_session", { id }),
+  // The open session, e.g. to pick it back up after a restart
+  getCurrent: () =>
+    invoke<StudySessionSummary | null>("get_current_study_session", {}),
+  getSummary: (id: string) =>
+    invoke<StudySessionSummary>("get_study_session_summary", { id }),
+  list: (limit?: number) =>
+    invoke<StudySessionSummary[]>("list_study_sessions", { limit }),
+};
+
+// ============ TIME BUDGETS ============
+export const timeBudgetAPI = {
+  listByDifficulty: () =>
+    invoke<DifficultyTimeBudget[]>("get_difficulty_time_budgets", {}),
+  setForDifficulty: (difficulty: number, budget_seconds: number) =>
+    invoke<DifficultyTimeBudget>("set_difficulty_time_budget", {
+      difficulty,
+      budget_seconds,
+    }),
+  getForProblem: (problem_id: string) =>
+    invoke<TimeBudget | null>("get_problem_time_budget", { problem_id }),
+  // null falls back to the difficulty's budget
+  setForProblem: (problem_id: string, budget_seconds: number | null) =>
+    invoke<TimeBudget | null>("set_problem_time_budget", {
+      problem_id,
+      budget_seconds,
+    }),
+};
+
+// ============ SYSTEM ============
+export const systemAPI = {
+  integrityCheck: () => invoke<IntegrityReport>("integrity_check", {}),
 };
@@ -1,6 +1,23 @@
 import { clsx, type ClassValue } from "clsx"
 import { twMerge } from "tailwind-merge"
+import type { AppError } from "@/types"
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
 }
+
+// Text for a rejected Tauri command, read off the AppError's code
+export function errorMessage(error: unknown, fallback: string) {
+  const e = error as Partial<AppError> | undefined
+  switch (e?.code) {
+    case "not_found":
+      return `${e.entity} ${e.id} not found`
+    case "conflict":
+    case "validation":
+    case "database":
+    case "io":
+      return e.message ?? fallback
+    default:
+      return fallback
+  }
+}
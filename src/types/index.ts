@@ -18,7 +18,7 @@ export interface Topic {
 export interface Theory {
   id: string;
   topic_id: string;
-  phase_number: number;
+  phase_number: number; // 1-6, labelled 1.1, 1.2, 2.1, 2.2, 3, 4
   title: string;
   content?: string;
   created_at: string;
@@ -39,11 +39,33 @@ export interface Problem {
   updated_at: string;
 }
 
+export interface ProblemTag {
+  id: string;
+  problem_id: string;
+  tag_name: string;
+  created_at: string;
+}
+
+export interface ProblemMastery {
+  problem_id: string;
+  solved: boolean;
+  mastery_percent: number;
+  attempt_count: number;
+  updated_at: string;
+}
+
+export interface Setting {
+  key: string;
+  value: string;
+  updated_at: string;
+}
+
 export interface Attempt {
   id: string;
   problem_id: string;
   is_solved: boolean;
   commentary?: string;
+  time_spent_seconds: number;
   created_at: string;
   updated_at: string;
 }
@@ -66,6 +88,12 @@ export interface AttemptError {
   updated_at: string;
 }
 
+export interface SubmittedAttempt {
+  attempt: Attempt;
+  errors: AttemptError[];
+  review: any | null;
+}
+
 export interface FsrsCard {
   id: string;
   problem_id: string;
@@ -77,10 +105,163 @@ export interface FsrsCard {
   lapses: number;
   elapsed_days: number;
   scheduled_days: number;
+  step: number;
+  is_leech: boolean;
+  is_suspended: boolean;
+  buried_until: string | null;
+  last_review: string | null;
   created_at: string;
   updated_at: string;
 }
 
+export interface Leech {
+  card: FsrsCard;
+  problem: Problem;
+  errors: AttemptError[];
+}
+
+export type Rating = "again" | "hard" | "good" | "easy";
+
+// What answering a card with `rating` would do; nothing is saved
+export interface RatingPreview {
+  rating: Rating;
+  state: FsrsCard["state"];
+  step: number;
+  stability: number;
+  difficulty: number;
+  elapsed_days: number;
+  scheduled_days: number; // 0 while in a sub-day (re)learning step
+  scheduled_minutes: number;
+  due: string;
+}
+
+export type ReviewKind =
+  | "review"
+  | "suspend"
+  | "unsuspend"
+  | "bury"
+  | "set_due"
+  | "reset"
+  | "forget"
+  | "reschedule";
+
+export interface ReviewLog {
+  id: string;
+  card_id: string;
+  problem_id: string;
+  rating: number; // 1-4, 0 for manual actions
+  kind: ReviewKind;
+  state_before: string;
+  state_after: string;
+  stability_before: number;
+  stability_after: number;
+  difficulty_before: number;
+  difficulty_after: number;
+  stability_penalty: number; // Divisor the error policy applied to stability, 1 for none
+  elapsed_days: number;
+  scheduled_days: number;
+  duration_seconds: number;
+  reviewed_at: string;
+}
+
+export interface ReviewLogFilter {
+  subject_id?: string;
+  topic_id?: string;
+  problem_id?: string;
+  rating?: number;
+  from?: string; // RFC 3339, inclusive
+  to?: string; // RFC 3339, exclusive
+  limit?: number;
+  offset?: number;
+}
+
+export interface CardRetrievability {
+  card_id: string;
+  problem_id: string;
+  state: FsrsCard["state"];
+  stability: number;
+  at: string;
+  retrievability: number;
+}
+
+export type QueueOrder = "due" | "retrievability" | "difficulty" | "random";
+
+export interface QueueOptions {
+  subject_id?: string;
+  new_per_day?: number;
+  reviews_per_day?: number;
+  order?: QueueOrder;
+  interleave_topics?: boolean;
+}
+
+export interface QueuedCard extends FsrsCard {
+  topic_id: string;
+  retrievability: number;
+}
+
+export interface ReviewQueue {
+  cards: QueuedCard[];
+  studied_today: { new_cards: number; reviews: number; learning: number };
+  new_remaining: number;
+  reviews_remaining: number;
+}
+
+export interface RescheduledCard {
+  card_id: string;
+  problem_id: string;
+  replayed: boolean;
+  stability_before: number;
+  stability_after: number;
+  difficulty_before: number;
+  difficulty_after: number;
+  interval_before: number;
+  interval_after: number;
+  due_before: string;
+  due_after: string;
+}
+
+export interface RescheduleSummary {
+  applied: boolean;
+  total_cards: number;
+  replayed: number;
+  moved: number;
+  earlier: number;
+  later: number;
+  cards: RescheduledCard[];
+}
+
+export interface SimulationRequest {
+  subject_id?: string;
+  days?: number;
+  desired_retention?: number;
+  new_per_day?: number;
+  reviews_per_day?: number;
+  runs?: number;
+  seed?: number;
+}
+
+export interface DailyLoad {
+  day: number;
+  date: string;
+  scheduled: number;
+  expected_reviews: number;
+  expected_new: number;
+  expected_lapses: number;
+  expected_minutes: number;
+}
+
+export interface WorkloadForecast {
+  desired_retention: number;
+  new_per_day: number;
+  reviews_per_day: number | null;
+  runs: number;
+  costs: { new_seconds: number; recall_seconds: number; lapse_seconds: number };
+  days: DailyLoad[];
+  total_reviews: number;
+  total_minutes: number;
+  expected_retention: number;
+}
+
 export interface FsrsStats {
   total_cards: number;
   new_count: number;
@@ -106,3 +287,117 @@ export interface TimerPhase {
   label: string;
   duration: number; // in seconds
 }
+
+export type StudyPhase = "decode" | "encode" | "recall" | "reflect";
+
+export interface TheoryWithPhase {
+  theory: Theory;
+  phase_label: string;
+  study_phase: StudyPhase;
+}
+
+export interface StudySessionData {
+  problem_id: string;
+  current_phase: StudyPhase;
+  current_step: number;
+  phase_description: string;
+  time_in_phase: number; // seconds
+  total_time: number; // seconds
+  total_completion: number; // percent
+  is_completed: boolean;
+}
+
+export interface StudyPhaseEntry {
+  id: string;
+  problem_id: string;
+  phase: number; // 1-4
+  time_spent_seconds: number;
+  notes: string | null;
+  created_at: string;
+}
+
+export interface PhaseTimeSummary {
+  phase: StudyPhase;
+  total_seconds: number;
+  passes: number;
+  share: number; // 0-1
+}
+
+export interface PhaseAdvanceResult {
+  problem_id: string;
+  from_phase: number;
+  to_phase: number;
+  is_completed: boolean;
+}
+
+export type StudySessionStatus = "running" | "paused" | "stopped";
+
+// Pomodoro work/break lengths
+export interface Timebox {
+  work_seconds: number;
+  break_seconds: number;
+}
+
+export interface TimeboxInterval {
+  kind: "work" | "break";
+  cycle: number; // 1-based
+  remaining_seconds: number;
+}
+
+export interface StudySession {
+  id: string;
+  status: StudySessionStatus;
+  started_at: string;
+  ended_at: string | null;
+  last_heartbeat_at: string;
+  active_seconds: number;
+  idle_seconds: number; // Heartbeat gaps over 5 minutes
+  break_seconds: number;
+  work_interval_seconds: number | null; // null = free session
+  break_interval_seconds: number | null;
+  created_at: string;
+  updated_at: string;
+  interval: TimeboxInterval | null; // As of the last heartbeat
+}
+
+export interface StudySessionSummary extends StudySession {
+  attempt_count: number;
+  solved_count: number;
+  over_time_count: number;
+  review_count: number;
+  review_seconds: number;
+  problem_count: number;
+}
+
+export interface ForeignKeyViolation {
+  table: string;
+  rowid: number | null;
+  parent: string;
+  fkid: number;
+}
+
+export interface IntegrityReport {
+  ok: boolean;
+  integrity_errors: string[];
+  foreign_key_violations: ForeignKeyViolation[];
+}
+
+// Rejection value of every backend command; branch on `code`
+export type AppError =
+  | { code: "not_found"; entity: string; id: string }
+  | { code: "conflict"; entity: string; message: string }
+  | { code: "validation"; field: string; message: string }
+  | { code: "database"; message: string }
+  | { code: "io"; message: string };
+
+export interface DifficultyTimeBudget {
+  difficulty: number; // 1-5
+  budget_seconds: number;
+  updated_at: string;
+}
+
+export interface TimeBudget {
+  problem_id: string;
+  budget_seconds: number;
+  source: "problem" | "difficulty";
+}